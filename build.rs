@@ -1,4 +1,7 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("proto/ch_ewf.proto")?;
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("ch_ewf_descriptor.bin"))
+        .compile(&["proto/ch_ewf.proto"], &["proto"])?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1 @@
+ALTER TABLE submissions ADD COLUMN form_type VARCHAR;
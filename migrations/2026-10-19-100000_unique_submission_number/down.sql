@@ -0,0 +1 @@
+DROP INDEX submissions_ch_submission_id;
@@ -0,0 +1,16 @@
+DROP INDEX submissions_ch_submission_id;
+-- Submission numbers used to only be kept unique over 30 days, so older submissions sharing a number with a later
+-- one are given a suffix the generator never produces. Pending submissions keep the number where they can, as
+-- theirs is the status the gateway still reports; any others are failed so the renamed number is never polled.
+UPDATE submissions SET
+    ch_submission_id = submissions.ch_submission_id || '-' || duplicates.n,
+    status = CASE WHEN submissions.status = 'pending' THEN 'internal_failure' ELSE submissions.status END
+FROM (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY ch_submission_id ORDER BY status = 'pending' DESC, received_timestamp DESC, id
+    ) - 1 AS n
+    FROM submissions
+) AS duplicates
+WHERE duplicates.id = submissions.id AND duplicates.n > 0;
+
+CREATE UNIQUE INDEX submissions_ch_submission_id ON submissions (ch_submission_id);
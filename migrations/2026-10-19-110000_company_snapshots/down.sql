@@ -0,0 +1,7 @@
+DROP TABLE company_snapshot_capital;
+DROP TABLE company_snapshot_registered_offices;
+DROP TABLE company_snapshot_sic_codes;
+DROP TABLE company_snapshot_shareholdings;
+DROP TABLE company_snapshot_pscs;
+DROP TABLE company_snapshot_officers;
+DROP TABLE company_snapshots;
@@ -0,0 +1,71 @@
+CREATE TABLE company_snapshots (
+    id UUID PRIMARY KEY,
+    company_number VARCHAR NOT NULL,
+    company_name VARCHAR NOT NULL,
+    transaction_id VARCHAR NOT NULL,
+    made_up_date DATE,
+    snapshot_timestamp TIMESTAMP NOT NULL,
+    data BYTEA NOT NULL
+);
+
+CREATE INDEX company_snapshots_company_number ON company_snapshots (company_number, snapshot_timestamp);
+
+CREATE TABLE company_snapshot_officers (
+    id UUID PRIMARY KEY,
+    snapshot_id UUID REFERENCES company_snapshots(id) ON DELETE CASCADE NOT NULL,
+    role VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    corporate BOOLEAN NOT NULL,
+    date_of_birth DATE,
+    appointment_date DATE,
+    resignation_date DATE,
+    designated BOOLEAN NOT NULL
+);
+
+CREATE TABLE company_snapshot_pscs (
+    id UUID PRIMARY KEY,
+    snapshot_id UUID REFERENCES company_snapshots(id) ON DELETE CASCADE NOT NULL,
+    kind VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    notification_date DATE,
+    cessation_date DATE,
+    nature_of_controls VARCHAR[] NOT NULL
+);
+
+CREATE TABLE company_snapshot_shareholdings (
+    id UUID PRIMARY KEY,
+    snapshot_id UUID REFERENCES company_snapshots(id) ON DELETE CASCADE NOT NULL,
+    share_class VARCHAR NOT NULL,
+    shareholders VARCHAR NOT NULL,
+    number_held DOUBLE PRECISION NOT NULL
+);
+
+CREATE TABLE company_snapshot_sic_codes (
+    snapshot_id UUID REFERENCES company_snapshots(id) ON DELETE CASCADE NOT NULL,
+    sic_code VARCHAR NOT NULL,
+    PRIMARY KEY (snapshot_id, sic_code)
+);
+
+CREATE TABLE company_snapshot_registered_offices (
+    snapshot_id UUID PRIMARY KEY REFERENCES company_snapshots(id) ON DELETE CASCADE,
+    premise VARCHAR NOT NULL,
+    street VARCHAR NOT NULL,
+    thoroughfare VARCHAR NOT NULL,
+    post_town VARCHAR NOT NULL,
+    county VARCHAR NOT NULL,
+    country VARCHAR NOT NULL,
+    postcode VARCHAR NOT NULL,
+    care_of_name VARCHAR NOT NULL,
+    po_box VARCHAR NOT NULL
+);
+
+CREATE TABLE company_snapshot_capital (
+    id UUID PRIMARY KEY,
+    snapshot_id UUID REFERENCES company_snapshots(id) ON DELETE CASCADE NOT NULL,
+    currency VARCHAR NOT NULL,
+    share_class VARCHAR NOT NULL,
+    prescribed_particulars VARCHAR NOT NULL,
+    num_shares DOUBLE PRECISION NOT NULL,
+    aggregate_nominal_value DOUBLE PRECISION NOT NULL,
+    total_amount_unpaid DOUBLE PRECISION NOT NULL
+);
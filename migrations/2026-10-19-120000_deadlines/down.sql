@@ -0,0 +1,2 @@
+DROP TABLE accounting_reference_date_changes;
+DROP TABLE tracked_companies;
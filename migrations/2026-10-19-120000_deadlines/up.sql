@@ -0,0 +1,21 @@
+CREATE TABLE tracked_companies (
+    company_number VARCHAR PRIMARY KEY,
+    company_name VARCHAR NOT NULL,
+    public_company BOOLEAN NOT NULL DEFAULT FALSE,
+    incorporation_date DATE NOT NULL,
+    accounting_period_end DATE NOT NULL,
+    last_accounts_made_up_to DATE,
+    confirmation_statement_made_up_to DATE,
+    confirmation_statement_due DATE
+);
+
+CREATE TABLE accounting_reference_date_changes (
+    submission_id UUID PRIMARY KEY REFERENCES submissions(id) ON DELETE CASCADE,
+    company_number VARCHAR NOT NULL,
+    current_period_end DATE NOT NULL,
+    new_period_end DATE NOT NULL,
+    shortened BOOLEAN NOT NULL,
+    notice_date DATE NOT NULL
+);
+
+CREATE INDEX accounting_reference_date_changes_company_number ON accounting_reference_date_changes (company_number);
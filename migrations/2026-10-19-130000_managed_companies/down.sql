@@ -0,0 +1,2 @@
+DROP TABLE managed_companies;
+DROP TYPE company_type;
@@ -0,0 +1,16 @@
+CREATE TYPE company_type AS ENUM (
+    'england_and_wales', 'scotland', 'northern_ireland', 'ireland',
+    'llp_england_and_wales', 'llp_scotland', 'llp_northern_ireland'
+);
+
+CREATE TABLE managed_companies (
+    id UUID PRIMARY KEY,
+    company_number INTEGER NOT NULL,
+    company_type company_type NOT NULL,
+    company_name VARCHAR NOT NULL,
+    authentication_code BYTEA NOT NULL,
+    tags VARCHAR[] NOT NULL DEFAULT '{}'
+);
+
+CREATE UNIQUE INDEX managed_companies_company_number ON managed_companies (company_type, company_number);
+CREATE INDEX managed_companies_tags ON managed_companies USING GIN (tags);
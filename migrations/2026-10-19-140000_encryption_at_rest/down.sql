@@ -0,0 +1,3 @@
+ALTER TABLE documents DROP COLUMN encrypted;
+ALTER TABLE company_snapshots DROP COLUMN data_encrypted;
+ALTER TABLE submissions DROP COLUMN authentication_code_encrypted;
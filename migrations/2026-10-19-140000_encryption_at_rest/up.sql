@@ -0,0 +1,7 @@
+ALTER TABLE submissions ADD COLUMN authentication_code_encrypted BYTEA;
+ALTER TABLE company_snapshots ADD COLUMN data_encrypted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE documents ADD COLUMN encrypted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Snapshot officer rows are only used for diffing, the month and year of birth is enough
+UPDATE company_snapshot_officers SET date_of_birth = date_trunc('month', date_of_birth)::DATE
+    WHERE date_of_birth IS NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE documents ADD COLUMN content_hash VARCHAR;
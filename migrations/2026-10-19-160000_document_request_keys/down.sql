@@ -0,0 +1,3 @@
+DROP INDEX documents_company_number;
+
+ALTER TABLE submissions DROP COLUMN document_request_key;
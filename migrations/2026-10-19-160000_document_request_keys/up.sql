@@ -0,0 +1,3 @@
+ALTER TABLE submissions ADD COLUMN document_request_key VARCHAR;
+
+CREATE INDEX documents_company_number ON documents (company_number, document_date);
@@ -0,0 +1,2 @@
+DROP TABLE register_entries;
+DROP TYPE register_type;
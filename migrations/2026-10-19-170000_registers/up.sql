@@ -0,0 +1,17 @@
+CREATE TYPE register_type AS ENUM (
+    'directors', 'secretaries', 'members', 'pscs', 'allotments', 'transfers', 'charges'
+);
+
+CREATE TABLE register_entries (
+    id UUID PRIMARY KEY,
+    submission_id UUID NOT NULL REFERENCES submissions (id) ON DELETE CASCADE,
+    company_number VARCHAR NOT NULL,
+    register register_type NOT NULL,
+    -- Identifies the person or holding an entry is about, so removals can be matched to the entry they end
+    entry_key VARCHAR NOT NULL,
+    entry_date DATE NOT NULL,
+    removal BOOLEAN NOT NULL,
+    details JSONB NOT NULL
+);
+
+CREATE INDEX register_entries_company_number ON register_entries (company_number, register, entry_date);
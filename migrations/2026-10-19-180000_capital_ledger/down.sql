@@ -0,0 +1,3 @@
+DROP TABLE share_movements;
+DROP TYPE share_movement_type;
+DROP TABLE share_classes;
@@ -0,0 +1,28 @@
+CREATE TABLE share_classes (
+    id UUID PRIMARY KEY,
+    company_number VARCHAR NOT NULL,
+    share_class VARCHAR NOT NULL,
+    currency VARCHAR NOT NULL,
+    nominal_value DOUBLE PRECISION NOT NULL,
+    prescribed_particulars VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX share_classes_company_number ON share_classes (company_number, share_class, currency);
+
+CREATE TYPE share_movement_type AS ENUM ('allotment', 'transfer', 'cancellation');
+
+CREATE TABLE share_movements (
+    id UUID PRIMARY KEY,
+    share_class_id UUID NOT NULL REFERENCES share_classes (id) ON DELETE CASCADE,
+    movement share_movement_type NOT NULL,
+    -- The allottee, transferee, or the member whose shares were cancelled
+    member_name VARCHAR NOT NULL,
+    -- The transferor, for transfers only
+    from_member_name VARCHAR,
+    num_shares DOUBLE PRECISION NOT NULL,
+    amount_paid_per_share DOUBLE PRECISION NOT NULL,
+    amount_unpaid_per_share DOUBLE PRECISION NOT NULL,
+    movement_date DATE NOT NULL
+);
+
+CREATE INDEX share_movements_share_class ON share_movements (share_class_id, movement_date);
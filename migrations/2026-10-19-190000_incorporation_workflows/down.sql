@@ -0,0 +1,2 @@
+DROP TABLE incorporation_workflows;
+DROP TYPE incorporation_workflow_state;
@@ -0,0 +1,25 @@
+CREATE TYPE incorporation_workflow_state AS ENUM ('draft', 'submitted', 'rejected', 'accepted', 'completed');
+
+CREATE TABLE incorporation_workflows (
+    id UUID PRIMARY KEY,
+    state incorporation_workflow_state NOT NULL,
+    -- Encoded CompanyIncorporation message, built up by each step
+    draft BYTEA NOT NULL,
+    draft_encrypted BOOLEAN NOT NULL,
+    e_reminder_emails VARCHAR[] NOT NULL,
+    submission_id UUID REFERENCES submissions (id),
+    company_number VARCHAR,
+    incorporation_date DATE,
+    authentication_code BYTEA,
+    authentication_code_encrypted BOOLEAN NOT NULL DEFAULT false,
+    certificate_document_id UUID REFERENCES documents (id),
+    managed_company_id UUID REFERENCES managed_companies (id) ON DELETE SET NULL,
+    e_reminders_set BOOLEAN NOT NULL DEFAULT false,
+    registers_recorded BOOLEAN NOT NULL DEFAULT false,
+    follow_up_error VARCHAR,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX incorporation_workflows_submission ON incorporation_workflows (submission_id);
+CREATE INDEX incorporation_workflows_state ON incorporation_workflows (state);
@@ -0,0 +1,3 @@
+DROP TABLE bulk_submission_items;
+DROP TABLE bulk_submissions;
+DROP TYPE bulk_item_state;
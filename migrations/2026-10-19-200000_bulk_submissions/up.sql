@@ -0,0 +1,24 @@
+CREATE TYPE bulk_item_state AS ENUM ('pending', 'submitting', 'submitted', 'failed');
+
+CREATE TABLE bulk_submissions (
+    id UUID PRIMARY KEY,
+    form_type VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE bulk_submission_items (
+    id UUID PRIMARY KEY,
+    bulk_submission_id UUID NOT NULL REFERENCES bulk_submissions (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    company_number VARCHAR NOT NULL,
+    state bulk_item_state NOT NULL,
+    -- Encoded BulkForm message for the company, cleared once it's been attempted
+    request BYTEA,
+    request_encrypted BOOLEAN NOT NULL,
+    submission_id UUID REFERENCES submissions (id),
+    error VARCHAR,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX bulk_submission_items_bulk_submission ON bulk_submission_items (bulk_submission_id);
+CREATE INDEX bulk_submission_items_state ON bulk_submission_items (state);
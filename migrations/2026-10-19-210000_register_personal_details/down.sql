@@ -0,0 +1,6 @@
+-- Encrypted personal details can't be restored without the key, so they're dropped
+UPDATE register_entries SET details = details || convert_from(personal_details, 'UTF8')::jsonb
+WHERE personal_details IS NOT NULL AND NOT personal_details_encrypted;
+
+ALTER TABLE register_entries DROP COLUMN personal_details_encrypted;
+ALTER TABLE register_entries DROP COLUMN personal_details;
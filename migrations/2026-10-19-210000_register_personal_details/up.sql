@@ -0,0 +1,20 @@
+-- Details that identify individuals are kept apart from the rest of an entry so they can be encrypted at rest
+ALTER TABLE register_entries ADD COLUMN personal_details BYTEA;
+ALTER TABLE register_entries ADD COLUMN personal_details_encrypted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Existing entries are moved out of the details unencrypted, and encrypted by the next key rotation.
+-- Only individuals have a date of birth or a PSC kind of 'Individual', which tells their addresses apart from corporate ones.
+WITH personal AS (
+    SELECT id, (
+        SELECT jsonb_object_agg(key, value) FROM jsonb_each(details)
+        WHERE key IN ('date_of_birth', 'nationality', 'country_of_residence')
+            OR (key IN ('service_address', 'address')
+                AND (details ? 'date_of_birth' OR details ->> 'kind' = 'Individual'))
+    ) AS data
+    FROM register_entries
+)
+UPDATE register_entries SET
+    personal_details = convert_to(personal.data::text, 'UTF8'),
+    details = register_entries.details - ARRAY(SELECT jsonb_object_keys(personal.data))
+FROM personal
+WHERE personal.id = register_entries.id AND personal.data IS NOT NULL;
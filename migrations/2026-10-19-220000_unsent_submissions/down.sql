@@ -0,0 +1,7 @@
+-- Whether the gateway received an unsent submission isn't known, so they're kept out of polling as failures
+ALTER TYPE status RENAME TO status_old;
+CREATE TYPE status AS ENUM ('pending', 'accepted', 'rejected', 'parked', 'internal_failure');
+ALTER TABLE submissions ALTER COLUMN status TYPE status USING (
+    CASE WHEN status::text = 'unsent' THEN 'internal_failure' ELSE status::text END
+)::status;
+DROP TYPE status_old;
@@ -0,0 +1,105 @@
+use super::{ch_ewf_grpc, schema, managed_companies};
+use ch_ewf_grpc::bulk_submission as bulk;
+
+pub const MAX_COMPANIES: usize = 1000;
+
+impl From<schema::BulkItemState> for bulk::BulkItemState {
+    fn from(value: schema::BulkItemState) -> Self {
+        match value {
+            schema::BulkItemState::Pending => bulk::BulkItemState::Pending,
+            schema::BulkItemState::Submitting => bulk::BulkItemState::Submitting,
+            schema::BulkItemState::Submitted => bulk::BulkItemState::Submitted,
+            schema::BulkItemState::Failed => bulk::BulkItemState::Failed,
+        }
+    }
+}
+
+pub fn form_type(form: &bulk::bulk_form::Form) -> &'static str {
+    match form {
+        bulk::bulk_form::Form::ChangeRegisteredOffice(_) => "ChangeRegisteredOfficeAddress",
+        bulk::bulk_form::Form::ConfirmationStatement(_) => "ConfirmationStatement",
+        bulk::bulk_form::Form::SetEReminders(_) => "EReminders",
+    }
+}
+
+/// The template with a company's details in place of its own
+pub fn company_form(template: &bulk::bulk_form::Form, company: &bulk::BulkCompany) -> bulk::bulk_form::Form {
+    let set_form_submission = |f: &mut Option<ch_ewf_grpc::form_submission::FormSubmission>| {
+        let f = f.get_or_insert_with(Default::default);
+        f.managed_company_id = company.managed_company_id.clone();
+        f.company_number = company.company_number;
+        f.company_type = company.company_type;
+        f.company_name = company.company_name.clone();
+        f.authentication_code = company.authentication_code.clone();
+        if company.customer_reference.is_some() {
+            f.customer_reference = company.customer_reference.clone();
+        }
+    };
+
+    let mut form = template.clone();
+    match &mut form {
+        bulk::bulk_form::Form::ChangeRegisteredOffice(f) => set_form_submission(&mut f.form_submission),
+        bulk::bulk_form::Form::ConfirmationStatement(f) => set_form_submission(&mut f.form_submission),
+        bulk::bulk_form::Form::SetEReminders(r) => {
+            r.managed_company_id = company.managed_company_id.clone();
+            r.company_number = company.company_number;
+            r.company_type = company.company_type;
+            r.authentication_code = company.authentication_code.clone();
+        }
+    }
+    form
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ImportedCompany {
+    managed_company_id: String,
+    company_number: String,
+    company_name: String,
+    authentication_code: String,
+    customer_reference: String,
+}
+
+impl ImportedCompany {
+    fn into_company(self, row: usize) -> Result<bulk::BulkCompany, String> {
+        let (company_number, company_type) = if self.company_number.is_empty() {
+            (0, ch_ewf_grpc::base_types::CompanyType::CompanyEnglandAndWales)
+        } else {
+            match managed_companies::parse_company_number(self.company_number.trim()) {
+                Some((n, t)) => (n, t.into()),
+                None => return Err(format!("Row {}: invalid company number", row))
+            }
+        };
+        Ok(bulk::BulkCompany {
+            managed_company_id: self.managed_company_id,
+            company_number,
+            company_type: company_type.into(),
+            company_name: self.company_name,
+            authentication_code: self.authentication_code,
+            customer_reference: if self.customer_reference.is_empty() {
+                None
+            } else {
+                Some(self.customer_reference)
+            },
+        })
+    }
+}
+
+/// Reads companies from a CSV file with a header row, or a JSON array of objects
+pub fn import(data: &[u8], format: bulk::ImportFormat) -> Result<Vec<bulk::BulkCompany>, String> {
+    let companies = match format {
+        bulk::ImportFormat::Csv => csv::Reader::from_reader(data)
+            .deserialize::<ImportedCompany>()
+            .enumerate()
+            .map(|(i, c)| c.map_err(|err| format!("Row {}: {}", i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?,
+        bulk::ImportFormat::Json => match serde_json::from_slice::<Vec<ImportedCompany>>(data) {
+            Ok(c) => c,
+            Err(err) => return Err(err.to_string())
+        }
+    };
+    companies.into_iter()
+        .enumerate()
+        .map(|(i, c)| c.into_company(i + 1))
+        .collect()
+}
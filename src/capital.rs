@@ -0,0 +1,407 @@
+use super::ch_ewf_grpc;
+
+/// Differences below this are rounding from 6 decimal place amounts
+const TOLERANCE: f64 = 0.000_001;
+
+/// Shares allotted by a filing, from SH01 allotments or incorporation subscribers
+pub struct Allotment<'a> {
+    pub share_class: &'a str,
+    pub currency: &'a str,
+    pub num_shares: f64,
+    pub nominal_value: f64,
+    pub amount_unpaid_per_share: f64,
+}
+
+impl<'a> From<&'a ch_ewf_grpc::return_allotment_shares::Allotment> for Allotment<'a> {
+    fn from(a: &'a ch_ewf_grpc::return_allotment_shares::Allotment) -> Self {
+        Allotment {
+            share_class: &a.share_class,
+            currency: &a.share_currency,
+            num_shares: a.num_shares,
+            nominal_value: a.share_value,
+            amount_unpaid_per_share: a.amount_unpaid_per_share,
+        }
+    }
+}
+
+impl<'a> From<&'a ch_ewf_grpc::company_incorporation::Allotment> for Allotment<'a> {
+    fn from(a: &'a ch_ewf_grpc::company_incorporation::Allotment) -> Self {
+        Allotment {
+            share_class: &a.share_class,
+            currency: &a.share_currency,
+            num_shares: a.num_shares,
+            nominal_value: a.share_value,
+            amount_unpaid_per_share: a.amount_unpaid_per_share,
+        }
+    }
+}
+
+/// How the allotments of a filing relate to its statement of capital
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Allotments {
+    /// Incorporation, where the subscribers' shares are all the shares of the company
+    All,
+    /// SH01, where the statement of capital also includes shares issued before
+    Additional,
+}
+
+fn differs(a: f64, b: f64) -> bool {
+    (a - b).abs() > TOLERANCE
+}
+
+fn same_class(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Checks a statement of capital adds up, returning a description of each mismatch
+pub fn check_statement(capital: &[ch_ewf_grpc::base_types::Capital]) -> Vec<String> {
+    let mut errors = vec![];
+
+    for (i, c) in capital.iter().enumerate() {
+        let currency = c.currency.to_uppercase();
+        if currency.len() != 3 || !currency.chars().all(|ch| ch.is_ascii_alphabetic()) {
+            errors.push(format!("Statement of capital {}: invalid currency code {:?}", i + 1, c.currency));
+        }
+        if capital[..i].iter().any(|o| o.currency.eq_ignore_ascii_case(&c.currency)) {
+            errors.push(format!("{}: currency appears more than once in the statement of capital", currency));
+        }
+        if c.shares.is_empty() {
+            errors.push(format!("{}: no share classes given", currency));
+            continue;
+        }
+
+        for (j, s) in c.shares.iter().enumerate() {
+            if c.shares[..j].iter().any(|o| same_class(&o.share_class, &s.share_class)) {
+                errors.push(format!("{} {}: share class appears more than once", currency, s.share_class));
+            }
+            if s.num_shares > 0.0 && s.aggregate_nominal_value <= 0.0 {
+                errors.push(format!("{} {}: aggregate nominal value must be given for issued shares", currency, s.share_class));
+            }
+        }
+
+        let num_shares: f64 = c.shares.iter().map(|s| s.num_shares).sum();
+        if differs(c.total_number_of_shares_issued, num_shares) {
+            errors.push(format!(
+                "{}: total number of shares issued is {} but the share classes add up to {}",
+                currency, c.total_number_of_shares_issued, num_shares
+            ));
+        }
+        let aggregate_nominal_value: f64 = c.shares.iter().map(|s| s.aggregate_nominal_value).sum();
+        if differs(c.total_aggregate_nominal_value, aggregate_nominal_value) {
+            errors.push(format!(
+                "{}: total aggregate nominal value is {} but the share classes add up to {}",
+                currency, c.total_aggregate_nominal_value, aggregate_nominal_value
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Checks the allotments of a filing agree with its statement of capital, returning a description of each mismatch
+pub fn check_allotments(
+    capital: &[ch_ewf_grpc::base_types::Capital], allotments: &[Allotment], kind: Allotments,
+) -> Vec<String> {
+    let mut errors = vec![];
+
+    // Totals per currency and class, in the order they're first allotted
+    let mut totals: Vec<(String, &str, f64, f64, f64)> = vec![];
+    for a in allotments {
+        let currency = a.currency.to_uppercase();
+        match totals.iter_mut().find(|(c, s, ..)| c == &currency && same_class(s, a.share_class)) {
+            Some((_, _, num_shares, nominal_value, unpaid)) => {
+                *num_shares += a.num_shares;
+                *nominal_value += a.num_shares * a.nominal_value;
+                *unpaid += a.num_shares * a.amount_unpaid_per_share;
+            }
+            None => totals.push((
+                currency, a.share_class, a.num_shares, a.num_shares * a.nominal_value, a.num_shares * a.amount_unpaid_per_share
+            )),
+        }
+    }
+
+    for (currency, share_class, num_shares, nominal_value, _) in &totals {
+        let statement = match capital.iter().find(|c| c.currency.eq_ignore_ascii_case(currency)) {
+            Some(c) => c,
+            None => {
+                errors.push(format!("{} {}: shares allotted in a currency missing from the statement of capital", currency, share_class));
+                continue;
+            }
+        };
+        let share = match statement.shares.iter().find(|s| same_class(&s.share_class, share_class)) {
+            Some(s) => s,
+            None => {
+                errors.push(format!("{} {}: share class allotted but missing from the statement of capital", currency, share_class));
+                continue;
+            }
+        };
+
+        match kind {
+            Allotments::All => {
+                if differs(share.num_shares, *num_shares) {
+                    errors.push(format!(
+                        "{} {}: statement of capital shows {} shares but {} are allotted",
+                        currency, share_class, share.num_shares, num_shares
+                    ));
+                }
+                if differs(share.aggregate_nominal_value, *nominal_value) {
+                    errors.push(format!(
+                        "{} {}: statement of capital shows an aggregate nominal value of {} but the allotments add up to {}",
+                        currency, share_class, share.aggregate_nominal_value, nominal_value
+                    ));
+                }
+            }
+            Allotments::Additional => {
+                if share.num_shares + TOLERANCE < *num_shares {
+                    errors.push(format!(
+                        "{} {}: {} shares allotted but the statement of capital only shows {}",
+                        currency, share_class, num_shares, share.num_shares
+                    ));
+                }
+            }
+        }
+    }
+
+    for c in capital {
+        let currency = c.currency.to_uppercase();
+        let unpaid: f64 = totals.iter().filter(|(tc, ..)| tc == &currency).map(|t| t.4).sum();
+        match kind {
+            Allotments::All => {
+                for s in &c.shares {
+                    if !totals.iter().any(|(tc, ts, ..)| tc == &currency && same_class(ts, &s.share_class)) {
+                        errors.push(format!("{} {}: share class in the statement of capital but not allotted to any subscriber", currency, s.share_class));
+                    }
+                }
+                if differs(c.total_amount_unpaid, unpaid) {
+                    errors.push(format!(
+                        "{}: total amount unpaid is {} but the allotments add up to {}",
+                        currency, c.total_amount_unpaid, unpaid
+                    ));
+                }
+            }
+            // Shares issued before may be partly paid too, so the allotments only set a lower bound
+            Allotments::Additional => {
+                if c.total_amount_unpaid + TOLERANCE < unpaid {
+                    errors.push(format!(
+                        "{}: total amount unpaid is {} but the allotted shares alone leave {} unpaid",
+                        currency, c.total_amount_unpaid, unpaid
+                    ));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// Checks CS01 shareholdings only name classes in the statement of capital, and hold no more than were issued.
+/// Shareholdings don't say how much has been paid on the shares, so the total amount unpaid can't be checked.
+pub fn check_shareholdings(
+    capital: &[ch_ewf_grpc::base_types::Capital], shareholdings: &[ch_ewf_grpc::confirmation_statement::Shareholding],
+) -> Vec<String> {
+    let mut errors = vec![];
+    let mut totals: Vec<(&str, f64)> = vec![];
+    for s in shareholdings {
+        match totals.iter_mut().find(|(c, _)| same_class(c, &s.share_class)) {
+            Some((_, n)) => *n += s.number_held,
+            None => totals.push((&s.share_class, s.number_held)),
+        }
+    }
+
+    for (share_class, number_held) in totals {
+        let issued: Vec<f64> = capital.iter()
+            .flat_map(|c| c.shares.iter())
+            .filter(|s| same_class(&s.share_class, share_class))
+            .map(|s| s.num_shares)
+            .collect();
+        if issued.is_empty() {
+            errors.push(format!("{}: shareholding in a class missing from the statement of capital", share_class));
+        } else if number_held > issued.iter().sum::<f64>() + TOLERANCE {
+            errors.push(format!(
+                "{}: shareholders hold {} shares but the statement of capital shows {}",
+                share_class, number_held, issued.iter().sum::<f64>()
+            ));
+        }
+    }
+
+    errors
+}
+
+pub fn into_result(errors: Vec<String>) -> Result<(), tonic::Status> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(tonic::Status::invalid_argument(format!("Inconsistent statement of capital: {}", errors.join("; "))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(share_class: &str, num_shares: f64, aggregate_nominal_value: f64) -> ch_ewf_grpc::base_types::Share {
+        ch_ewf_grpc::base_types::Share {
+            share_class: share_class.to_string(),
+            num_shares,
+            aggregate_nominal_value,
+            ..Default::default()
+        }
+    }
+
+    fn capital(currency: &str, shares: Vec<ch_ewf_grpc::base_types::Share>, total_amount_unpaid: f64) -> ch_ewf_grpc::base_types::Capital {
+        ch_ewf_grpc::base_types::Capital {
+            currency: currency.to_string(),
+            total_number_of_shares_issued: shares.iter().map(|s| s.num_shares).sum(),
+            total_aggregate_nominal_value: shares.iter().map(|s| s.aggregate_nominal_value).sum(),
+            total_amount_unpaid,
+            shares,
+        }
+    }
+
+    fn allotment<'a>(share_class: &'a str, currency: &'a str, num_shares: f64, nominal_value: f64, amount_unpaid_per_share: f64) -> Allotment<'a> {
+        Allotment {
+            share_class,
+            currency,
+            num_shares,
+            nominal_value,
+            amount_unpaid_per_share,
+        }
+    }
+
+    fn shareholding(share_class: &str, number_held: f64) -> ch_ewf_grpc::confirmation_statement::Shareholding {
+        ch_ewf_grpc::confirmation_statement::Shareholding {
+            share_class: share_class.to_string(),
+            number_held,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn statement_adds_up() {
+        let statement = vec![
+            capital("GBP", vec![share("ORDINARY", 100.0, 100.0), share("PREFERENCE", 50.0, 5.0)], 0.0),
+            capital("usd", vec![share("ORDINARY", 10.0, 10.0)], 0.0),
+        ];
+        assert!(check_statement(&statement).is_empty());
+    }
+
+    #[test]
+    fn statement_totals_checked_per_currency() {
+        let mut gbp = capital("GBP", vec![share("ORDINARY", 100.0, 100.0)], 0.0);
+        gbp.total_number_of_shares_issued = 110.0;
+        let mut usd = capital("USD", vec![share("ORDINARY", 10.0, 10.0)], 0.0);
+        usd.total_aggregate_nominal_value = 5.0;
+        let errors = check_statement(&[gbp, usd]);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].starts_with("GBP: total number of shares issued"));
+        assert!(errors[1].starts_with("USD: total aggregate nominal value"));
+    }
+
+    #[test]
+    fn statement_structure_checked() {
+        let errors = check_statement(&[
+            capital("GBP", vec![share("ORDINARY", 100.0, 100.0), share(" ordinary ", 1.0, 0.0)], 0.0),
+            capital("gbp", vec![], 0.0),
+            capital("POUNDS", vec![share("ORDINARY", 1.0, 1.0)], 0.0),
+        ]);
+        assert!(errors.iter().any(|e| e.contains("share class appears more than once")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("aggregate nominal value must be given")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("currency appears more than once")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("no share classes given")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("invalid currency code")), "{:?}", errors);
+    }
+
+    #[test]
+    fn nominal_value_rounding_tolerated() {
+        // 3 shares of 0.1 don't add up to exactly 0.3 in floating point
+        let statement = vec![capital("GBP", vec![share("ORDINARY", 3.0, 0.3)], 0.0)];
+        let allotments = vec![
+            allotment("ORDINARY", "GBP", 1.0, 0.1, 0.0),
+            allotment("ORDINARY", "GBP", 1.0, 0.1, 0.0),
+            allotment("ORDINARY", "GBP", 1.0, 0.1, 0.0),
+        ];
+        assert!(check_statement(&statement).is_empty());
+        assert!(check_allotments(&statement, &allotments, Allotments::All).is_empty());
+
+        let statement = vec![capital("GBP", vec![share("ORDINARY", 3.0, 0.30001)], 0.0)];
+        assert_eq!(check_allotments(&statement, &allotments, Allotments::All).len(), 1);
+    }
+
+    #[test]
+    fn all_allotments_must_match_statement() {
+        let statement = vec![
+            capital("GBP", vec![share("ORDINARY", 100.0, 100.0), share("PREFERENCE", 10.0, 10.0)], 25.0),
+            capital("EUR", vec![share("ORDINARY", 5.0, 5.0)], 0.0),
+        ];
+        let allotments = vec![
+            allotment("ordinary", "gbp", 50.0, 1.0, 0.5),
+            allotment("ORDINARY", "GBP", 50.0, 1.0, 0.0),
+            allotment("PREFERENCE", "GBP", 10.0, 1.0, 0.0),
+            allotment("ORDINARY", "EUR", 5.0, 1.0, 0.0),
+        ];
+        assert!(check_allotments(&statement, &allotments, Allotments::All).is_empty());
+
+        // Subscribers must take every share, and the unpaid amounts must add up
+        let errors = check_allotments(&statement, &allotments[1..], Allotments::All);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("GBP ORDINARY: statement of capital shows 100 shares but 50 are allotted"));
+        assert!(errors[2].starts_with("GBP: total amount unpaid is 25 but the allotments add up to 0"));
+
+        let errors = check_allotments(&statement, &allotments[..3], Allotments::All);
+        assert_eq!(errors, vec!["EUR ORDINARY: share class in the statement of capital but not allotted to any subscriber".to_string()]);
+    }
+
+    #[test]
+    fn additional_allotments_within_statement() {
+        // Shares issued before the allotment, partly paid, are included in the statement
+        let statement = vec![capital("GBP", vec![share("ORDINARY", 100.0, 100.0), share("PREFERENCE", 10.0, 10.0)], 30.0)];
+        let allotments = vec![allotment("ORDINARY", "GBP", 20.0, 1.0, 0.5)];
+        assert!(check_allotments(&statement, &allotments, Allotments::Additional).is_empty());
+
+        let allotments = vec![allotment("ORDINARY", "GBP", 120.0, 1.0, 0.0)];
+        let errors = check_allotments(&statement, &allotments, Allotments::Additional);
+        assert_eq!(errors, vec!["GBP ORDINARY: 120 shares allotted but the statement of capital only shows 100".to_string()]);
+
+        let allotments = vec![allotment("ORDINARY", "GBP", 80.0, 1.0, 0.5)];
+        let errors = check_allotments(&statement, &allotments, Allotments::Additional);
+        assert_eq!(errors, vec!["GBP: total amount unpaid is 30 but the allotted shares alone leave 40 unpaid".to_string()]);
+    }
+
+    #[test]
+    fn allotments_missing_from_statement() {
+        let statement = vec![capital("GBP", vec![share("ORDINARY", 100.0, 100.0)], 0.0)];
+        let allotments = vec![
+            allotment("PREFERENCE", "GBP", 1.0, 1.0, 0.0),
+            allotment("ORDINARY", "USD", 1.0, 1.0, 0.0),
+        ];
+        for kind in [Allotments::All, Allotments::Additional] {
+            let errors = check_allotments(&statement, &allotments, kind);
+            assert!(errors.iter().any(|e| e == "GBP PREFERENCE: share class allotted but missing from the statement of capital"), "{:?}", errors);
+            assert!(errors.iter().any(|e| e == "USD ORDINARY: shares allotted in a currency missing from the statement of capital"), "{:?}", errors);
+        }
+    }
+
+    #[test]
+    fn shareholdings_within_statement() {
+        let statement = vec![
+            capital("GBP", vec![share("ORDINARY", 100.0, 100.0)], 0.0),
+            capital("USD", vec![share("ORDINARY", 50.0, 50.0)], 0.0),
+        ];
+        // Classes of the same name in different currencies are added together
+        assert!(check_shareholdings(&statement, &[shareholding("ORDINARY", 100.0), shareholding("ordinary", 50.0)]).is_empty());
+
+        let errors = check_shareholdings(&statement, &[shareholding("ORDINARY", 151.0), shareholding("B", 1.0)]);
+        assert_eq!(errors, vec![
+            "ORDINARY: shareholders hold 151 shares but the statement of capital shows 150".to_string(),
+            "B: shareholding in a class missing from the statement of capital".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn errors_into_result() {
+        assert!(into_result(vec![]).is_ok());
+        let err = into_result(vec!["a".to_string(), "b".to_string()]).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert_eq!(err.message(), "Inconsistent statement of capital: a; b");
+    }
+}
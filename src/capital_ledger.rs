@@ -0,0 +1,369 @@
+use super::{ch_ewf_grpc, models, schema, managed_companies};
+use super::grpc::chrono_to_proto;
+use diesel::prelude::*;
+
+impl From<models::ShareClass> for ch_ewf_grpc::capital::ShareClass {
+    fn from(value: models::ShareClass) -> Self {
+        let (company_number, company_type) = managed_companies::parse_company_number(&value.company_number)
+            .unwrap_or((0, schema::CompanyType::EnglandAndWales));
+        ch_ewf_grpc::capital::ShareClass {
+            id: value.id.to_string(),
+            company_number,
+            company_type: ch_ewf_grpc::base_types::CompanyType::from(company_type).into(),
+            share_class: value.share_class,
+            currency: value.currency,
+            nominal_value: value.nominal_value,
+            prescribed_particulars: value.prescribed_particulars,
+        }
+    }
+}
+
+impl From<ch_ewf_grpc::capital::ShareMovementType> for schema::ShareMovementType {
+    fn from(value: ch_ewf_grpc::capital::ShareMovementType) -> Self {
+        match value {
+            ch_ewf_grpc::capital::ShareMovementType::Allotment => schema::ShareMovementType::Allotment,
+            ch_ewf_grpc::capital::ShareMovementType::Transfer => schema::ShareMovementType::Transfer,
+            ch_ewf_grpc::capital::ShareMovementType::Cancellation => schema::ShareMovementType::Cancellation,
+        }
+    }
+}
+
+impl From<schema::ShareMovementType> for ch_ewf_grpc::capital::ShareMovementType {
+    fn from(value: schema::ShareMovementType) -> Self {
+        match value {
+            schema::ShareMovementType::Allotment => ch_ewf_grpc::capital::ShareMovementType::Allotment,
+            schema::ShareMovementType::Transfer => ch_ewf_grpc::capital::ShareMovementType::Transfer,
+            schema::ShareMovementType::Cancellation => ch_ewf_grpc::capital::ShareMovementType::Cancellation,
+        }
+    }
+}
+
+impl From<models::ShareMovement> for ch_ewf_grpc::capital::ShareMovement {
+    fn from(value: models::ShareMovement) -> Self {
+        ch_ewf_grpc::capital::ShareMovement {
+            id: value.id.to_string(),
+            share_class_id: value.share_class_id.to_string(),
+            movement: ch_ewf_grpc::capital::ShareMovementType::from(value.movement).into(),
+            member_name: value.member_name,
+            from_member_name: value.from_member_name.unwrap_or_default(),
+            num_shares: value.num_shares,
+            amount_paid_per_share: value.amount_paid_per_share,
+            amount_unpaid_per_share: value.amount_unpaid_per_share,
+            date: chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(
+                value.movement_date.and_hms(0, 0, 0), chrono::Utc
+            ))),
+        }
+    }
+}
+
+/// Companies House accepts at most 6 decimal places, and summing floats leaves noise beyond that
+fn round(value: f64) -> f64 {
+    (value * 1_000_000.0).round() / 1_000_000.0
+}
+
+fn member_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Shares a movement gives to (or takes from) the member with the given key
+fn member_change(movement: &models::ShareMovement, key: &str) -> f64 {
+    let to = member_key(&movement.member_name) == key;
+    match movement.movement {
+        schema::ShareMovementType::Allotment if to => movement.num_shares,
+        schema::ShareMovementType::Transfer => {
+            let from = movement.from_member_name.as_deref().map_or(false, |f| member_key(f) == key);
+            match (from, to) {
+                (true, false) => -movement.num_shares,
+                (false, true) => movement.num_shares,
+                _ => 0.0
+            }
+        }
+        schema::ShareMovementType::Cancellation if to => -movement.num_shares,
+        _ => 0.0
+    }
+}
+
+pub struct Holding {
+    pub member_name: String,
+    pub share_class: models::ShareClass,
+    pub num_shares: f64,
+}
+
+/// Share classes and the movements between members of a company, oldest first
+pub struct Ledger {
+    pub share_classes: Vec<models::ShareClass>,
+    pub movements: Vec<models::ShareMovement>,
+}
+
+impl Ledger {
+    pub fn load(conn: &diesel::pg::PgConnection, company_number: String, as_at: Option<chrono::NaiveDate>) -> QueryResult<Self> {
+        let share_classes: Vec<models::ShareClass> = schema::share_classes::dsl::share_classes
+            .filter(schema::share_classes::dsl::company_number.eq(company_number))
+            .order_by((schema::share_classes::dsl::currency.asc(), schema::share_classes::dsl::share_class.asc()))
+            .load(conn)?;
+
+        let mut query = schema::share_movements::dsl::share_movements
+            .filter(schema::share_movements::dsl::share_class_id.eq_any(share_classes.iter().map(|c| c.id).collect::<Vec<_>>()))
+            .order_by(schema::share_movements::dsl::movement_date.asc())
+            .into_boxed();
+        if let Some(as_at) = as_at {
+            query = query.filter(schema::share_movements::dsl::movement_date.le(as_at));
+        }
+
+        Ok(Ledger {
+            share_classes,
+            movements: query.load(conn)?,
+        })
+    }
+
+    fn share_class(&self, id: &uuid::Uuid) -> Option<&models::ShareClass> {
+        self.share_classes.iter().find(|c| &c.id == id)
+    }
+
+    /// Shares held by each member in each class, leaving out members who no longer hold any
+    pub fn holdings(&self) -> Vec<Holding> {
+        // Keyed by member and class, keeping the order members first appear in
+        let mut holdings: Vec<((String, uuid::Uuid), Holding)> = vec![];
+        let mut adjust = |name: &str, share_class_id: uuid::Uuid, num_shares: f64| {
+            let key = (member_key(name), share_class_id);
+            match holdings.iter_mut().find(|(k, _)| k == &key) {
+                Some((_, h)) => h.num_shares += num_shares,
+                None => if let Some(share_class) = self.share_class(&share_class_id) {
+                    holdings.push((key, Holding {
+                        member_name: name.to_string(),
+                        share_class: share_class.clone(),
+                        num_shares,
+                    }));
+                }
+            }
+        };
+
+        for movement in &self.movements {
+            match movement.movement {
+                schema::ShareMovementType::Allotment =>
+                    adjust(&movement.member_name, movement.share_class_id, movement.num_shares),
+                schema::ShareMovementType::Transfer => {
+                    if let Some(from) = &movement.from_member_name {
+                        adjust(from, movement.share_class_id, -movement.num_shares);
+                    }
+                    adjust(&movement.member_name, movement.share_class_id, movement.num_shares);
+                }
+                schema::ShareMovementType::Cancellation =>
+                    adjust(&movement.member_name, movement.share_class_id, -movement.num_shares),
+            }
+        }
+
+        holdings.into_iter()
+            .map(|(_, mut h)| {
+                h.num_shares = round(h.num_shares);
+                h
+            })
+            .filter(|h| h.num_shares > 0.0)
+            .collect()
+    }
+
+    /// Shares a member holds in a class
+    pub fn member_holding(&self, member_name: &str, share_class_id: &uuid::Uuid) -> f64 {
+        let key = member_key(member_name);
+        self.holdings().into_iter()
+            .filter(|h| member_key(&h.member_name) == key && &h.share_class.id == share_class_id)
+            .map(|h| h.num_shares)
+            .sum()
+    }
+
+    /// Checks a new movement leaves no member holding a negative number of shares at the end of any day. The
+    /// whole ledger is replayed with the movement in place, so a backdated movement can't take away shares the
+    /// member has since transferred or cancelled. Movements on the same day aren't ordered, so only the balance
+    /// at the end of each day is checked.
+    pub fn check_movement(&self, movement: &models::ShareMovement) -> Result<(), String> {
+        let (from, verb) = match movement.movement {
+            schema::ShareMovementType::Allotment => return Ok(()),
+            schema::ShareMovementType::Transfer => match &movement.from_member_name {
+                Some(f) => (f.as_str(), "transfer"),
+                None => return Err("Transfers require the member shares are transferred from".to_string())
+            },
+            schema::ShareMovementType::Cancellation => (movement.member_name.as_str(), "cancel"),
+        };
+
+        // Only the member the shares come from loses any, so theirs is the only balance that can go negative
+        let key = member_key(from);
+        let mut movements = self.movements.iter()
+            .filter(|m| m.share_class_id == movement.share_class_id)
+            .collect::<Vec<_>>();
+        let position = movements.iter()
+            .position(|m| m.movement_date > movement.movement_date)
+            .unwrap_or(movements.len());
+        movements.insert(position, movement);
+
+        let mut held = 0.0;
+        for (i, m) in movements.iter().enumerate() {
+            held += member_change(m, &key);
+            let end_of_day = movements.get(i + 1).map_or(true, |n| n.movement_date != m.movement_date);
+            if end_of_day && m.movement_date >= movement.movement_date && round(held) < 0.0 {
+                return Err(format!(
+                    "{} would hold {} shares in this class on {}, unable to {} {}",
+                    from, round(held), m.movement_date, verb, movement.num_shares
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// A statement of capital per currency with totals consistent with the ledger
+    pub fn statement_of_capital(&self) -> Vec<ch_ewf_grpc::base_types::Capital> {
+        let mut capital: Vec<ch_ewf_grpc::base_types::Capital> = vec![];
+
+        for share_class in &self.share_classes {
+            let mut num_shares = 0.0;
+            let mut amount_unpaid = 0.0;
+            for movement in self.movements.iter().filter(|m| m.share_class_id == share_class.id) {
+                match movement.movement {
+                    schema::ShareMovementType::Allotment => {
+                        num_shares += movement.num_shares;
+                        amount_unpaid += movement.num_shares * movement.amount_unpaid_per_share;
+                    }
+                    schema::ShareMovementType::Cancellation => {
+                        num_shares -= movement.num_shares;
+                        amount_unpaid -= movement.num_shares * movement.amount_unpaid_per_share;
+                    }
+                    schema::ShareMovementType::Transfer => {}
+                }
+            }
+            let num_shares = round(num_shares);
+            if num_shares <= 0.0 {
+                continue;
+            }
+
+            let share = ch_ewf_grpc::base_types::Share {
+                share_class: share_class.share_class.clone(),
+                prescribed_particulars: share_class.prescribed_particulars.clone(),
+                num_shares,
+                aggregate_nominal_value: round(num_shares * share_class.nominal_value),
+            };
+            let currency_capital = match capital.iter_mut().find(|c| c.currency == share_class.currency) {
+                Some(c) => c,
+                None => {
+                    capital.push(ch_ewf_grpc::base_types::Capital {
+                        currency: share_class.currency.clone(),
+                        ..Default::default()
+                    });
+                    capital.last_mut().unwrap()
+                }
+            };
+            currency_capital.total_number_of_shares_issued = round(currency_capital.total_number_of_shares_issued + share.num_shares);
+            currency_capital.total_aggregate_nominal_value = round(currency_capital.total_aggregate_nominal_value + share.aggregate_nominal_value);
+            currency_capital.total_amount_unpaid = round(currency_capital.total_amount_unpaid + amount_unpaid.max(0.0));
+            currency_capital.shares.push(share);
+        }
+
+        capital
+    }
+
+    pub fn cap_table(&self) -> ch_ewf_grpc::capital::CapTable {
+        let holdings = self.holdings();
+        let class_totals = |id: &uuid::Uuid| -> f64 {
+            holdings.iter().filter(|h| &h.share_class.id == id).map(|h| h.num_shares).sum()
+        };
+
+        ch_ewf_grpc::capital::CapTable {
+            holdings: holdings.iter().map(|h| ch_ewf_grpc::capital::Holding {
+                member_name: h.member_name.clone(),
+                share_class_id: h.share_class.id.to_string(),
+                share_class: h.share_class.share_class.clone(),
+                currency: h.share_class.currency.clone(),
+                num_shares: h.num_shares,
+                aggregate_nominal_value: round(h.num_shares * h.share_class.nominal_value),
+                class_percentage: round(h.num_shares / class_totals(&h.share_class.id) * 100.0),
+            }).collect(),
+            statement_of_capital: self.statement_of_capital(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share_class() -> models::ShareClass {
+        models::ShareClass {
+            id: uuid::Uuid::new_v4(),
+            company_number: "EW12345678".to_string(),
+            share_class: "ORDINARY".to_string(),
+            currency: "GBP".to_string(),
+            nominal_value: 1.0,
+            prescribed_particulars: String::new(),
+        }
+    }
+
+    fn movement(
+        share_class: &models::ShareClass, movement: schema::ShareMovementType, member_name: &str,
+        from_member_name: Option<&str>, num_shares: f64, date: (i32, u32, u32),
+    ) -> models::ShareMovement {
+        models::ShareMovement {
+            id: uuid::Uuid::new_v4(),
+            share_class_id: share_class.id,
+            movement,
+            member_name: member_name.to_string(),
+            from_member_name: from_member_name.map(str::to_string),
+            num_shares,
+            amount_paid_per_share: 1.0,
+            amount_unpaid_per_share: 0.0,
+            movement_date: chrono::NaiveDate::from_ymd(date.0, date.1, date.2),
+        }
+    }
+
+    fn ledger(share_class: &models::ShareClass) -> Ledger {
+        Ledger {
+            share_classes: vec![share_class.clone()],
+            movements: vec![
+                movement(share_class, schema::ShareMovementType::Allotment, "Alice Smith", None, 100.0, (2020, 1, 1)),
+                movement(share_class, schema::ShareMovementType::Transfer, "Bob Jones", Some("Alice Smith"), 60.0, (2021, 1, 1)),
+            ],
+        }
+    }
+
+    #[test]
+    fn movement_within_holding() {
+        let c = share_class();
+        let ledger = ledger(&c);
+        assert!(ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Transfer, "Carol White", Some("alice  smith"), 40.0, (2022, 1, 1),
+        )).is_ok());
+        assert!(ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Cancellation, "Bob Jones", None, 60.0, (2021, 1, 1),
+        )).is_ok());
+    }
+
+    #[test]
+    fn movement_over_holding() {
+        let c = share_class();
+        let ledger = ledger(&c);
+        assert!(ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Transfer, "Carol White", Some("Alice Smith"), 41.0, (2022, 1, 1),
+        )).is_err());
+        assert!(ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Cancellation, "Bob Jones", None, 1.0, (2020, 6, 1),
+        )).is_err());
+    }
+
+    #[test]
+    fn backdated_movement_checked_against_later_movements() {
+        let c = share_class();
+        let ledger = ledger(&c);
+        // Alice held 100 shares in mid 2020, but 60 of them were transferred later on
+        let err = ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Cancellation, "Alice Smith", None, 50.0, (2020, 6, 1),
+        )).unwrap_err();
+        assert!(err.contains("2021-01-01"), "{}", err);
+    }
+
+    #[test]
+    fn same_day_movements_checked_at_end_of_day() {
+        let c = share_class();
+        let ledger = ledger(&c);
+        // Bob passes on the shares on the day he receives them
+        assert!(ledger.check_movement(&movement(
+            &c, schema::ShareMovementType::Transfer, "Carol White", Some("Bob Jones"), 60.0, (2021, 1, 1),
+        )).is_ok());
+    }
+}
@@ -0,0 +1,649 @@
+use super::{grpc, ch_ewf_grpc, schema, models, metrics, crypto, document_store};
+use ch_ewf_grpc::ch_filling_server::ChFilling;
+use diesel::prelude::*;
+use tokio_diesel::AsyncRunQueryDsl;
+
+lazy_static! {
+    static ref DESCRIPTOR_POOL: prost_reflect::DescriptorPool = prost_reflect::DescriptorPool::decode(
+        include_bytes!(concat!(env!("OUT_DIR"), "/ch_ewf_descriptor.bin")).as_ref()
+    ).unwrap();
+}
+
+pub fn command() -> clap::Command<'static> {
+    clap::Command::new("AS207960 Companies House eFiller")
+        .author("Q Misell <q@as207960.net>")
+        .version(env!("CARGO_PKG_VERSION"))
+        .arg(clap::Arg::new("settings")
+            .short('s')
+            .long("settings")
+            .takes_value(true)
+            .required(false)
+            .global(true)
+            .help("Location of the settings file"))
+        .subcommand(clap::Command::new("serve")
+            .about("Runs the gRPC server and submission watcher (default)"))
+        .subcommand(clap::Command::new("migrate")
+            .about("Applies database migrations"))
+        .subcommand(clap::Command::new("submissions")
+            .about("Inspects stored submissions")
+            .subcommand_required(true)
+            .subcommand(clap::Command::new("list")
+                .about("Lists the most recent submissions")
+                .arg(clap::Arg::new("status")
+                    .long("status")
+                    .takes_value(true)
+                    .possible_values(["pending", "accepted", "rejected", "parked", "internal_failure", "unsent"])
+                    .help("Only list submissions with this status"))
+                .arg(clap::Arg::new("limit")
+                    .long("limit")
+                    .takes_value(true)
+                    .default_value("50")
+                    .help("Maximum number of submissions to list")))
+            .subcommand(clap::Command::new("show")
+                .about("Shows the stored status of a submission")
+                .arg(clap::Arg::new("id")
+                    .required(true)
+                    .help("Submission ID"))))
+        .subcommand(clap::Command::new("status")
+            .about("Manages submission statuses")
+            .subcommand_required(true)
+            .subcommand(clap::Command::new("refresh")
+                .about("Queries the gateway for the current status of a submission")
+                .arg(clap::Arg::new("id")
+                    .required(true)
+                    .help("Submission ID"))))
+        .subcommand(clap::Command::new("document")
+            .about("Manages documents")
+            .subcommand_required(true)
+            .subcommand(clap::Command::new("fetch")
+                .about("Fetches a document from the gateway and stores it")
+                .arg(clap::Arg::new("request-key")
+                    .required(true)
+                    .help("Document request key")))
+            .subcommand(clap::Command::new("migrate")
+                .about("Copies all documents from another store into the configured document store")
+                .arg(clap::Arg::new("source")
+                    .required(true)
+                    .help("Settings file describing the store to copy from, in the same form as [document_store]"))))
+        .subcommand(clap::Command::new("company-data")
+            .about("Fetches company data from the gateway")
+            .arg(clap::Arg::new("number")
+                .required(true)
+                .help("Company number, without any prefix"))
+            .arg(clap::Arg::new("auth-code")
+                .long("auth-code")
+                .takes_value(true)
+                .required(true)
+                .help("Company authentication code"))
+            .arg(clap::Arg::new("type")
+                .long("type")
+                .takes_value(true)
+                .possible_values(["EW", "SC", "NI", "R", "OC", "SO", "NC"])
+                .default_value("EW")
+                .help("Company type"))
+            .arg(clap::Arg::new("made-up-date")
+                .long("made-up-date")
+                .takes_value(true)
+                .help("Made up date, in YYYY-MM-DD format")))
+        .subcommand(clap::Command::new("keys")
+            .about("Manages encryption keys")
+            .subcommand_required(true)
+            .subcommand(clap::Command::new("rotate")
+                .about("Re-wraps stored data under the current encryption key, and encrypts data stored before a key was configured")))
+        .subcommand(clap::Command::new("xml")
+            .about("Works with GovTalk XML messages")
+            .subcommand_required(true)
+            .subcommand(clap::Command::new("render")
+                .about("Renders the GovTalk XML a gRPC request would send, without sending it")
+                .arg(clap::Arg::new("request")
+                    .required(true)
+                    .help("JSON file of the form {\"method\": \"<RPC name>\", \"request\": {...}}"))))
+}
+
+pub async fn run(service: grpc::CHFillingService, args: &clap::ArgMatches) -> Result<(), String> {
+    match args.subcommand() {
+        Some(("submissions", args)) => match args.subcommand() {
+            Some(("list", args)) => list_submissions(&service, args).await,
+            Some(("show", args)) => show_submission(&service, args.value_of("id").unwrap()).await,
+            _ => unreachable!()
+        },
+        Some(("status", args)) => match args.subcommand() {
+            Some(("refresh", args)) => refresh_status(&service, args.value_of("id").unwrap()).await,
+            _ => unreachable!()
+        },
+        Some(("document", args)) => match args.subcommand() {
+            Some(("fetch", args)) => fetch_document(&service, args.value_of("request-key").unwrap()).await,
+            Some(("migrate", args)) => migrate_documents(&service, args.value_of("source").unwrap()).await,
+            _ => unreachable!()
+        },
+        Some(("company-data", args)) => company_data(&service, args).await,
+        Some(("keys", args)) => match args.subcommand() {
+            Some(("rotate", _)) => rotate_keys(&service).await,
+            _ => unreachable!()
+        },
+        Some(("xml", args)) => match args.subcommand() {
+            Some(("render", args)) => render_xml(service, args.value_of("request").unwrap()).await,
+            _ => unreachable!()
+        },
+        _ => unreachable!()
+    }
+}
+
+async fn list_submissions(service: &grpc::CHFillingService, args: &clap::ArgMatches) -> Result<(), String> {
+    let limit: i64 = match args.value_of("limit").unwrap().parse() {
+        Ok(l) => l,
+        Err(_) => return Err("Invalid limit".to_string())
+    };
+
+    let mut query = schema::submissions::dsl::submissions
+        .order_by(schema::submissions::dsl::received_timestamp.desc())
+        .limit(limit)
+        .into_boxed();
+    if let Some(status) = args.value_of("status") {
+        query = query.filter(schema::submissions::dsl::status.eq(match status {
+            "pending" => schema::Status::Pending,
+            "accepted" => schema::Status::Accepted,
+            "rejected" => schema::Status::Rejected,
+            "parked" => schema::Status::Parked,
+            "internal_failure" => schema::Status::InternalFailure,
+            "unsent" => schema::Status::Unsent,
+            _ => unreachable!()
+        }));
+    }
+
+    let submissions = match query.get_results_async::<models::Submission>(&service.connection).await {
+        Ok(s) => s,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+
+    for submission in submissions {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            submission.id,
+            submission.ch_submission_id,
+            submission.received_timestamp,
+            metrics::status_label(&submission.status),
+            submission.form_type.as_deref().unwrap_or("-"),
+            submission.company_number.as_deref().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
+
+async fn show_submission(service: &grpc::CHFillingService, submission_id: &str) -> Result<(), String> {
+    let res = match service.submission_status(tonic::Request::new(ch_ewf_grpc::form_submission::SubmissionStatusRequest {
+        submission_id: submission_id.to_string(),
+    })).await {
+        Ok(r) => r.into_inner(),
+        Err(err) => return Err(err.message().to_string())
+    };
+    println!("{:#?}", res);
+    Ok(())
+}
+
+async fn refresh_status(service: &grpc::CHFillingService, submission_id: &str) -> Result<(), String> {
+    let res = match service.refresh_submission_status(tonic::Request::new(ch_ewf_grpc::form_submission::SubmissionStatusRequest {
+        submission_id: submission_id.to_string(),
+    })).await {
+        Ok(r) => r.into_inner(),
+        Err(err) => return Err(err.message().to_string())
+    };
+    println!("{:#?}", res);
+    Ok(())
+}
+
+async fn fetch_document(service: &grpc::CHFillingService, request_key: &str) -> Result<(), String> {
+    let document_id = service.get_document(request_key).await?;
+    println!("{}", document_id);
+    Ok(())
+}
+
+async fn migrate_documents(service: &grpc::CHFillingService, source: &str) -> Result<(), String> {
+    let source: document_store::DocumentStoreConfig = match config::Config::builder()
+        .add_source(config::File::with_name(source))
+        .build()
+        .and_then(|c| c.try_deserialize()) {
+        Ok(s) => s,
+        Err(err) => return Err(format!("Invalid source store settings: {}", err))
+    };
+    let source = source.build()?;
+
+    let documents = match schema::documents::dsl::documents
+        .get_results_async::<models::Documents>(&service.connection).await {
+        Ok(d) => d,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let total = documents.len();
+    for document in documents {
+        let file_data = source.get(&document.storage_filename).await
+            .map_err(|err| format!("Unable to read document {}: {}", document.id, err))?;
+
+        // Check the copy against the recorded hash, or record one for documents stored before hashes were
+        let contents = service.decrypt_at_rest(file_data.clone(), document.encrypted).await
+            .map_err(|err| format!("Unable to decrypt document {}: {}", document.id, err))?;
+        let content_hash = document_store::content_hash(&contents);
+        match &document.content_hash {
+            Some(h) if h != &content_hash => {
+                return Err(format!("Document {} does not match its content hash", document.id));
+            }
+            Some(_) => {}
+            None => {
+                if let Err(err) = diesel::update(schema::documents::dsl::documents.find(document.id))
+                    .set(schema::documents::dsl::content_hash.eq(content_hash))
+                    .execute_async(&service.connection).await {
+                    return Err(format!("Unable to access DB: {}", err));
+                }
+            }
+        }
+
+        service.document_store.put(&document.storage_filename, file_data).await
+            .map_err(|err| format!("Unable to write document {}: {}", document.id, err))?;
+        println!("{}\t{}", document.id, document.storage_filename);
+    }
+    println!("{} documents copied", total);
+
+    Ok(())
+}
+
+async fn company_data(service: &grpc::CHFillingService, args: &clap::ArgMatches) -> Result<(), String> {
+    let made_up_date = match args.value_of("made-up-date") {
+        Some(d) => match chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d") {
+            Ok(d) => grpc::chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(d.and_hms(0, 0, 0), chrono::Utc))),
+            Err(_) => return Err("Invalid made up date".to_string())
+        },
+        None => None
+    };
+
+    let company_number: u32 = match args.value_of("number").unwrap().parse() {
+        Ok(n) => n,
+        Err(_) => return Err("Invalid company number".to_string())
+    };
+
+    let res = match service.company_data(tonic::Request::new(ch_ewf_grpc::company_data::CompanyDataRequest {
+        company_number,
+        company_type: match args.value_of("type").unwrap() {
+            "EW" => ch_ewf_grpc::base_types::CompanyType::CompanyEnglandAndWales,
+            "SC" => ch_ewf_grpc::base_types::CompanyType::CompanyScotland,
+            "NI" => ch_ewf_grpc::base_types::CompanyType::CompanyNorthernIreland,
+            "R" => ch_ewf_grpc::base_types::CompanyType::CompanyIreland,
+            "OC" => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipEnglandAndWales,
+            "SO" => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipScotland,
+            "NC" => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipNorthernIreland,
+            _ => unreachable!()
+        }.into(),
+        authentication_code: args.value_of("auth-code").unwrap().to_string(),
+        made_up_date,
+        managed_company_id: String::new(),
+    })).await {
+        Ok(r) => r.into_inner(),
+        Err(err) => return Err(err.message().to_string())
+    };
+    println!("{:#?}", res);
+    Ok(())
+}
+
+/// Encrypts plaintext data, or re-wraps already encrypted data under the current key,
+/// returning `None` if nothing needs changing
+async fn rotate_data(cipher: &crypto::Cipher, data: &[u8], encrypted: bool) -> Result<Option<Vec<u8>>, String> {
+    if encrypted {
+        cipher.rewrap(data).await
+    } else {
+        cipher.encrypt(data).await.map(Some)
+    }
+}
+
+async fn rotate_keys(service: &grpc::CHFillingService) -> Result<(), String> {
+    let cipher = match &service.cipher {
+        Some(c) => c,
+        None => return Err("No encryption key configured".to_string())
+    };
+
+    let companies = match schema::managed_companies::dsl::managed_companies
+        .get_results_async::<models::ManagedCompany>(&service.connection).await {
+        Ok(c) => c,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for company in companies {
+        if let Some(authentication_code) = cipher.rewrap(&company.authentication_code).await
+            .map_err(|err| format!("Unable to rotate managed company {}: {}", company.id, err))? {
+            if let Err(err) = diesel::update(schema::managed_companies::dsl::managed_companies.find(company.id))
+                .set(schema::managed_companies::dsl::authentication_code.eq(authentication_code))
+                .execute_async(&service.connection).await {
+                return Err(format!("Unable to access DB: {}", err));
+            }
+            rotated += 1;
+        }
+    }
+    println!("Managed companies: {} rotated", rotated);
+
+    let submissions = match schema::submissions::dsl::submissions
+        .filter(schema::submissions::dsl::authentication_code.is_not_null()
+            .or(schema::submissions::dsl::authentication_code_encrypted.is_not_null()))
+        .get_results_async::<models::Submission>(&service.connection).await {
+        Ok(s) => s,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for submission in submissions {
+        let authentication_code = match (&submission.authentication_code_encrypted, &submission.authentication_code) {
+            (Some(c), _) => cipher.rewrap(c).await,
+            (None, Some(c)) => cipher.encrypt(c.as_bytes()).await.map(Some),
+            (None, None) => Ok(None)
+        }.map_err(|err| format!("Unable to rotate submission {}: {}", submission.id, err))?;
+        if let Some(authentication_code) = authentication_code {
+            if let Err(err) = diesel::update(schema::submissions::dsl::submissions.find(submission.id))
+                .set((
+                    schema::submissions::dsl::authentication_code_encrypted.eq(authentication_code),
+                    schema::submissions::dsl::authentication_code.eq(None::<String>),
+                ))
+                .execute_async(&service.connection).await {
+                return Err(format!("Unable to access DB: {}", err));
+            }
+            rotated += 1;
+        }
+    }
+    println!("Submission authentication codes: {} rotated", rotated);
+
+    // Snapshots can be large, so only load the data of one at a time
+    let snapshot_ids = match schema::company_snapshots::dsl::company_snapshots
+        .select(schema::company_snapshots::dsl::id)
+        .get_results_async::<uuid::Uuid>(&service.connection).await {
+        Ok(s) => s,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for snapshot_id in snapshot_ids {
+        let (data, encrypted) = match schema::company_snapshots::dsl::company_snapshots
+            .find(snapshot_id)
+            .select((schema::company_snapshots::dsl::data, schema::company_snapshots::dsl::data_encrypted))
+            .get_result_async::<(Vec<u8>, bool)>(&service.connection).await {
+            Ok(s) => s,
+            Err(err) => return Err(format!("Unable to access DB: {}", err))
+        };
+        if let Some(data) = rotate_data(cipher, &data, encrypted).await
+            .map_err(|err| format!("Unable to rotate snapshot {}: {}", snapshot_id, err))? {
+            if let Err(err) = diesel::update(schema::company_snapshots::dsl::company_snapshots.find(snapshot_id))
+                .set((
+                    schema::company_snapshots::dsl::data.eq(data),
+                    schema::company_snapshots::dsl::data_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await {
+                return Err(format!("Unable to access DB: {}", err));
+            }
+            rotated += 1;
+        }
+    }
+    println!("Company data snapshots: {} rotated", rotated);
+
+    let bulk_items = match schema::bulk_submission_items::dsl::bulk_submission_items
+        .filter(schema::bulk_submission_items::dsl::request.is_not_null())
+        .get_results_async::<models::BulkSubmissionItem>(&service.connection).await {
+        Ok(i) => i,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for item in bulk_items {
+        let request = match &item.request {
+            Some(r) => r,
+            None => continue
+        };
+        if let Some(request) = rotate_data(cipher, request, item.request_encrypted).await
+            .map_err(|err| format!("Unable to rotate bulk submission item {}: {}", item.id, err))? {
+            // Only rotate items that haven't been picked up for submission since they were loaded
+            if let Err(err) = diesel::update(schema::bulk_submission_items::dsl::bulk_submission_items
+                .find(item.id)
+                .filter(schema::bulk_submission_items::dsl::request.is_not_null()))
+                .set((
+                    schema::bulk_submission_items::dsl::request.eq(request),
+                    schema::bulk_submission_items::dsl::request_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await {
+                return Err(format!("Unable to access DB: {}", err));
+            }
+            rotated += 1;
+        }
+    }
+    println!("Bulk submission items: {} rotated", rotated);
+
+    let workflows = match schema::incorporation_workflows::dsl::incorporation_workflows
+        .get_results_async::<models::IncorporationWorkflow>(&service.connection).await {
+        Ok(w) => w,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for workflow in workflows {
+        let draft = rotate_data(cipher, &workflow.draft, workflow.draft_encrypted).await
+            .map_err(|err| format!("Unable to rotate incorporation workflow {}: {}", workflow.id, err))?;
+        let authentication_code = match &workflow.authentication_code {
+            Some(c) => rotate_data(cipher, c, workflow.authentication_code_encrypted).await
+                .map_err(|err| format!("Unable to rotate incorporation workflow {}: {}", workflow.id, err))?,
+            None => None
+        };
+        // Only rotate what hasn't been changed by the workflow since it was loaded
+        let res = match (draft, authentication_code) {
+            (Some(draft), Some(authentication_code)) => diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows
+                .find(workflow.id)
+                .filter(schema::incorporation_workflows::dsl::draft.eq(workflow.draft))
+                .filter(schema::incorporation_workflows::dsl::authentication_code.eq(workflow.authentication_code)))
+                .set((
+                    schema::incorporation_workflows::dsl::draft.eq(draft),
+                    schema::incorporation_workflows::dsl::draft_encrypted.eq(true),
+                    schema::incorporation_workflows::dsl::authentication_code.eq(authentication_code),
+                    schema::incorporation_workflows::dsl::authentication_code_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await,
+            (Some(draft), None) => diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows
+                .find(workflow.id)
+                .filter(schema::incorporation_workflows::dsl::draft.eq(workflow.draft)))
+                .set((
+                    schema::incorporation_workflows::dsl::draft.eq(draft),
+                    schema::incorporation_workflows::dsl::draft_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await,
+            (None, Some(authentication_code)) => diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows
+                .find(workflow.id)
+                .filter(schema::incorporation_workflows::dsl::authentication_code.eq(workflow.authentication_code)))
+                .set((
+                    schema::incorporation_workflows::dsl::authentication_code.eq(authentication_code),
+                    schema::incorporation_workflows::dsl::authentication_code_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await,
+            (None, None) => continue
+        };
+        match res {
+            Ok(0) => println!("Incorporation workflow {} changed during rotation, run rotation again", workflow.id),
+            Ok(_) => rotated += 1,
+            Err(err) => return Err(format!("Unable to access DB: {}", err))
+        }
+    }
+    println!("Incorporation workflows: {} rotated", rotated);
+
+    let register_entries = match schema::register_entries::dsl::register_entries
+        .filter(schema::register_entries::dsl::personal_details.is_not_null())
+        .select((
+            schema::register_entries::dsl::id,
+            schema::register_entries::dsl::personal_details,
+            schema::register_entries::dsl::personal_details_encrypted,
+        ))
+        .get_results_async::<(uuid::Uuid, Option<Vec<u8>>, bool)>(&service.connection).await {
+        Ok(e) => e,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for (entry_id, personal_details, encrypted) in register_entries {
+        let personal_details = match personal_details {
+            Some(p) => p,
+            None => continue
+        };
+        if let Some(personal_details) = rotate_data(cipher, &personal_details, encrypted).await
+            .map_err(|err| format!("Unable to rotate register entry {}: {}", entry_id, err))? {
+            if let Err(err) = diesel::update(schema::register_entries::dsl::register_entries.find(entry_id))
+                .set((
+                    schema::register_entries::dsl::personal_details.eq(personal_details),
+                    schema::register_entries::dsl::personal_details_encrypted.eq(true),
+                ))
+                .execute_async(&service.connection).await {
+                return Err(format!("Unable to access DB: {}", err));
+            }
+            rotated += 1;
+        }
+    }
+    println!("Register entries: {} rotated", rotated);
+
+    let documents = match schema::documents::dsl::documents
+        .get_results_async::<models::Documents>(&service.connection).await {
+        Ok(d) => d,
+        Err(err) => return Err(format!("Unable to access DB: {}", err))
+    };
+    let mut rotated = 0;
+    for document in documents {
+        let file_data = service.document_store.get(&document.storage_filename).await
+            .map_err(|err| format!("Unable to read document {}: {}", document.id, err))?;
+        if let Some(file_data) = rotate_data(cipher, &file_data, document.encrypted).await
+            .map_err(|err| format!("Unable to rotate document {}: {}", document.id, err))? {
+            service.document_store.put(&document.storage_filename, file_data).await
+                .map_err(|err| format!("Unable to write document {}: {}", document.id, err))?;
+            if !document.encrypted {
+                if let Err(err) = diesel::update(schema::documents::dsl::documents.find(document.id))
+                    .set(schema::documents::dsl::encrypted.eq(true))
+                    .execute_async(&service.connection).await {
+                    return Err(format!("Unable to access DB: {}", err));
+                }
+            }
+            rotated += 1;
+        }
+    }
+    println!("Documents: {} rotated", rotated);
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct RenderRequest {
+    method: String,
+    request: serde_json::Value,
+}
+
+fn decode_request<T: prost::Message + Default>(
+    method: &prost_reflect::MethodDescriptor, request: serde_json::Value,
+) -> Result<T, tonic::Status> {
+    let message = match prost_reflect::DynamicMessage::deserialize(method.input(), request) {
+        Ok(m) => m,
+        Err(err) => return Err(tonic::Status::invalid_argument(format!("Invalid request: {}", err)))
+    };
+    match T::decode(prost::Message::encode_to_vec(&message).as_slice()) {
+        Ok(m) => Ok(m),
+        Err(err) => Err(tonic::Status::invalid_argument(format!("Invalid request: {}", err)))
+    }
+}
+
+macro_rules! render_methods {
+    ($service:expr, $method:expr, $request:expr, { $($name:literal => $func:ident),* $(,)? }) => {
+        match $method.name() {
+            $($name => $service.$func(tonic::Request::new(decode_request(&$method, $request)?)).await.map(|_| ()),)*
+            m => Err(tonic::Status::invalid_argument(format!("{} does not send a gateway request", m)))
+        }
+    }
+}
+
+async fn render_request(
+    service: &grpc::CHFillingService, method: prost_reflect::MethodDescriptor, request: serde_json::Value,
+) -> Result<(), tonic::Status> {
+    render_methods!(service, method, request, {
+        "CompanyData" => company_data,
+        "GetEReminders" => get_e_reminders,
+        "SetEReminders" => set_e_reminders,
+        "PaymentPeriods" => payment_periods,
+        "MembersRegister" => members_register,
+        "ChargeSearch" => charge_search,
+        "ChangeRegisteredOffice" => change_registered_office,
+        "SAILAddress" => sail_address,
+        "ChangeOfLocation" => change_of_location,
+        "OfficerAppointment" => officer_appointment,
+        "OfficerResignation" => officer_resignation,
+        "OfficerChange" => officer_change,
+        "ReturnOfAllotmentShares" => return_of_allotment_shares,
+        "IncreaseNominalCapital" => increase_nominal_capital,
+        "AccountingReferenceDate" => accounting_reference_date,
+        "CompanyIncorporation" => company_incorporation,
+        "ChangeOfName" => change_of_name,
+        "ConfirmationStatement" => confirmation_statement,
+        "PSCNotification" => psc_notification,
+        "PSCChangeDetails" => psc_change_details,
+        "PSCCessation" => psc_cessation,
+        "PSCStatementNotification" => psc_statement_notification,
+        "PSCStatementWithdrawal" => psc_statement_withdrawal,
+        "RegisterElectOrWithdraw" => register_elect_or_withdraw,
+        "MembersRegisterElectOrWithdraw" => members_register_elect_or_withdraw,
+        "MembersRegisterUpdate" => members_register_update,
+        "ChargeRegistration" => charge_registration,
+        "ChargeUpdate" => charge_update,
+    })
+}
+
+async fn render_xml(service: grpc::CHFillingService, path: &str) -> Result<(), String> {
+    let request_data = match tokio::fs::read(path).await {
+        Ok(d) => d,
+        Err(err) => return Err(format!("Unable to read request: {}", err))
+    };
+    let request: RenderRequest = match serde_json::from_slice(&request_data) {
+        Ok(r) => r,
+        Err(err) => return Err(format!("Invalid request file: {}", err))
+    };
+
+    let method = match DESCRIPTOR_POOL.get_service_by_name("ch_ewf.CHFilling")
+        .and_then(|s| s.methods().find(|m| m.name() == request.method)) {
+        Some(m) => m,
+        None => return Err(format!("Unknown method {}", request.method))
+    };
+
+    let (sender, rendered) = service.sender.dry_run();
+    let service = grpc::CHFillingService {
+        sender,
+        ..service
+    };
+
+    let res = render_request(&service, method, request.request).await;
+    let rendered = rendered.lock().unwrap().take();
+    match (rendered, res) {
+        (Some(xml), _) => {
+            println!("{}", xml);
+            Ok(())
+        }
+        (None, Err(err)) => Err(err.message().to_string()),
+        (None, Ok(())) => Err("No gateway request was made".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(name: &str, key_file: &str) -> crypto::Cipher {
+        let path = std::env::temp_dir().join(format!("ch-ewf-{}-{}.keys", name, std::process::id()));
+        std::fs::write(&path, key_file).unwrap();
+        let cipher = crypto::Cipher::from_key_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        cipher.unwrap()
+    }
+
+    #[tokio::test]
+    async fn workflow_draft_decrypts_after_rotation() {
+        let old_key = base64::encode([1; 32]);
+        let new_key = base64::encode([2; 32]);
+        let old = cipher("old", &format!("2026-01 {}\n", old_key));
+        let rotating = cipher("rotating", &format!("2026-01 {}\n2026-10 {}\n", old_key, new_key));
+        let rotated = cipher("rotated", &format!("2026-10 {}\n", new_key));
+
+        let draft = old.encrypt(b"draft").await.unwrap();
+        let draft = rotate_data(&rotating, &draft, true).await.unwrap().unwrap();
+        assert_eq!(rotated.decrypt(&draft).await.unwrap(), b"draft");
+        assert_eq!(rotate_data(&rotated, &draft, true).await.unwrap(), None);
+
+        // Drafts stored before a key was configured are encrypted
+        let draft = rotate_data(&rotating, b"draft", false).await.unwrap().unwrap();
+        assert_eq!(rotated.decrypt(&draft).await.unwrap(), b"draft");
+    }
+}
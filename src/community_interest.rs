@@ -0,0 +1,127 @@
+use super::{ch_ewf_grpc, constitution, names, pdf};
+use ch_ewf_grpc::company_incorporation::{self as incorporation, CompanyType, Articles};
+
+/// Whether an incorporation is of a community interest company, either from its statement or a CIC36 given as is
+pub fn is_community_interest(msg: &incorporation::CompanyIncorporation) -> bool {
+    msg.community_interest_statement.is_some() || msg.cic36.is_some()
+}
+
+/// The legal form an incorporation's name is checked against
+pub fn name_kind(msg: &incorporation::CompanyIncorporation) -> Option<names::CompanyKind> {
+    let kind: names::CompanyKind = CompanyType::from_i32(msg.company_type)?.into();
+    if is_community_interest(msg) {
+        // Forms that can't be a CIC are reported by check, so the name is checked as usual
+        Some(kind.community_interest().unwrap_or(kind))
+    } else {
+        Some(kind)
+    }
+}
+
+/// Problems with incorporating a company as a community interest company
+pub fn check(msg: &incorporation::CompanyIncorporation) -> Vec<String> {
+    let mut errors = vec![];
+    if !is_community_interest(msg) {
+        return errors;
+    }
+
+    match CompanyType::from_i32(msg.company_type) {
+        Some(CompanyType::LimitedByShares) | Some(CompanyType::LimitedByGuarantee) | Some(CompanyType::Plc) => {}
+        Some(CompanyType::LimitedByGuaranteeExempt) =>
+            errors.push("A community interest company can't be exempt from using \"limited\"".to_string()),
+        _ => errors.push("An LLP can't be a community interest company".to_string())
+    }
+
+    if let Some(statement) = &msg.community_interest_statement {
+        if statement.community.trim().is_empty() {
+            errors.push("Community statement required: who the company's activities will benefit".to_string());
+        }
+        if statement.activities.trim().is_empty() {
+            errors.push("Community statement required: the company's activities".to_string());
+        }
+        if statement.community_benefit.trim().is_empty() {
+            errors.push("Community statement required: how the activities will benefit the community".to_string());
+        }
+        if !statement.not_excluded_company {
+            errors.push("Declaration required that the company won't be an excluded company".to_string());
+        }
+        if !statement.articles_include_asset_lock {
+            errors.push("Declaration required that the articles include the asset lock provisions".to_string());
+        }
+    }
+
+    // The model articles don't contain the asset lock, so the articles must always be filed
+    match Articles::from_i32(msg.articles) {
+        Some(Articles::AmendedByShares) | Some(Articles::AmendedByGuarantee) | Some(Articles::AmendedPlc)
+        | Some(Articles::Bespoke) => if msg.articles_doc.is_none() {
+            errors.push("Articles containing the asset lock provisions required".to_string());
+        },
+        _ => errors.push(
+            "A community interest company needs amended or bespoke articles containing the asset lock provisions".to_string()
+        )
+    }
+    errors
+}
+
+fn or_none(text: &str) -> &str {
+    if text.trim().is_empty() {
+        "None"
+    } else {
+        text
+    }
+}
+
+/// The CIC36 declarations and community interest statement, authenticated by each subscriber
+pub fn cic36(
+    msg: &incorporation::CompanyIncorporation, statement: &incorporation::CommunityInterestStatement,
+    date: chrono::NaiveDate,
+) -> Result<Vec<u8>, String> {
+    let (company_type, signatories): (&str, Vec<String>) = match CompanyType::from_i32(msg.company_type) {
+        Some(CompanyType::LimitedByShares) => ("Private company limited by shares",
+                                               msg.subscribers.iter().map(|s| constitution::subscriber_name(&s.person)).collect()),
+        Some(CompanyType::Plc) => ("Public limited company",
+                                   msg.subscribers.iter().map(|s| constitution::subscriber_name(&s.person)).collect()),
+        Some(CompanyType::LimitedByGuarantee) => ("Private company limited by guarantee",
+                                                  msg.guarantors.iter().map(|g| constitution::subscriber_name(&g.person)).collect()),
+        _ => return Err("Only companies limited by shares or guarantee can be community interest companies".to_string())
+    };
+    if signatories.is_empty() {
+        return Err("No subscribers to make the declarations".to_string());
+    }
+
+    let company_name = msg.company_name.to_uppercase();
+    let mut doc = pdf::Document::new(&format!("CIC36 {}", company_name), false)?;
+    doc.heading("Declarations on formation of a community interest company");
+    doc.table(&["Company name", "Company type"], &[vec![company_name, company_type.to_string()]]);
+
+    doc.heading("Part 1 - Community interest statement");
+    doc.paragraph(&format!("The company's activities will provide benefit to: {}", statement.community));
+    doc.paragraph(&format!("The company's activities are: {}", statement.activities));
+    doc.paragraph(&format!("How the activities will benefit the community: {}", statement.community_benefit));
+    if !statement.surplus_use.trim().is_empty() {
+        doc.paragraph(&format!("How any surplus will be used: {}", statement.surplus_use));
+    }
+
+    doc.heading("Part 2 - Consultation with stakeholders");
+    doc.paragraph(or_none(&statement.stakeholder_consultation));
+    doc.heading("Part 3 - Directors' remuneration");
+    doc.paragraph(or_none(&statement.directors_remuneration));
+    doc.heading("Part 4 - Transfers of assets other than for full consideration");
+    doc.paragraph(or_none(&statement.asset_transfers));
+
+    doc.heading("Part 5 - Declarations");
+    doc.paragraph(
+        "We, the subscribers, declare that the company will carry on its activities for the benefit of the community \
+         described above, and that it will not be an excluded company as defined by regulation 6 of the Community \
+         Interest Company Regulations 2005."
+    );
+    doc.paragraph(
+        "The company's articles include the asset lock provisions required by the Community Interest Company \
+         Regulations 2005."
+    );
+    doc.table(
+        &["Name of each subscriber", "Authentication by each subscriber"],
+        &signatories.into_iter().map(|s| vec![s, "Authenticated electronically".to_string()]).collect::<Vec<_>>(),
+    );
+    doc.paragraph(&format!("Dated {}", date.format("%-d %B %Y")));
+    doc.finish()
+}
@@ -0,0 +1,518 @@
+use super::{ch_ewf_grpc, schema, models};
+use super::grpc::{chrono_to_proto, proto_to_chrono};
+use chrono::Datelike;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+pub struct SnapshotRows {
+    pub snapshot: models::CompanySnapshot,
+    pub officers: Vec<models::CompanySnapshotOfficer>,
+    pub pscs: Vec<models::CompanySnapshotPSC>,
+    pub shareholdings: Vec<models::CompanySnapshotShareholding>,
+    pub sic_codes: Vec<models::CompanySnapshotSICCode>,
+    pub registered_office: Option<models::CompanySnapshotRegisteredOffice>,
+    pub capital: Vec<models::CompanySnapshotCapital>,
+}
+
+fn proto_date(date: Option<prost_types::Timestamp>) -> Option<chrono::NaiveDate> {
+    proto_to_chrono(date).map(|d| d.naive_utc().date())
+}
+
+/// Officer rows only need the month and year of birth to tell officers apart, as on the public register
+fn birth_month(date: Option<prost_types::Timestamp>) -> Option<chrono::NaiveDate> {
+    proto_date(date).and_then(|d| d.with_day(1))
+}
+
+fn date_proto(date: Option<chrono::NaiveDate>) -> Option<prost_types::Timestamp> {
+    chrono_to_proto::<chrono::Utc>(date.map(|d| chrono::DateTime::from_utc(d.and_hms(0, 0, 0), chrono::Utc)))
+}
+
+pub fn person_name(name: &Option<ch_ewf_grpc::base_types::PersonName>) -> String {
+    match name {
+        Some(n) => std::iter::once(n.title.as_str())
+            .chain(n.forenames.iter().map(|f| f.as_str()))
+            .chain(std::iter::once(n.surname.as_str()))
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => String::new()
+    }
+}
+
+fn shareholder_name(shareholder: &ch_ewf_grpc::base_types::Shareholder) -> String {
+    match &shareholder.name {
+        Some(ch_ewf_grpc::base_types::shareholder::Name::PartsName(n)) => n.forenames.iter()
+            .map(|f| f.as_str())
+            .chain(std::iter::once(n.surname.as_str()))
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(ch_ewf_grpc::base_types::shareholder::Name::AmalgamatedName(n)) => n.clone(),
+        None => String::new()
+    }
+}
+
+pub fn nature_of_controls(natures: &Option<ch_ewf_grpc::psc::NatureOfControls>) -> Vec<String> {
+    match natures.as_ref().and_then(|n| n.nature_of_controls.as_ref()) {
+        Some(ch_ewf_grpc::psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(n)) => n.nature_of_controls.iter()
+            .filter_map(|c| ch_ewf_grpc::psc::company_nature_of_controls::NatureOfControl::from_i32(*c))
+            .map(|c| format!("{:?}", c))
+            .collect(),
+        Some(ch_ewf_grpc::psc::nature_of_controls::NatureOfControls::LlpNatureOfControls(n)) => n.nature_of_controls.iter()
+            .filter_map(|c| ch_ewf_grpc::psc::llp_nature_of_controls::NatureOfControl::from_i32(*c))
+            .map(|c| format!("{:?}", c))
+            .collect(),
+        None => vec![]
+    }
+}
+
+/// Breaks a company data response down into rows for the snapshot tables
+pub fn snapshot_rows(
+    snapshot_id: uuid::Uuid, company_number: String, data: &ch_ewf_grpc::company_data::CompanyDataResponse,
+) -> SnapshotRows {
+    let mut officers = vec![];
+    for director in &data.directors {
+        let (name, corporate, date_of_birth) = match &director.value {
+            Some(ch_ewf_grpc::company_data::director::Value::Person(p)) =>
+                (person_name(&p.person), false, birth_month(p.date_of_birth.clone())),
+            Some(ch_ewf_grpc::company_data::director::Value::Corporate(c)) => (c.corporate_name.clone(), true, None),
+            None => continue
+        };
+        officers.push(models::CompanySnapshotOfficer {
+            id: uuid::Uuid::new_v4(),
+            snapshot_id,
+            role: "director".to_string(),
+            name,
+            corporate,
+            date_of_birth,
+            appointment_date: proto_date(director.appointment_date.clone()),
+            resignation_date: proto_date(director.resignation_date.clone()),
+            designated: false,
+        });
+    }
+    for secretary in &data.secretaries {
+        let (name, corporate) = match &secretary.value {
+            Some(ch_ewf_grpc::company_data::secretary::Value::Person(p)) => (person_name(&p.person), false),
+            Some(ch_ewf_grpc::company_data::secretary::Value::Corporate(c)) => (c.corporate_name.clone(), true),
+            None => continue
+        };
+        officers.push(models::CompanySnapshotOfficer {
+            id: uuid::Uuid::new_v4(),
+            snapshot_id,
+            role: "secretary".to_string(),
+            name,
+            corporate,
+            date_of_birth: None,
+            appointment_date: proto_date(secretary.appointment_date.clone()),
+            resignation_date: proto_date(secretary.resignation_date.clone()),
+            designated: false,
+        });
+    }
+    for member in &data.members {
+        let (name, corporate, date_of_birth) = match &member.value {
+            Some(ch_ewf_grpc::company_data::member::Value::Person(p)) =>
+                (person_name(&p.person), false, birth_month(p.date_of_birth.clone())),
+            Some(ch_ewf_grpc::company_data::member::Value::Corporate(c)) => (c.corporate_name.clone(), true, None),
+            None => continue
+        };
+        officers.push(models::CompanySnapshotOfficer {
+            id: uuid::Uuid::new_v4(),
+            snapshot_id,
+            role: "member".to_string(),
+            name,
+            corporate,
+            date_of_birth,
+            appointment_date: None,
+            resignation_date: None,
+            designated: member.designated,
+        });
+    }
+
+    let mut pscs = vec![];
+    match &data.pscs {
+        Some(ch_ewf_grpc::company_data::company_data_response::Pscs::PscStatement(s)) => {
+            pscs.push(models::CompanySnapshotPSC {
+                id: uuid::Uuid::new_v4(),
+                snapshot_id,
+                kind: "company_statement".to_string(),
+                name: ch_ewf_grpc::psc::CompanyLevelStatement::from_i32(*s).map(|s| format!("{:?}", s)).unwrap_or_default(),
+                notification_date: None,
+                cessation_date: None,
+                nature_of_controls: vec![],
+            });
+        }
+        Some(ch_ewf_grpc::company_data::company_data_response::Pscs::CompanyPscs(p)) => {
+            for psc in &p.pscs {
+                let (kind, name, notification_date, cessation_date, natures) = match &psc.psc {
+                    Some(ch_ewf_grpc::company_data::company_psc::Psc::StatementNotification(s)) => (
+                        "psc_statement",
+                        ch_ewf_grpc::psc::PscLevelStatement::from_i32(*s).map(|s| format!("{:?}", s)).unwrap_or_default(),
+                        None, None, vec![]
+                    ),
+                    Some(ch_ewf_grpc::company_data::company_psc::Psc::LinkedStatementNotification(l)) => (
+                        "linked_statement",
+                        match &l.psc {
+                            Some(ch_ewf_grpc::psc::linked_statement::Psc::Individual(i)) => person_name(&i.name),
+                            Some(ch_ewf_grpc::psc::linked_statement::Psc::CorporateName(n)) => n.clone(),
+                            Some(ch_ewf_grpc::psc::linked_statement::Psc::LegalPersonName(n)) => n.clone(),
+                            Some(ch_ewf_grpc::psc::linked_statement::Psc::SuperSecureIndividual(_)) | None => String::new(),
+                        },
+                        None, None, vec![]
+                    ),
+                    Some(ch_ewf_grpc::company_data::company_psc::Psc::SuperSecureIndividual(_)) => (
+                        "super_secure_individual", String::new(), None, None, vec![]
+                    ),
+                    Some(ch_ewf_grpc::company_data::company_psc::Psc::Notification(n)) => {
+                        let (kind, name) = match n.notification.as_ref().and_then(|n| n.psc.as_ref()) {
+                            Some(ch_ewf_grpc::psc::notification::Psc::Individual(i)) => ("individual", person_name(&i.person)),
+                            Some(ch_ewf_grpc::psc::notification::Psc::Corporate(c)) => ("corporate", c.corporate_name.clone()),
+                            Some(ch_ewf_grpc::psc::notification::Psc::LegalPerson(l)) => ("legal_person", l.name.clone()),
+                            None => continue
+                        };
+                        (
+                            kind, name,
+                            proto_date(n.notification_date.clone()),
+                            proto_date(n.cessation_date.clone()),
+                            nature_of_controls(&n.nature_of_controls)
+                        )
+                    }
+                    None => continue
+                };
+                pscs.push(models::CompanySnapshotPSC {
+                    id: uuid::Uuid::new_v4(),
+                    snapshot_id,
+                    kind: kind.to_string(),
+                    name,
+                    notification_date,
+                    cessation_date,
+                    nature_of_controls: natures,
+                });
+            }
+        }
+        None => {}
+    }
+
+    let shareholdings = data.shareholdings.iter().map(|s| models::CompanySnapshotShareholding {
+        id: uuid::Uuid::new_v4(),
+        snapshot_id,
+        share_class: s.share_class.clone(),
+        shareholders: s.shareholders.iter().map(shareholder_name).collect::<Vec<_>>().join("; "),
+        number_held: s.number_held,
+    }).collect();
+
+    let sic_codes = data.sic_codes.iter().map(|c| models::CompanySnapshotSICCode {
+        snapshot_id,
+        sic_code: c.clone(),
+    }).collect();
+
+    let registered_office = data.registered_office_address.as_ref().map(|a| models::CompanySnapshotRegisteredOffice {
+        snapshot_id,
+        premise: a.premise.clone(),
+        street: a.street.clone(),
+        thoroughfare: a.thoroughfare.clone(),
+        post_town: a.post_town.clone(),
+        county: a.county.clone(),
+        country: ch_ewf_grpc::base_types::uk_address::Country::from_i32(a.country).map(|c| format!("{:?}", c)).unwrap_or_default(),
+        postcode: a.postcode.clone(),
+        care_of_name: a.care_of_name.clone(),
+        po_box: a.po_box.clone(),
+    });
+
+    let capital = data.statement_of_capital.iter().flat_map(|c| c.shares.iter().map(move |s| models::CompanySnapshotCapital {
+        id: uuid::Uuid::new_v4(),
+        snapshot_id,
+        currency: c.currency.clone(),
+        share_class: s.share_class.clone(),
+        prescribed_particulars: s.prescribed_particulars.clone(),
+        num_shares: s.num_shares,
+        aggregate_nominal_value: s.aggregate_nominal_value,
+        total_amount_unpaid: c.total_amount_unpaid,
+    })).collect();
+
+    SnapshotRows {
+        snapshot: models::CompanySnapshot {
+            id: snapshot_id,
+            company_number,
+            company_name: data.company_name.clone(),
+            transaction_id: data.transaction_id.clone(),
+            made_up_date: proto_date(data.made_up_date.clone()),
+            snapshot_timestamp: chrono::Utc::now().naive_utc(),
+            data: prost::Message::encode_to_vec(data),
+            data_encrypted: false,
+        },
+        officers,
+        pscs,
+        shareholdings,
+        sic_codes,
+        registered_office,
+        capital,
+    }
+}
+
+pub fn save_snapshot(conn: &diesel::pg::PgConnection, rows: SnapshotRows) -> QueryResult<()> {
+    conn.transaction(|| {
+        diesel::insert_into(schema::company_snapshots::table)
+            .values(&rows.snapshot)
+            .execute(conn)?;
+        diesel::insert_into(schema::company_snapshot_officers::table)
+            .values(&rows.officers)
+            .execute(conn)?;
+        diesel::insert_into(schema::company_snapshot_pscs::table)
+            .values(&rows.pscs)
+            .execute(conn)?;
+        diesel::insert_into(schema::company_snapshot_shareholdings::table)
+            .values(&rows.shareholdings)
+            .execute(conn)?;
+        diesel::insert_into(schema::company_snapshot_sic_codes::table)
+            .values(&rows.sic_codes)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if let Some(registered_office) = &rows.registered_office {
+            diesel::insert_into(schema::company_snapshot_registered_offices::table)
+                .values(registered_office)
+                .execute(conn)?;
+        }
+        diesel::insert_into(schema::company_snapshot_capital::table)
+            .values(&rows.capital)
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+pub fn load_snapshot(conn: &diesel::pg::PgConnection, snapshot_id: uuid::Uuid) -> QueryResult<Option<SnapshotRows>> {
+    let snapshot = match schema::company_snapshots::dsl::company_snapshots
+        .filter(schema::company_snapshots::dsl::id.eq(snapshot_id))
+        .get_result::<models::CompanySnapshot>(conn)
+        .optional()? {
+        Some(s) => s,
+        None => return Ok(None)
+    };
+
+    Ok(Some(SnapshotRows {
+        officers: schema::company_snapshot_officers::dsl::company_snapshot_officers
+            .filter(schema::company_snapshot_officers::dsl::snapshot_id.eq(snapshot_id))
+            .load(conn)?,
+        pscs: schema::company_snapshot_pscs::dsl::company_snapshot_pscs
+            .filter(schema::company_snapshot_pscs::dsl::snapshot_id.eq(snapshot_id))
+            .load(conn)?,
+        shareholdings: schema::company_snapshot_shareholdings::dsl::company_snapshot_shareholdings
+            .filter(schema::company_snapshot_shareholdings::dsl::snapshot_id.eq(snapshot_id))
+            .load(conn)?,
+        sic_codes: schema::company_snapshot_sic_codes::dsl::company_snapshot_sic_codes
+            .filter(schema::company_snapshot_sic_codes::dsl::snapshot_id.eq(snapshot_id))
+            .load(conn)?,
+        registered_office: schema::company_snapshot_registered_offices::dsl::company_snapshot_registered_offices
+            .filter(schema::company_snapshot_registered_offices::dsl::snapshot_id.eq(snapshot_id))
+            .get_result(conn)
+            .optional()?,
+        capital: schema::company_snapshot_capital::dsl::company_snapshot_capital
+            .filter(schema::company_snapshot_capital::dsl::snapshot_id.eq(snapshot_id))
+            .load(conn)?,
+        snapshot,
+    }))
+}
+
+pub fn decode_snapshot(data: &[u8]) -> Result<ch_ewf_grpc::company_data::CompanyDataResponse, prost::DecodeError> {
+    prost::Message::decode(data)
+}
+
+fn officer_summary(officer: &models::CompanySnapshotOfficer) -> ch_ewf_grpc::company_history::OfficerSummary {
+    ch_ewf_grpc::company_history::OfficerSummary {
+        role: officer.role.clone(),
+        name: officer.name.clone(),
+        corporate: officer.corporate,
+        date_of_birth: date_proto(officer.date_of_birth),
+        appointment_date: date_proto(officer.appointment_date),
+        resignation_date: date_proto(officer.resignation_date),
+        designated: officer.designated,
+    }
+}
+
+fn psc_summary(psc: &models::CompanySnapshotPSC) -> ch_ewf_grpc::company_history::PscSummary {
+    ch_ewf_grpc::company_history::PscSummary {
+        kind: psc.kind.clone(),
+        name: psc.name.clone(),
+        notification_date: date_proto(psc.notification_date),
+        cessation_date: date_proto(psc.cessation_date),
+        nature_of_controls: psc.nature_of_controls.clone(),
+    }
+}
+
+/// Pairs up items from two snapshots by key, returning the before and after of each. Items sharing a
+/// key are paired in order, and any left over on either side are removals or additions.
+fn pair_by_key<'a, T, K: std::hash::Hash + Eq>(
+    before: &'a [T], after: &'a [T], key: impl Fn(&T) -> K,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+    let mut after_map: HashMap<K, std::collections::VecDeque<usize>> = HashMap::new();
+    for (i, a) in after.iter().enumerate() {
+        after_map.entry(key(a)).or_default().push_back(i);
+    }
+    let mut paired = vec![false; after.len()];
+    let mut out = vec![];
+    for b in before {
+        let a = after_map.get_mut(&key(b)).and_then(|a| a.pop_front());
+        if let Some(i) = a {
+            paired[i] = true;
+        }
+        out.push((Some(b), a.map(|i| &after[i])));
+    }
+    for (a, paired) in after.iter().zip(paired) {
+        if !paired {
+            out.push((None, Some(a)));
+        }
+    }
+    out
+}
+
+fn change_type<T>(before: Option<T>, after: Option<T>) -> ch_ewf_grpc::company_history::ChangeType {
+    match (before, after) {
+        (None, _) => ch_ewf_grpc::company_history::ChangeType::Added,
+        (_, None) => ch_ewf_grpc::company_history::ChangeType::Removed,
+        _ => ch_ewf_grpc::company_history::ChangeType::Modified,
+    }
+}
+
+/// Computes the changes between two snapshots of the same company
+pub fn diff(
+    from: &SnapshotRows, from_data: &ch_ewf_grpc::company_data::CompanyDataResponse,
+    to: &SnapshotRows, to_data: &ch_ewf_grpc::company_data::CompanyDataResponse,
+) -> ch_ewf_grpc::company_history::DiffCompanyDataResponse {
+    let from_sic_codes = from.sic_codes.iter().map(|c| &c.sic_code).collect::<std::collections::HashSet<_>>();
+    let to_sic_codes = to.sic_codes.iter().map(|c| &c.sic_code).collect::<std::collections::HashSet<_>>();
+
+    ch_ewf_grpc::company_history::DiffCompanyDataResponse {
+        company_name: if from.snapshot.company_name != to.snapshot.company_name {
+            Some(ch_ewf_grpc::company_history::ValueChange {
+                before: from.snapshot.company_name.clone(),
+                after: to.snapshot.company_name.clone(),
+            })
+        } else {
+            None
+        },
+        registered_office: if from.registered_office.as_ref().map(|r| models::CompanySnapshotRegisteredOffice { snapshot_id: to.snapshot.id, ..r.clone() })
+            != to.registered_office {
+            Some(ch_ewf_grpc::company_history::AddressChange {
+                before: from_data.registered_office_address.clone(),
+                after: to_data.registered_office_address.clone(),
+            })
+        } else {
+            None
+        },
+        sic_codes_added: to_sic_codes.difference(&from_sic_codes).map(|c| c.to_string()).collect(),
+        sic_codes_removed: from_sic_codes.difference(&to_sic_codes).map(|c| c.to_string()).collect(),
+        officers: pair_by_key(&from.officers, &to.officers, |o| (o.role.clone(), o.name.to_lowercase(), o.date_of_birth))
+            .into_iter()
+            .filter(|(b, a)| match (b, a) {
+                (Some(b), Some(a)) => b.appointment_date != a.appointment_date || b.resignation_date != a.resignation_date ||
+                    b.designated != a.designated || b.corporate != a.corporate,
+                _ => true
+            })
+            .map(|(b, a)| ch_ewf_grpc::company_history::OfficerChange {
+                change: change_type(b, a).into(),
+                before: b.map(officer_summary),
+                after: a.map(officer_summary),
+            })
+            .collect(),
+        pscs: pair_by_key(&from.pscs, &to.pscs, |p| (p.kind.clone(), p.name.to_lowercase()))
+            .into_iter()
+            .filter(|(b, a)| match (b, a) {
+                (Some(b), Some(a)) => b.notification_date != a.notification_date || b.cessation_date != a.cessation_date ||
+                    b.nature_of_controls != a.nature_of_controls,
+                _ => true
+            })
+            .map(|(b, a)| ch_ewf_grpc::company_history::PscChange {
+                change: change_type(b, a).into(),
+                before: b.map(psc_summary),
+                after: a.map(psc_summary),
+            })
+            .collect(),
+        shareholdings: pair_by_key(&from.shareholdings, &to.shareholdings, |s| (s.share_class.clone(), s.shareholders.to_lowercase()))
+            .into_iter()
+            .filter(|(b, a)| match (b, a) {
+                (Some(b), Some(a)) => (b.number_held - a.number_held).abs() > f64::EPSILON,
+                _ => true
+            })
+            .map(|(b, a)| {
+                let s = a.or(b).unwrap();
+                ch_ewf_grpc::company_history::ShareholdingChange {
+                    change: change_type(b, a).into(),
+                    share_class: s.share_class.clone(),
+                    shareholders: s.shareholders.clone(),
+                    number_held_before: b.map(|b| b.number_held).unwrap_or_default(),
+                    number_held_after: a.map(|a| a.number_held).unwrap_or_default(),
+                }
+            })
+            .collect(),
+        capital: pair_by_key(&from.capital, &to.capital, |c| (c.currency.clone(), c.share_class.clone()))
+            .into_iter()
+            .filter(|(b, a)| match (b, a) {
+                (Some(b), Some(a)) => (b.num_shares - a.num_shares).abs() > f64::EPSILON ||
+                    (b.aggregate_nominal_value - a.aggregate_nominal_value).abs() > f64::EPSILON,
+                _ => true
+            })
+            .map(|(b, a)| {
+                let c = a.or(b).unwrap();
+                ch_ewf_grpc::company_history::CapitalChange {
+                    change: change_type(b, a).into(),
+                    currency: c.currency.clone(),
+                    share_class: c.share_class.clone(),
+                    num_shares_before: b.map(|b| b.num_shares).unwrap_or_default(),
+                    num_shares_after: a.map(|a| a.num_shares).unwrap_or_default(),
+                    aggregate_nominal_value_before: b.map(|b| b.aggregate_nominal_value).unwrap_or_default(),
+                    aggregate_nominal_value_after: a.map(|a| a.aggregate_nominal_value).unwrap_or_default(),
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs<'a>(before: &'a [(&'a str, u32)], after: &'a [(&'a str, u32)]) -> Vec<(Option<u32>, Option<u32>)> {
+        pair_by_key(before, after, |i| i.0)
+            .into_iter()
+            .map(|(b, a)| (b.map(|b| b.1), a.map(|a| a.1)))
+            .collect()
+    }
+
+    #[test]
+    fn pairs_by_key() {
+        assert_eq!(
+            pairs(&[("a", 1), ("b", 2)], &[("c", 3), ("b", 4)]),
+            vec![(Some(1), None), (Some(2), Some(4)), (None, Some(3))]
+        );
+    }
+
+    #[test]
+    fn pairs_duplicate_keys_in_order() {
+        assert_eq!(
+            pairs(&[("a", 1), ("a", 2)], &[("a", 3), ("a", 4)]),
+            vec![(Some(1), Some(3)), (Some(2), Some(4))]
+        );
+    }
+
+    #[test]
+    fn extra_duplicates_are_added() {
+        assert_eq!(
+            pairs(&[("a", 1)], &[("a", 2), ("b", 3), ("a", 4)]),
+            vec![(Some(1), Some(2)), (None, Some(3)), (None, Some(4))]
+        );
+    }
+
+    #[test]
+    fn missing_duplicates_are_removed() {
+        assert_eq!(
+            pairs(&[("a", 1), ("a", 2), ("a", 3)], &[("a", 4)]),
+            vec![(Some(1), Some(4)), (Some(2), None), (Some(3), None)]
+        );
+    }
+
+    #[test]
+    fn change_types() {
+        assert_eq!(change_type(None, Some(1)), ch_ewf_grpc::company_history::ChangeType::Added);
+        assert_eq!(change_type(Some(1), None), ch_ewf_grpc::company_history::ChangeType::Removed);
+        assert_eq!(change_type(Some(1), Some(2)), ch_ewf_grpc::company_history::ChangeType::Modified);
+    }
+}
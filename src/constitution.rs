@@ -0,0 +1,110 @@
+use super::{ch_ewf_grpc, pdf};
+use ch_ewf_grpc::company_incorporation::{self as incorporation, CompanyType, MemorandumStatement};
+
+/// Text of the model articles prescribed by the Companies (Model Articles) Regulations 2008, loaded from
+/// `private_by_shares.txt`, `private_by_guarantee.txt` and `public.txt`. Lines starting with `#` are headings.
+pub struct ModelArticles {
+    by_shares: String,
+    by_guarantee: String,
+    public: String,
+}
+
+impl ModelArticles {
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let read = |name: &str| std::fs::read_to_string(path.join(name))
+            .map_err(|err| format!("Unable to read model articles {}: {}", name, err));
+        Ok(ModelArticles {
+            by_shares: read("private_by_shares.txt")?,
+            by_guarantee: read("private_by_guarantee.txt")?,
+            public: read("public.txt")?,
+        })
+    }
+}
+
+pub fn subscriber_name(person: &Option<incorporation::Person>) -> String {
+    match person.as_ref().and_then(|p| p.name.as_ref()) {
+        Some(incorporation::person::Name::Person(p)) => format!("{} {}", p.forename, p.surname),
+        Some(incorporation::person::Name::Corporate(c)) => match &c.person {
+            Some(p) => format!("{}, acting by {} {}", c.corporate_name, p.forename, p.surname),
+            None => c.corporate_name.clone()
+        },
+        None => String::new()
+    }
+}
+
+fn is_llp(company_type: i32) -> bool {
+    company_type == CompanyType::Llp as i32 || company_type == CompanyType::LlpOnlyDesignated as i32
+}
+
+fn has_share_capital(company_type: i32) -> bool {
+    company_type == CompanyType::LimitedByShares as i32 || company_type == CompanyType::Plc as i32
+}
+
+/// The memorandum of association in the form prescribed by the Companies (Registration) Regulations 2008,
+/// naming each subscriber who makes the memorandum statement
+pub fn memorandum(msg: &incorporation::CompanyIncorporation, date: chrono::NaiveDate) -> Result<Vec<u8>, String> {
+    if is_llp(msg.company_type) {
+        return Err("LLPs don't have a memorandum of association".to_string());
+    }
+    let share_capital = has_share_capital(msg.company_type);
+    let subscribers: Vec<String> = if share_capital {
+        msg.subscribers.iter()
+            .filter(|s| s.memorandum_statement != MemorandumStatement::NoMemorandumStatement as i32)
+            .map(|s| subscriber_name(&s.person))
+            .collect()
+    } else {
+        msg.guarantors.iter()
+            .filter(|g| g.memorandum_statement != MemorandumStatement::NoMemorandumStatement as i32)
+            .map(|g| subscriber_name(&g.person))
+            .collect()
+    };
+    if subscribers.is_empty() {
+        return Err("No subscribers make the memorandum statement".to_string());
+    }
+
+    let company_name = msg.company_name.to_uppercase();
+    let mut doc = pdf::Document::new(&format!("Memorandum of association of {}", company_name), false)?;
+    doc.paragraph(if share_capital {
+        "COMPANY HAVING A SHARE CAPITAL"
+    } else {
+        "COMPANY NOT HAVING A SHARE CAPITAL"
+    });
+    doc.heading(&format!("Memorandum of association of {}", company_name));
+    doc.paragraph(if share_capital {
+        "Each subscriber to this memorandum of association wishes to form a company under the Companies Act 2006 \
+         and agrees to become a member of the company and to take at least one share."
+    } else {
+        "Each subscriber to this memorandum of association wishes to form a company under the Companies Act 2006 \
+         and agrees to become a member of the company."
+    });
+    doc.table(
+        &["Name of each subscriber", "Authentication by each subscriber"],
+        &subscribers.into_iter().map(|s| vec![s, "Authenticated electronically".to_string()]).collect::<Vec<_>>(),
+    );
+    doc.paragraph(&format!("Dated {}", date.format("%-d %B %Y")));
+    doc.finish()
+}
+
+/// The model articles a company adopts, as a document to file or keep with its records
+pub fn model_articles(msg: &incorporation::CompanyIncorporation, text: &ModelArticles) -> Result<Vec<u8>, String> {
+    let (title, text) = match incorporation::Articles::from_i32(msg.articles) {
+        Some(incorporation::Articles::ModelByShares) => ("Private company limited by shares", &text.by_shares),
+        Some(incorporation::Articles::ModelByGuarantee) => ("Private company limited by guarantee", &text.by_guarantee),
+        Some(incorporation::Articles::ModelPlc) => ("Public company", &text.public),
+        _ => return Err("Only model articles can be generated".to_string())
+    };
+
+    let company_name = msg.company_name.to_uppercase();
+    let mut doc = pdf::Document::new(&format!("Articles of association of {}", company_name), false)?;
+    doc.paragraph(&title.to_uppercase());
+    doc.heading(&format!("Articles of association of {}", company_name));
+    for block in text.split("\n\n") {
+        let block = block.trim();
+        match block.strip_prefix('#') {
+            Some(heading) => doc.heading(heading.trim()),
+            None if !block.is_empty() => doc.paragraph(block),
+            None => {}
+        }
+    }
+    doc.finish()
+}
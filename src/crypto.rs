@@ -0,0 +1,274 @@
+use aes_gcm::aead::{Aead, NewAead};
+use rand::Rng;
+
+const NONCE_LEN: usize = 12;
+const DATA_KEY_LEN: usize = 32;
+const ENVELOPE_VERSION: u8 = 1;
+
+/// Holds the key encryption keys that data keys are wrapped under, such as a local key file
+/// or an external KMS
+#[tonic::async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// ID of the key new data keys are wrapped under
+    fn current_key_id(&self) -> &str;
+
+    async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>, String>;
+
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+fn aes_encrypt(key: &aes_gcm::Aes256Gcm, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = rand::thread_rng().gen::<[u8; NONCE_LEN]>();
+    let ciphertext = match key.encrypt(aes_gcm::Nonce::from_slice(&nonce), plaintext) {
+        Ok(c) => c,
+        Err(_) => return Err("Unable to encrypt data".to_string())
+    };
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+fn aes_decrypt(key: &aes_gcm::Aes256Gcm, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("Encrypted data too short".to_string());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    match key.decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext) {
+        Ok(p) => Ok(p),
+        Err(_) => Err("Unable to decrypt data".to_string())
+    }
+}
+
+/// Key encryption keys read from a local file
+pub struct KeyFileProvider {
+    keys: std::collections::HashMap<String, aes_gcm::Aes256Gcm>,
+    current_key_id: String,
+}
+
+impl KeyFileProvider {
+    /// Each line of the file is a key ID followed by a base64 encoded 256 bit key. The last key is used
+    /// for new data, earlier keys are kept to read data that hasn't been rotated yet. A line with only
+    /// a key is given the ID `default`.
+    pub fn from_file(path: &std::path::Path) -> Result<Self, String> {
+        let key_data = match std::fs::read_to_string(path) {
+            Ok(k) => k,
+            Err(err) => return Err(format!("Unable to read encryption key: {}", err))
+        };
+
+        let mut keys = std::collections::HashMap::new();
+        let mut current_key_id = None;
+        for line in key_data.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut parts = line.split_whitespace();
+            let (key_id, key) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(key), None, None) => ("default", key),
+                (Some(key_id), Some(key), None) => (key_id, key),
+                _ => return Err("Invalid line in encryption key file".to_string())
+            };
+            if key_id.len() > u8::MAX as usize {
+                return Err(format!("Encryption key ID {} is too long", key_id));
+            }
+            let key = match base64::decode(key) {
+                Ok(k) => k,
+                Err(err) => return Err(format!("Invalid encryption key encoding for {}: {}", key_id, err))
+            };
+            if key.len() != 32 {
+                return Err(format!("Encryption key {} must be 32 bytes", key_id));
+            }
+            keys.insert(key_id.to_string(), aes_gcm::Aes256Gcm::new(aes_gcm::Key::from_slice(&key)));
+            current_key_id = Some(key_id.to_string());
+        }
+
+        match current_key_id {
+            Some(current_key_id) => Ok(KeyFileProvider {
+                keys,
+                current_key_id,
+            }),
+            None => Err("No keys in encryption key file".to_string())
+        }
+    }
+
+    fn key(&self, key_id: &str) -> Result<&aes_gcm::Aes256Gcm, String> {
+        match self.keys.get(key_id) {
+            Some(k) => Ok(k),
+            None => Err(format!("Unknown encryption key {}", key_id))
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl KeyProvider for KeyFileProvider {
+    fn current_key_id(&self) -> &str {
+        &self.current_key_id
+    }
+
+    async fn wrap_key(&self, key_id: &str, data_key: &[u8]) -> Result<Vec<u8>, String> {
+        aes_encrypt(self.key(key_id)?, data_key)
+    }
+
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> Result<Vec<u8>, String> {
+        aes_decrypt(self.key(key_id)?, wrapped_key)
+    }
+}
+
+struct Envelope<'a> {
+    key_id: &'a str,
+    wrapped_key: &'a [u8],
+    body: &'a [u8],
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, String> {
+        let too_short = || "Encrypted data too short".to_string();
+
+        let (&version, rest) = data.split_first().ok_or_else(too_short)?;
+        if version != ENVELOPE_VERSION {
+            return Err(format!("Unsupported encryption envelope version {}", version));
+        }
+        let (&key_id_len, rest) = rest.split_first().ok_or_else(too_short)?;
+        if rest.len() < key_id_len as usize + 2 {
+            return Err(too_short());
+        }
+        let (key_id, rest) = rest.split_at(key_id_len as usize);
+        let key_id = match std::str::from_utf8(key_id) {
+            Ok(k) => k,
+            Err(_) => return Err("Invalid encryption key ID".to_string())
+        };
+        let (wrapped_key_len, rest) = rest.split_at(2);
+        let wrapped_key_len = u16::from_be_bytes([wrapped_key_len[0], wrapped_key_len[1]]) as usize;
+        if rest.len() < wrapped_key_len {
+            return Err(too_short());
+        }
+        let (wrapped_key, body) = rest.split_at(wrapped_key_len);
+
+        Ok(Envelope {
+            key_id,
+            wrapped_key,
+            body,
+        })
+    }
+
+    fn serialise(&self) -> Result<Vec<u8>, String> {
+        if self.wrapped_key.len() > u16::MAX as usize {
+            return Err("Wrapped data key too long".to_string());
+        }
+        let mut out = Vec::with_capacity(4 + self.key_id.len() + self.wrapped_key.len() + self.body.len());
+        out.push(ENVELOPE_VERSION);
+        out.push(self.key_id.len() as u8);
+        out.extend_from_slice(self.key_id.as_bytes());
+        out.extend_from_slice(&(self.wrapped_key.len() as u16).to_be_bytes());
+        out.extend_from_slice(self.wrapped_key);
+        out.extend_from_slice(self.body);
+        Ok(out)
+    }
+}
+
+/// Envelope encryption for data at rest, such as authentication codes and documents. Each value is
+/// encrypted with its own AES-256-GCM data key, which is stored alongside it wrapped under a key from
+/// the key provider.
+pub struct Cipher {
+    provider: Box<dyn KeyProvider>,
+}
+
+impl Cipher {
+    pub fn new(provider: Box<dyn KeyProvider>) -> Self {
+        Cipher {
+            provider
+        }
+    }
+
+    pub fn from_key_file(path: &std::path::Path) -> Result<Self, String> {
+        Ok(Self::new(Box::new(KeyFileProvider::from_file(path)?)))
+    }
+
+    pub async fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let data_key = rand::thread_rng().gen::<[u8; DATA_KEY_LEN]>();
+        let body = aes_encrypt(&aes_gcm::Aes256Gcm::new(aes_gcm::Key::from_slice(&data_key)), plaintext)?;
+        let key_id = self.provider.current_key_id();
+        let wrapped_key = self.provider.wrap_key(key_id, &data_key).await?;
+
+        Envelope {
+            key_id,
+            wrapped_key: &wrapped_key,
+            body: &body,
+        }.serialise()
+    }
+
+    pub async fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let envelope = Envelope::parse(data)?;
+        let data_key = self.provider.unwrap_key(envelope.key_id, envelope.wrapped_key).await?;
+        if data_key.len() != DATA_KEY_LEN {
+            return Err("Invalid data key length".to_string());
+        }
+        aes_decrypt(&aes_gcm::Aes256Gcm::new(aes_gcm::Key::from_slice(&data_key)), envelope.body)
+    }
+
+    /// Wraps the data key under the current key without re-encrypting the data itself,
+    /// returns `None` if it's already under the current key
+    pub async fn rewrap(&self, data: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        let envelope = Envelope::parse(data)?;
+        let key_id = self.provider.current_key_id();
+        if envelope.key_id == key_id {
+            return Ok(None);
+        }
+
+        let data_key = self.provider.unwrap_key(envelope.key_id, envelope.wrapped_key).await?;
+        let wrapped_key = self.provider.wrap_key(key_id, &data_key).await?;
+        Envelope {
+            key_id,
+            wrapped_key: &wrapped_key,
+            body: envelope.body,
+        }.serialise().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: [u8; 32] = [1; 32];
+    const NEW_KEY: [u8; 32] = [2; 32];
+
+    fn cipher(keys: &[(&str, [u8; 32])]) -> Cipher {
+        Cipher::new(Box::new(KeyFileProvider {
+            keys: keys.iter()
+                .map(|(id, k)| (id.to_string(), aes_gcm::Aes256Gcm::new(aes_gcm::Key::from_slice(k))))
+                .collect(),
+            current_key_id: keys.last().unwrap().0.to_string(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn envelope_round_trip() {
+        let cipher = cipher(&[("2026-01", OLD_KEY)]);
+        let data = cipher.encrypt(b"secret").await.unwrap();
+        let envelope = Envelope::parse(&data).unwrap();
+        assert_eq!(envelope.key_id, "2026-01");
+        assert_eq!(envelope.serialise().unwrap(), data);
+        assert_eq!(cipher.decrypt(&data).await.unwrap(), b"secret");
+    }
+
+    #[tokio::test]
+    async fn tampered_data_is_rejected() {
+        let cipher = cipher(&[("2026-01", OLD_KEY)]);
+        let mut data = cipher.encrypt(b"secret").await.unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt(&data).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rotation() {
+        let data = cipher(&[("2026-01", OLD_KEY)]).encrypt(b"secret").await.unwrap();
+
+        let rotating = cipher(&[("2026-01", OLD_KEY), ("2026-10", NEW_KEY)]);
+        assert_eq!(rotating.decrypt(&data).await.unwrap(), b"secret");
+        let rewrapped = rotating.rewrap(&data).await.unwrap().unwrap();
+        assert_eq!(Envelope::parse(&rewrapped).unwrap().key_id, "2026-10");
+        assert_eq!(Envelope::parse(&rewrapped).unwrap().body, Envelope::parse(&data).unwrap().body);
+        assert_eq!(rotating.rewrap(&rewrapped).await.unwrap(), None);
+
+        // Once rotated the old key is no longer needed
+        let rotated = cipher(&[("2026-10", NEW_KEY)]);
+        assert_eq!(rotated.decrypt(&rewrapped).await.unwrap(), b"secret");
+        assert!(rotated.decrypt(&data).await.is_err());
+    }
+}
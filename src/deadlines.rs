@@ -0,0 +1,321 @@
+use super::{ch_ewf_grpc, models, schema};
+use chrono::Datelike;
+use diesel::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineKind {
+    ConfirmationStatement,
+    Accounts,
+}
+
+#[derive(Debug, Clone)]
+pub struct Deadline {
+    pub company_number: String,
+    pub company_name: String,
+    pub kind: DeadlineKind,
+    pub period_start: chrono::NaiveDate,
+    pub period_end: chrono::NaiveDate,
+    pub due_date: chrono::NaiveDate,
+}
+
+fn last_day_of_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    chrono::NaiveDate::from_ymd(next_year, next_month, 1).pred().day()
+}
+
+/// Adds calendar months following s443 of the Companies Act 2006, a period ending on
+/// the last day of a month ends on the last day of the target month
+fn add_months(date: chrono::NaiveDate, months: u32) -> chrono::NaiveDate {
+    let total = date.year() * 12 + date.month0() as i32 + months as i32;
+    let year = total.div_euclid(12);
+    let month = total.rem_euclid(12) as u32 + 1;
+    let last_day = last_day_of_month(year, month);
+    let day = if date.day() == last_day_of_month(date.year(), date.month()) {
+        last_day
+    } else {
+        date.day().min(last_day)
+    };
+    chrono::NaiveDate::from_ymd(year, month, day)
+}
+
+fn accounts_deadline(
+    company: &models::TrackedCompany, changes: &[models::AccountingReferenceDateChange],
+) -> Deadline {
+    let mut period_end = company.accounting_period_end;
+    let mut shortened_notice = None;
+    for change in changes {
+        if change.current_period_end == period_end {
+            period_end = change.new_period_end;
+            shortened_notice = if change.shortened {
+                Some(change.notice_date)
+            } else {
+                None
+            };
+        }
+    }
+
+    // The tracked period end goes stale once accounts for it are filed
+    if let Some(last_accounts) = company.last_accounts_made_up_to {
+        while period_end <= last_accounts {
+            period_end = add_months(period_end, 12);
+            shortened_notice = None;
+        }
+    }
+
+    let period_start = match company.last_accounts_made_up_to {
+        Some(d) => d.succ(),
+        None => company.incorporation_date,
+    };
+
+    let mut due_date = add_months(period_end, if company.public_company { 6 } else { 9 });
+
+    // s442(3): a first period longer than 12 months gets 21 months (18 for a public company)
+    // from incorporation, or 3 months from the end of the period if that's later
+    if company.last_accounts_made_up_to.is_none() && period_end > add_months(company.incorporation_date, 12) {
+        due_date = std::cmp::max(
+            add_months(company.incorporation_date, if company.public_company { 18 } else { 21 }),
+            add_months(period_end, 3),
+        );
+    }
+
+    // s442(4): a shortened period allows at least 3 months from the date of the notice
+    if let Some(notice_date) = shortened_notice {
+        due_date = std::cmp::max(due_date, add_months(notice_date, 3));
+    }
+
+    Deadline {
+        company_number: company.company_number.clone(),
+        company_name: company.company_name.clone(),
+        kind: DeadlineKind::Accounts,
+        period_start,
+        period_end,
+        due_date,
+    }
+}
+
+fn confirmation_statement_deadline(company: &models::TrackedCompany) -> Deadline {
+    let period_start = company.confirmation_statement_made_up_to.unwrap_or(company.incorporation_date);
+    let period_end = add_months(period_start, 12);
+    let due_date = match company.confirmation_statement_due {
+        Some(d) if d > period_start => d,
+        _ => period_end + chrono::Duration::days(14)
+    };
+
+    Deadline {
+        company_number: company.company_number.clone(),
+        company_name: company.company_name.clone(),
+        kind: DeadlineKind::ConfirmationStatement,
+        period_start,
+        period_end,
+        due_date,
+    }
+}
+
+/// Computes the upcoming deadlines of tracked companies, taking into account accepted
+/// accounting reference date changes
+pub fn load_deadlines(conn: &diesel::pg::PgConnection, company_number: Option<String>) -> QueryResult<Vec<Deadline>> {
+    let mut companies_query = schema::tracked_companies::dsl::tracked_companies.into_boxed();
+    let mut changes_query = schema::accounting_reference_date_changes::table
+        .inner_join(schema::submissions::table)
+        .filter(schema::submissions::dsl::status.eq(schema::Status::Accepted))
+        .select(schema::accounting_reference_date_changes::all_columns)
+        .order_by(schema::accounting_reference_date_changes::dsl::notice_date.asc())
+        .into_boxed();
+    if let Some(company_number) = company_number {
+        companies_query = companies_query.filter(schema::tracked_companies::dsl::company_number.eq(company_number.clone()));
+        changes_query = changes_query.filter(schema::accounting_reference_date_changes::dsl::company_number.eq(company_number));
+    }
+
+    let companies: Vec<models::TrackedCompany> = companies_query.load(conn)?;
+    let changes: Vec<models::AccountingReferenceDateChange> = changes_query.load(conn)?;
+
+    let mut deadlines = companies.iter().flat_map(|c| {
+        let company_changes = changes.iter()
+            .filter(|ch| ch.company_number == c.company_number)
+            .cloned()
+            .collect::<Vec<_>>();
+        vec![confirmation_statement_deadline(c), accounts_deadline(c, &company_changes)]
+    }).collect::<Vec<_>>();
+    deadlines.sort_by_key(|d| d.due_date);
+    Ok(deadlines)
+}
+
+/// Records the confirmation statement due date returned by company data, if the company is tracked
+pub fn record_confirmation_statement_due(
+    conn: &diesel::pg::PgConnection, company_number: String, due: chrono::NaiveDate,
+) -> QueryResult<usize> {
+    diesel::update(schema::tracked_companies::dsl::tracked_companies.find(company_number))
+        .set(schema::tracked_companies::dsl::confirmation_statement_due.eq(due))
+        .execute(conn)
+}
+
+/// Moves the confirmation statement review period forward to the end of the latest paid period
+pub fn record_paid_period(
+    conn: &diesel::pg::PgConnection, company_number: String, period_end: chrono::NaiveDate,
+) -> QueryResult<usize> {
+    use schema::tracked_companies::dsl;
+
+    diesel::update(dsl::tracked_companies.find(company_number))
+        .filter(dsl::confirmation_statement_made_up_to.is_null().or(dsl::confirmation_statement_made_up_to.lt(period_end)))
+        .set(dsl::confirmation_statement_made_up_to.eq(period_end))
+        .execute(conn)
+}
+
+impl From<Deadline> for ch_ewf_grpc::deadlines::Deadline {
+    fn from(d: Deadline) -> Self {
+        let to_proto = |d: chrono::NaiveDate| super::grpc::chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(d.and_hms(0, 0, 0), chrono::Utc)));
+        ch_ewf_grpc::deadlines::Deadline {
+            company_number: d.company_number,
+            company_name: d.company_name,
+            r#type: match d.kind {
+                DeadlineKind::ConfirmationStatement => ch_ewf_grpc::deadlines::DeadlineType::ConfirmationStatement.into(),
+                DeadlineKind::Accounts => ch_ewf_grpc::deadlines::DeadlineType::Accounts.into(),
+            },
+            period_start: to_proto(d.period_start),
+            period_end: to_proto(d.period_end),
+            overdue: d.due_date < chrono::Utc::today().naive_utc(),
+            due_date: to_proto(d.due_date),
+        }
+    }
+}
+
+fn escape_ical(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Renders deadlines as an iCalendar feed of all-day events
+pub fn render_ical(deadlines: &[Deadline]) -> String {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut out = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//ch-ewf//deadlines//EN\r\nCALSCALE:GREGORIAN\r\n");
+    for d in deadlines {
+        let (kind, summary) = match d.kind {
+            DeadlineKind::ConfirmationStatement => ("cs", "Confirmation statement due"),
+            DeadlineKind::Accounts => ("accounts", "Accounts due"),
+        };
+        out.push_str("BEGIN:VEVENT\r\n");
+        out.push_str(&format!("UID:{}-{}-{}@ch-ewf\r\n", d.company_number, kind, d.period_end.format("%Y%m%d")));
+        out.push_str(&format!("DTSTAMP:{}\r\n", now));
+        out.push_str(&format!("DTSTART;VALUE=DATE:{}\r\n", d.due_date.format("%Y%m%d")));
+        out.push_str(&format!("DTEND;VALUE=DATE:{}\r\n", d.due_date.succ().format("%Y%m%d")));
+        out.push_str(&format!("SUMMARY:{}\r\n", escape_ical(&format!("{} – {} ({})", summary, d.company_name, d.company_number))));
+        out.push_str(&format!("DESCRIPTION:{}\r\n", escape_ical(&format!(
+            "Period {} to {}", d.period_start.format("%d/%m/%Y"), d.period_end.format("%d/%m/%Y")
+        ))));
+        out.push_str("END:VEVENT\r\n");
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd(year, month, day)
+    }
+
+    fn company(incorporation_date: chrono::NaiveDate, accounting_period_end: chrono::NaiveDate) -> models::TrackedCompany {
+        models::TrackedCompany {
+            company_number: "EW12345678".to_string(),
+            company_name: "TEST LIMITED".to_string(),
+            public_company: false,
+            incorporation_date,
+            accounting_period_end,
+            last_accounts_made_up_to: None,
+            confirmation_statement_made_up_to: None,
+            confirmation_statement_due: None,
+        }
+    }
+
+    #[test]
+    fn add_months_within_month() {
+        assert_eq!(add_months(date(2025, 1, 15), 1), date(2025, 2, 15));
+        assert_eq!(add_months(date(2025, 11, 15), 3), date(2026, 2, 15));
+        assert_eq!(add_months(date(2025, 1, 30), 1), date(2025, 2, 28));
+    }
+
+    #[test]
+    fn add_months_month_end() {
+        assert_eq!(add_months(date(2025, 4, 30), 1), date(2025, 5, 31));
+        assert_eq!(add_months(date(2025, 6, 30), 9), date(2026, 3, 31));
+        assert_eq!(add_months(date(2025, 2, 28), 1), date(2025, 3, 31));
+    }
+
+    #[test]
+    fn add_months_leap_year() {
+        assert_eq!(add_months(date(2024, 1, 31), 1), date(2024, 2, 29));
+        assert_eq!(add_months(date(2023, 2, 28), 12), date(2024, 2, 29));
+        assert_eq!(add_months(date(2024, 2, 29), 12), date(2025, 2, 28));
+        assert_eq!(add_months(date(2024, 2, 29), 1), date(2024, 3, 31));
+    }
+
+    #[test]
+    fn accounts_due_after_period() {
+        let mut c = company(date(2020, 3, 1), date(2025, 12, 31));
+        c.last_accounts_made_up_to = Some(date(2024, 12, 31));
+        let d = accounts_deadline(&c, &[]);
+        assert_eq!(d.period_start, date(2025, 1, 1));
+        assert_eq!(d.due_date, date(2026, 9, 30));
+
+        c.public_company = true;
+        assert_eq!(accounts_deadline(&c, &[]).due_date, date(2026, 6, 30));
+    }
+
+    #[test]
+    fn accounts_period_moves_on_once_filed() {
+        let mut c = company(date(2020, 3, 1), date(2024, 2, 29));
+        c.last_accounts_made_up_to = Some(date(2024, 2, 29));
+        let d = accounts_deadline(&c, &[]);
+        assert_eq!(d.period_end, date(2025, 2, 28));
+        assert_eq!(d.due_date, date(2025, 11, 30));
+    }
+
+    #[test]
+    fn long_first_accounts_period() {
+        let c = company(date(2024, 1, 15), date(2025, 1, 31));
+        assert_eq!(accounts_deadline(&c, &[]).due_date, date(2025, 10, 15));
+    }
+
+    #[test]
+    fn shortened_accounts_period() {
+        let mut c = company(date(2020, 3, 1), date(2025, 12, 31));
+        c.last_accounts_made_up_to = Some(date(2024, 12, 31));
+        let change = |notice_date| models::AccountingReferenceDateChange {
+            submission_id: uuid::Uuid::nil(),
+            company_number: c.company_number.clone(),
+            current_period_end: date(2025, 12, 31),
+            new_period_end: date(2025, 6, 30),
+            shortened: true,
+            notice_date,
+        };
+
+        let d = accounts_deadline(&c, &[change(date(2025, 9, 1))]);
+        assert_eq!(d.period_end, date(2025, 6, 30));
+        assert_eq!(d.due_date, date(2026, 3, 31));
+        assert_eq!(accounts_deadline(&c, &[change(date(2026, 2, 1))]).due_date, date(2026, 5, 1));
+    }
+
+    #[test]
+    fn confirmation_statement_due() {
+        let mut c = company(date(2023, 2, 28), date(2024, 2, 29));
+        let d = confirmation_statement_deadline(&c);
+        assert_eq!(d.period_end, date(2024, 2, 29));
+        assert_eq!(d.due_date, date(2024, 3, 14));
+
+        c.confirmation_statement_made_up_to = Some(date(2024, 2, 29));
+        let d = confirmation_statement_deadline(&c);
+        assert_eq!(d.period_end, date(2025, 2, 28));
+        assert_eq!(d.due_date, date(2025, 3, 14));
+
+        c.confirmation_statement_due = Some(date(2025, 3, 10));
+        assert_eq!(confirmation_statement_deadline(&c).due_date, date(2025, 3, 10));
+    }
+}
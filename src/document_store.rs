@@ -0,0 +1,422 @@
+use super::{ch_ewf_grpc, models};
+use hmac::Mac;
+use sha2::Digest;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentStoreConfig {
+    Local {
+        path: std::path::PathBuf,
+    },
+    S3(S3Config),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct S3Config {
+    pub endpoint: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Address the bucket as part of the path rather than the hostname, as MinIO requires by default
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+impl DocumentStoreConfig {
+    pub fn build(&self) -> Result<std::sync::Arc<dyn DocumentStore>, String> {
+        Ok(match self {
+            DocumentStoreConfig::Local { path } => std::sync::Arc::new(LocalStore::new(path.clone())),
+            DocumentStoreConfig::S3(config) => std::sync::Arc::new(S3Store::new(config)?),
+        })
+    }
+}
+
+/// Hex encoded SHA-256 of a document's contents, as recorded in the `documents` table
+pub fn content_hash(data: &[u8]) -> String {
+    hex::encode(sha2::Sha256::digest(data))
+}
+
+/// Content hash of a document read in chunks
+#[derive(Default)]
+pub struct ContentHasher(sha2::Sha256);
+
+impl ContentHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finish(self) -> String {
+        hex::encode(self.0.finalize())
+    }
+}
+
+/// How many chunks a document stream reads ahead of its consumer
+const STREAM_BUFFER: usize = 4;
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+pub type DocumentStream = std::pin::Pin<Box<
+    dyn tokio_stream::Stream<Item = Result<Vec<u8>, String>> + Send + Sync + 'static
+>>;
+
+/// A document being read from the store
+pub struct DocumentReader {
+    /// Size of the whole document
+    pub size: u64,
+    pub chunks: DocumentStream,
+}
+
+impl From<models::Documents> for ch_ewf_grpc::documents::DocumentInfo {
+    fn from(d: models::Documents) -> Self {
+        ch_ewf_grpc::documents::DocumentInfo {
+            document_id: d.id.to_string(),
+            company_number: d.company_number,
+            date: super::grpc::chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(
+                d.document_date.and_hms(0, 0, 0), chrono::Utc,
+            ))),
+            document_type: d.document_type,
+            ch_document_id: d.document_id,
+            ch_filename: d.document_filename,
+            sha256: d.content_hash.unwrap_or_default(),
+        }
+    }
+}
+
+/// Storage for documents retrieved from Companies House, keyed by their storage filename
+#[tonic::async_trait]
+pub trait DocumentStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), String>;
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String>;
+
+    /// Reads a document in chunks, so that it needn't be held in memory
+    async fn get_stream(&self, key: &str) -> Result<DocumentReader, String>;
+
+    async fn delete(&self, key: &str) -> Result<(), String>;
+
+    /// A time limited URL to download a document straight from the store, if the store supports it
+    fn presigned_url(&self, _key: &str, _expires_in: std::time::Duration) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocumentURLConfig {
+    /// Base URL the HTTP server is reachable at, such as `https://ch-ewf.example.com`
+    pub base_url: String,
+    pub signing_secret: String,
+}
+
+/// Signs time limited URLs to download documents through the HTTP server, for documents that
+/// can't be served from the store directly
+pub struct URLSigner {
+    base_url: String,
+    secret: Vec<u8>,
+}
+
+impl URLSigner {
+    pub fn new(config: &DocumentURLConfig) -> Self {
+        URLSigner {
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            secret: config.signing_secret.as_bytes().to_vec(),
+        }
+    }
+
+    fn mac(&self, document_id: &uuid::Uuid, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}:{}", document_id, expires).as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, document_id: &uuid::Uuid, expires: chrono::DateTime<chrono::Utc>) -> String {
+        let expires = expires.timestamp();
+        let signature = hex::encode(self.mac(document_id, expires).finalize().into_bytes());
+        format!("{}/documents/{}?expires={}&signature={}", self.base_url, document_id, expires, signature)
+    }
+
+    pub fn verify(&self, document_id: &uuid::Uuid, expires: i64, signature: &str) -> bool {
+        if expires < chrono::Utc::now().timestamp() {
+            return false;
+        }
+        let signature = match hex::decode(signature) {
+            Ok(s) => s,
+            Err(_) => return false
+        };
+        self.mac(document_id, expires).verify_slice(&signature).is_ok()
+    }
+}
+
+pub struct LocalStore {
+    path: std::path::PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(path: std::path::PathBuf) -> Self {
+        LocalStore {
+            path
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl DocumentStore for LocalStore {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), String> {
+        let file_path = self.path.join(key);
+        // Write to a temporary file first so a document is never left half written
+        let tmp_path = self.path.join(format!(".{}.tmp", key));
+        if let Err(err) = tokio::fs::write(&tmp_path, &data).await {
+            return Err(format!("Unable to write document: {}", err));
+        }
+        if let Err(err) = tokio::fs::rename(&tmp_path, &file_path).await {
+            return Err(format!("Unable to write document: {}", err));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        match tokio::fs::read(self.path.join(key)).await {
+            Ok(d) => Ok(d),
+            Err(err) => Err(format!("Unable to read document: {}", err))
+        }
+    }
+
+    async fn get_stream(&self, key: &str) -> Result<DocumentReader, String> {
+        use tokio::io::AsyncReadExt;
+
+        let mut file = match tokio::fs::File::open(self.path.join(key)).await {
+            Ok(f) => f,
+            Err(err) => return Err(format!("Unable to read document: {}", err))
+        };
+        let size = match file.metadata().await {
+            Ok(m) => m.len(),
+            Err(err) => return Err(format!("Unable to read document: {}", err))
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+                let res = match file.read(&mut chunk).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        Ok(chunk)
+                    }
+                    Err(err) => Err(format!("Unable to read document: {}", err))
+                };
+                let failed = res.is_err();
+                if tx.send(res).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(DocumentReader {
+            size,
+            chunks: Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx)),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.path.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format!("Unable to delete document: {}", err))
+        }
+    }
+}
+
+type HmacSha256 = hmac::Hmac<sha2::Sha256>;
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent encodes everything but unreserved characters, as AWS signature version 4 requires
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b))
+        }
+    }
+    out
+}
+
+/// An S3 compatible object store, such as AWS S3 or MinIO, with requests signed using AWS signature version 4
+pub struct S3Store {
+    client: reqwest::Client,
+    endpoint: reqwest::Url,
+    region: String,
+    bucket: String,
+    access_key_id: String,
+    secret_access_key: String,
+    path_style: bool,
+}
+
+impl S3Store {
+    pub fn new(config: &S3Config) -> Result<Self, String> {
+        let endpoint = match reqwest::Url::parse(&config.endpoint) {
+            Ok(u) => u,
+            Err(err) => return Err(format!("Invalid S3 endpoint: {}", err))
+        };
+        if endpoint.host_str().is_none() {
+            return Err("S3 endpoint has no host".to_string());
+        }
+
+        Ok(S3Store {
+            client: reqwest::Client::new(),
+            endpoint,
+            region: config.region.clone(),
+            bucket: config.bucket.clone(),
+            access_key_id: config.access_key_id.clone(),
+            secret_access_key: config.secret_access_key.clone(),
+            path_style: config.path_style,
+        })
+    }
+
+    fn object_url(&self, key: &str) -> (String, String) {
+        let host = self.endpoint.host_str().unwrap();
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string()
+        };
+        if self.path_style {
+            let path = format!("/{}/{}", uri_encode(&self.bucket, true), uri_encode(key, false));
+            (host, path)
+        } else {
+            (format!("{}.{}", self.bucket, host), format!("/{}", uri_encode(key, false)))
+        }
+    }
+
+    fn signing_key(&self, date: &str) -> Vec<u8> {
+        let key = hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        let key = hmac_sha256(&key, self.region.as_bytes());
+        let key = hmac_sha256(&key, b"s3");
+        hmac_sha256(&key, b"aws4_request")
+    }
+
+    fn credential_scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.region)
+    }
+
+    fn request(&self, method: reqwest::Method, key: &str, body: Vec<u8>) -> reqwest::RequestBuilder {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = content_hash(&body);
+        let (host, path) = self.object_url(key);
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method.as_str(), path, host, payload_hash, amz_date, payload_hash
+        );
+        let scope = self.credential_scope(&date);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, content_hash(canonical_request.as_bytes())
+        );
+        let signature = hex::encode(hmac_sha256(&self.signing_key(&date), string_to_sign.as_bytes()));
+
+        self.client.request(method, format!("{}://{}{}", self.endpoint.scheme(), host, path))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                self.access_key_id, scope, signature
+            ))
+            .body(body)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let res = match request.send().await {
+            Ok(r) => r,
+            Err(err) => return Err(format!("Unable to contact S3: {}", err))
+        };
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            Err(format!("S3 error {}: {}", status, body))
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl DocumentStore for S3Store {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), String> {
+        self.send(self.request(reqwest::Method::PUT, key, data)).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        let res = self.send(self.request(reqwest::Method::GET, key, vec![])).await?;
+        match res.bytes().await {
+            Ok(b) => Ok(b.to_vec()),
+            Err(err) => Err(format!("Unable to read document from S3: {}", err))
+        }
+    }
+
+    async fn get_stream(&self, key: &str) -> Result<DocumentReader, String> {
+        let mut res = self.send(self.request(reqwest::Method::GET, key, vec![])).await?;
+        let size = match res.content_length() {
+            Some(s) => s,
+            None => return Err("S3 didn't give the document's length".to_string())
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let res = match res.chunk().await {
+                    Ok(Some(c)) => Ok(c.to_vec()),
+                    Ok(None) => break,
+                    Err(err) => Err(format!("Unable to read document from S3: {}", err))
+                };
+                let failed = res.is_err();
+                if tx.send(res).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(DocumentReader {
+            size,
+            chunks: Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx)),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.send(self.request(reqwest::Method::DELETE, key, vec![])).await?;
+        Ok(())
+    }
+
+    fn presigned_url(&self, key: &str, expires_in: std::time::Duration) -> Option<String> {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let (host, path) = self.object_url(key);
+
+        // Query parameters must be in sorted order
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&format!("{}/{}", self.access_key_id, self.credential_scope(&date)), true),
+            amz_date, expires_in.as_secs()
+        );
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, host);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, self.credential_scope(&date), content_hash(canonical_request.as_bytes())
+        );
+        let signature = hex::encode(hmac_sha256(&self.signing_key(&date), string_to_sign.as_bytes()));
+
+        Some(format!("{}://{}{}?{}&X-Amz-Signature={}", self.endpoint.scheme(), host, path, query, signature))
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::prelude::*;
-use crate::proto;
+use crate::{proto, metrics};
 
 static GATEWAY: &str = "https://xmlgw.companieshouse.gov.uk/v1-0/xmlgw/Gateway";
 
@@ -9,8 +9,12 @@ pub struct GovTalkSender {
     presenter_id: String,
     presenter_code: String,
     is_test: bool,
+    dry_run: Option<DryRunCapture>,
 }
 
+/// Receives the rendered XML of a message from a dry run sender
+pub type DryRunCapture = std::sync::Arc<std::sync::Mutex<Option<String>>>;
+
 impl GovTalkSender {
     pub fn new(email: &str, presenter_id: &str, presenter_code: &str, is_test: bool) -> Self {
         Self {
@@ -18,8 +22,22 @@ impl GovTalkSender {
             presenter_id: format!("{:x}", md5::compute(presenter_id.as_bytes())),
             presenter_code: format!("{:x}", md5::compute(presenter_code.as_bytes())),
             is_test,
+            dry_run: None,
         }
     }
+
+    /// Returns a copy of this sender that renders messages without sending them to the gateway
+    pub fn dry_run(&self) -> (Self, DryRunCapture) {
+        let capture = DryRunCapture::default();
+        (Self {
+            dry_run: Some(capture.clone()),
+            ..self.clone()
+        }, capture)
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
 }
 
 impl From<&GovTalkSender> for proto::govtalk::GovTalkSenderDetails {
@@ -59,8 +77,27 @@ pub struct GovTalkResponse {
     pub body: Option<proto::govtalk::GovTalkBody>,
 }
 
+#[tracing::instrument(skip(sender, body), fields(otel.kind = "client", transaction_id = tracing::field::Empty))]
 pub async fn exec_govtalk_transaction(sender: &GovTalkSender, class: &str, body: proto::govtalk::GovTalkBody) -> Result<GovTalkResponse, GovTalkErrors> {
+    // Dry runs only render the message, so aren't counted as gateway transactions
+    if sender.is_dry_run() {
+        return exec_govtalk_transaction_inner(sender, class, body).await;
+    }
+    let timer = metrics::GATEWAY_LATENCY.with_label_values(&[class]).start_timer();
+    let res = exec_govtalk_transaction_inner(sender, class, body).await;
+    timer.observe_duration();
+
+    metrics::GATEWAY_TRANSACTIONS.with_label_values(&[class, match &res {
+        Ok(_) => "success",
+        Err(_) => "error",
+    }]).inc();
+
+    res
+}
+
+async fn exec_govtalk_transaction_inner(sender: &GovTalkSender, class: &str, body: proto::govtalk::GovTalkBody) -> Result<GovTalkResponse, GovTalkErrors> {
     let trans_id = format!("{:X}", uuid::Uuid::new_v4().to_simple());
+    tracing::Span::current().record("transaction_id", &trans_id.as_str());
     let req_msg = proto::govtalk::GovTalkRoot {
         message: proto::govtalk::GovTalkMessage {
             envelope_version: "1.0".to_string(),
@@ -105,7 +142,19 @@ pub async fn exec_govtalk_transaction(sender: &GovTalkSender, class: &str, body:
         })
     };
 
-    println!("{}", req_msg_str);
+    trace!("GovTalk request (trans ID: {}): {}", trans_id, req_msg_str);
+
+    if let Some(capture) = &sender.dry_run {
+        *capture.lock().unwrap() = Some(req_msg_str);
+        return Err(GovTalkErrors {
+            transaction_id: trans_id,
+            errors: vec![GovTalkError {
+                raised_by: "Dry run".to_string(),
+                code: 0,
+                msg: "Message not sent".to_string(),
+            }],
+        });
+    }
 
     let http_client = reqwest::Client::new();
     let res_msg_str = match http_client.post(GATEWAY)
@@ -134,7 +183,7 @@ pub async fn exec_govtalk_transaction(sender: &GovTalkSender, class: &str, body:
         })
     };
 
-    println!("{}", res_msg_str);
+    trace!("GovTalk response (trans ID: {}): {}", trans_id, res_msg_str);
 
     let res_msg: proto::govtalk::GovTalkRoot = match xml_serde::from_str(&res_msg_str) {
         Ok(s) => s,
@@ -147,7 +196,7 @@ pub async fn exec_govtalk_transaction(sender: &GovTalkSender, class: &str, body:
             }],
         })
     };
-    println!("{:#?}", res_msg);
+    debug!("GovTalk response (trans ID: {}): {:?}", trans_id, res_msg);
 
     let error_vec = res_msg.message.details.errors.map_or(vec![], |es| {
         es.errors.into_iter().map(|e| GovTalkError {
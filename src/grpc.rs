@@ -1,8 +1,15 @@
-use super::{proto, gov_talk, ch_ewf_grpc, schema, models};
+use super::{proto, gov_talk, ch_ewf_grpc, schema, models, metrics, leader, company_history, officer_reconciliation, psc_reconciliation, psc_determination, deadlines, crypto, managed_companies, document_store, registers, capital_ledger, capital, names, incorporation_workflow, constitution, community_interest, bulk_submission};
 use std::convert::{TryFrom, TryInto};
 use rand::Rng;
 use diesel::prelude::*;
 use tokio_diesel::{OptionalExtension, AsyncConnection, AsyncRunQueryDsl};
+use tracing::Instrument;
+use ch_ewf_grpc::ch_filling_server::ChFilling;
+
+const DOCUMENT_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_DOCUMENT_URL_EXPIRY: u32 = 5 * 60;
+/// The longest expiry S3 presigned URLs allow
+const MAX_DOCUMENT_URL_EXPIRY: u32 = 7 * 24 * 60 * 60;
 
 /// Helper function to convert chrono times to protobuf well-known type times
 pub fn chrono_to_proto<T: chrono::TimeZone>(
@@ -26,13 +33,45 @@ pub fn proto_to_chrono(
     }
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReconciliationConfig {
+    #[serde(default = "default_stale_after_hours")]
+    pub stale_after_hours: i64,
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+}
+
+fn default_stale_after_hours() -> i64 {
+    24
+}
+
+fn default_interval_minutes() -> u64 {
+    60
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            stale_after_hours: default_stale_after_hours(),
+            interval_minutes: default_interval_minutes(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct CHFillingService {
     pub sender: gov_talk::GovTalkSender,
     pub connection: r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>,
-    pub documents_path: std::path::PathBuf,
+    pub document_store: std::sync::Arc<dyn document_store::DocumentStore>,
+    pub url_signer: Option<std::sync::Arc<document_store::URLSigner>>,
     pub presenter_id: String,
     pub package_reference: String,
+    pub cipher: Option<std::sync::Arc<crypto::Cipher>>,
+    pub name_index: std::sync::Arc<names::NameIndex>,
+    pub model_articles: Option<std::sync::Arc<constitution::ModelArticles>>,
+    pub bulk_submit_permits: std::sync::Arc<tokio::sync::Semaphore>,
+    /// ID to give the submission made through this service, so that it can be recorded before sending
+    pub submission_id: Option<uuid::Uuid>,
 }
 
 impl CHFillingService {
@@ -52,6 +91,13 @@ impl CHFillingService {
         })
     }
 
+    fn format_company_number(company_number: u32, company_type: i32) -> Result<String, tonic::Status> {
+        match Self::map_company_type(company_type) {
+            Some(t) => Ok(format!("{}{}", t.to_string(), company_number)),
+            None => Err(tonic::Status::invalid_argument("Invalid company type"))
+        }
+    }
+
     fn map_record_type(ct: i32) -> Option<proto::base_types::RecordType> {
         ch_ewf_grpc::base_types::RecordType::from_i32(ct).map(|ct| match ct {
             ch_ewf_grpc::base_types::RecordType::Directors => proto::base_types::RecordType::Directors,
@@ -91,44 +137,293 @@ impl CHFillingService {
         Ok(())
     }
 
-    fn gen_submission_number(conn: &diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>) -> Result<String, tonic::Status> {
+    fn gen_submission_number() -> String {
         let mut rng = rand::thread_rng();
+        std::iter::repeat(())
+            .map(|()| rng.sample(rand::distributions::Alphanumeric))
+            .map(char::from)
+            .take(6)
+            .collect()
+    }
+
+    /// Saves the submission as unsent with a freshly generated submission number, retrying if another
+    /// instance has already taken the number. Unsent submissions aren't polled, so a reservation left
+    /// behind by a crash before the gateway replied never shows up as pending.
+    #[tracing::instrument(skip_all, fields(submission_id = %submission.id, submission_number = tracing::field::Empty))]
+    fn reserve_submission(
+        conn: &diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>,
+        submission: &mut models::Submission,
+    ) -> Result<(), tonic::Status> {
         loop {
-            let submission_number = std::iter::repeat(())
-                .map(|()| rng.sample(rand::distributions::Alphanumeric))
-                .map(char::from)
-                .take(6)
-                .collect();
-            let submission_count: i64 = match schema::submissions::dsl::submissions
-                .filter(schema::submissions::dsl::ch_submission_id.eq(&submission_number))
-                .filter(schema::submissions::dsl::received_timestamp.ge((chrono::Utc::now() - chrono::Duration::days(30)).naive_utc()))
-                .count()
-                .get_result(conn) {
-                Ok(c) => c,
-                Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
-            };
+            submission.ch_submission_id = Self::gen_submission_number();
+            match diesel::insert_into(schema::submissions::table)
+                .values(&*submission)
+                .execute(conn) {
+                Ok(_) => {
+                    tracing::Span::current().record("submission_number", &submission.ch_submission_id.as_str());
+                    break Ok(());
+                }
+                Err(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => continue,
+                Err(err) => return Err(tonic::Status::internal(format!("Unable to save submission to DB: {}", err)))
+            }
+        }
+    }
+
+    /// Removes a reserved submission that the gateway never accepted
+    fn release_submission(
+        conn: &diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>,
+        submission_id: uuid::Uuid,
+    ) {
+        if let Err(err) = diesel::delete(schema::submissions::table)
+            .filter(schema::submissions::dsl::id.eq(submission_id))
+            .execute(conn) {
+            error!("Unable to remove unsent submission {}: {}", submission_id, err);
+        }
+    }
+
+    /// Records the gateway's received time against a submission that it has accepted, making it pending
+    /// so that the watcher starts polling for its status
+    fn confirm_submission(
+        conn: &diesel::r2d2::PooledConnection<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>,
+        submission_id: uuid::Uuid, received_timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), tonic::Status> {
+        if let Err(err) = tracing::info_span!("db.update_submission", submission_id = %submission_id).in_scope(|| {
+            conn.transaction::<_, diesel::result::Error, _>(|| {
+                diesel::update(schema::submissions::table)
+                    .filter(schema::submissions::dsl::id.eq(submission_id))
+                    .set(schema::submissions::dsl::received_timestamp.eq(received_timestamp.naive_utc()))
+                    .execute(conn)?;
+                // The status queue may already have reported on it
+                diesel::update(schema::submissions::table)
+                    .filter(schema::submissions::dsl::id.eq(submission_id))
+                    .filter(schema::submissions::dsl::status.eq(schema::Status::Unsent))
+                    .set(schema::submissions::dsl::status.eq(schema::Status::Pending))
+                    .execute(conn)
+            })
+        }) {
+            return Err(tonic::Status::internal(format!("Unable to save submission to DB: {}", err)));
+        }
+        Ok(())
+    }
+
+    fn cipher(&self) -> Result<&crypto::Cipher, tonic::Status> {
+        match &self.cipher {
+            Some(c) => Ok(c),
+            None => Err(tonic::Status::failed_precondition("No encryption key configured"))
+        }
+    }
+
+    /// Encrypts data to be stored if an encryption key is configured, returning whether it was encrypted
+    async fn encrypt_at_rest(&self, data: Vec<u8>) -> Result<(Vec<u8>, bool), String> {
+        match &self.cipher {
+            Some(c) => Ok((c.encrypt(&data).await?, true)),
+            None => Ok((data, false))
+        }
+    }
+
+    pub(crate) async fn decrypt_at_rest(&self, data: Vec<u8>, encrypted: bool) -> Result<Vec<u8>, String> {
+        if !encrypted {
+            return Ok(data);
+        }
+        match &self.cipher {
+            Some(c) => c.decrypt(&data).await,
+            None => Err("Data is encrypted but no encryption key is configured".to_string())
+        }
+    }
+
+    /// Incorporation submissions store the new company's authentication code, encrypted if a key is configured
+    async fn submission_authentication_code(&self, submission: &models::Submission) -> Result<Option<String>, String> {
+        match &submission.authentication_code_encrypted {
+            Some(c) => match String::from_utf8(self.decrypt_at_rest(c.clone(), true).await?) {
+                Ok(c) => Ok(Some(c)),
+                Err(_) => Err("Invalid authentication code encoding".to_string())
+            },
+            None => Ok(submission.authentication_code.clone())
+        }
+    }
 
-            if submission_count == 0 {
-                break Ok(submission_number);
+    pub(crate) async fn load_document(&self, id: &str) -> Result<models::Documents, tonic::Status> {
+        let document_id = match uuid::Uuid::parse_str(id) {
+            Ok(i) => i,
+            Err(_) => {
+                return Err(tonic::Status::not_found("Invalid document ID"));
+            }
+        };
+        match schema::documents::dsl::documents
+            .filter(schema::documents::dsl::id.eq(document_id))
+            .get_result_async::<models::Documents>(&self.connection).await
+            .optional() {
+            Ok(Some(s)) => Ok(s),
+            Ok(None) => Err(tonic::Status::not_found("Document not found")),
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                Err(tonic::Status::internal("Error accessing database"))
             }
         }
     }
 
-    async fn form_submission(
-        &self,
-        form_submission: Option<ch_ewf_grpc::form_submission::FormSubmission>,
-        submission_class: &str, form_type: &str,
-        form: proto::form_submission::Form,
-        documents: Vec<proto::form_submission::Document>,
-    ) -> Result<ch_ewf_grpc::form_submission::SubmissionResponse, tonic::Status> {
-        let conn = match self.connection.get() {
-            Ok(c) => c,
-            Err(err) => return Err(tonic::Status::internal(format!("Unable to get DB connection: {}", err)))
+    /// Reads a stored document, checking it against its recorded content hash
+    pub(crate) async fn read_document(&self, document: &models::Documents) -> Result<Vec<u8>, tonic::Status> {
+        let file_data = match self.document_store.get(&document.storage_filename).await {
+            Ok(d) => d,
+            Err(err) => {
+                error!("Unable to read document: {}", err);
+                return Err(tonic::Status::internal("Error accessing document"));
+            }
+        };
+        let file_data = match self.decrypt_at_rest(file_data, document.encrypted).await {
+            Ok(d) => d,
+            Err(err) => {
+                error!("Unable to decrypt document: {}", err);
+                return Err(tonic::Status::internal("Error accessing document"));
+            }
         };
+        if let Some(content_hash) = &document.content_hash {
+            if &document_store::content_hash(&file_data) != content_hash {
+                error!("Document {} does not match its content hash", document.id);
+                return Err(tonic::Status::data_loss("Document does not match its content hash"));
+            }
+        }
+        Ok(file_data)
+    }
 
-        let submission_number = Self::gen_submission_number(&conn)?;
-        let submission_id = uuid::Uuid::new_v4();
+    /// Streams a stored document in chunks from `offset`, for `length` bytes or to the end if 0, giving the size
+    /// and content hash of the whole document. Unencrypted documents are streamed straight from the store and, when
+    /// read whole, checked against their content hash on the way, ending the stream with an error if they don't
+    /// match. Encrypted documents have to be read into memory to be decrypted, as do documents with no recorded
+    /// hash to have one to give.
+    pub(crate) async fn stream_document(
+        &self, document: &models::Documents, offset: u64, length: u64,
+    ) -> Result<(u64, String, tokio_stream::wrappers::ReceiverStream<Result<Vec<u8>, tonic::Status>>), tonic::Status> {
+        use tokio_stream::StreamExt;
+
+        let (size, content_hash, mut chunks, verify): (_, _, document_store::DocumentStream, _) = match &document.content_hash {
+            Some(h) if !document.encrypted => match self.document_store.get_stream(&document.storage_filename).await {
+                Ok(r) => (r.size, h.clone(), r.chunks, true),
+                Err(err) => {
+                    error!("Unable to read document: {}", err);
+                    return Err(tonic::Status::internal("Error accessing document"));
+                }
+            },
+            _ => {
+                let file_data = self.read_document(document).await?;
+                let content_hash = document_store::content_hash(&file_data);
+                let size = file_data.len() as u64;
+                let chunks: document_store::DocumentStream = Box::pin(tokio_stream::iter(std::iter::once(Ok(file_data))));
+                (size, content_hash, chunks, false)
+            }
+        };
+        if offset > size {
+            return Err(tonic::Status::out_of_range("Offset past end of document"));
+        }
+        let end = match length {
+            0 => size,
+            l => std::cmp::min(size, offset.saturating_add(l))
+        };
+        let verify = verify && offset == 0 && end == size;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let document_id = document.id;
+        let expected_hash = content_hash.clone();
+        tokio::spawn(async move {
+            let mut hasher = document_store::ContentHasher::default();
+            let mut position = 0;
+            let mut buf = Vec::with_capacity(DOCUMENT_CHUNK_SIZE);
+            while let Some(chunk) = chunks.next().await {
+                let chunk = match chunk {
+                    Ok(c) => c,
+                    Err(err) => {
+                        error!("Unable to read document {}: {}", document_id, err);
+                        let _ = tx.send(Err(tonic::Status::internal("Error accessing document"))).await;
+                        return;
+                    }
+                };
+                if verify {
+                    hasher.update(&chunk);
+                }
+                let chunk_start = position;
+                position += chunk.len() as u64;
+                let from = std::cmp::min(offset.saturating_sub(chunk_start), chunk.len() as u64) as usize;
+                let to = std::cmp::min(end.saturating_sub(chunk_start), chunk.len() as u64) as usize;
+                buf.extend_from_slice(&chunk[from..to]);
+                while buf.len() >= DOCUMENT_CHUNK_SIZE {
+                    let rest = buf.split_off(DOCUMENT_CHUNK_SIZE);
+                    if tx.send(Ok(std::mem::replace(&mut buf, rest))).await.is_err() {
+                        return;
+                    }
+                }
+                if position >= end && !verify {
+                    break;
+                }
+            }
+            if position < end {
+                error!("Document {} is shorter than expected", document_id);
+                let _ = tx.send(Err(tonic::Status::data_loss("Document is shorter than expected"))).await;
+                return;
+            }
+            // The last chunk is held back until the whole document has been checked
+            if verify && hasher.finish() != expected_hash {
+                error!("Document {} does not match its content hash", document_id);
+                let _ = tx.send(Err(tonic::Status::data_loss("Document does not match its content hash"))).await;
+                return;
+            }
+            if !buf.is_empty() {
+                let _ = tx.send(Ok(buf)).await;
+            }
+        });
+
+        Ok((size, content_hash, tokio_stream::wrappers::ReceiverStream::new(rx)))
+    }
+
+    async fn snapshot_data(
+        &self, snapshot: &models::CompanySnapshot,
+    ) -> Result<ch_ewf_grpc::company_data::CompanyDataResponse, tonic::Status> {
+        let data = match self.decrypt_at_rest(snapshot.data.clone(), snapshot.data_encrypted).await {
+            Ok(d) => d,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to decrypt snapshot data: {}", err)))
+        };
+        match company_history::decode_snapshot(&data) {
+            Ok(d) => Ok(d),
+            Err(err) => Err(tonic::Status::internal(format!("Invalid snapshot data: {}", err)))
+        }
+    }
+
+    async fn load_managed_company(&self, id: &str) -> Result<models::ManagedCompany, tonic::Status> {
+        let id = match uuid::Uuid::parse_str(id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid managed company ID"))
+        };
+        match schema::managed_companies::dsl::managed_companies
+            .find(id)
+            .get_result_async::<models::ManagedCompany>(&self.connection).await
+            .optional() {
+            Ok(Some(c)) => Ok(c),
+            Ok(None) => Err(tonic::Status::not_found("Managed company not found")),
+            Err(err) => Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        }
+    }
+
+    /// Fills in the credentials of the managed company a request refers to, if any
+    #[tracing::instrument(skip_all)]
+    async fn resolve_managed_company<T: managed_companies::CompanyCredentials>(&self, msg: &mut T) -> Result<(), tonic::Status> {
+        if msg.managed_company_id().is_empty() {
+            return Ok(());
+        }
+
+        let company = self.load_managed_company(msg.managed_company_id()).await?;
+        let authentication_code = match self.cipher()?.decrypt(&company.authentication_code).await
+            .map(String::from_utf8) {
+            Ok(Ok(c)) => c,
+            _ => return Err(tonic::Status::internal("Unable to decrypt authentication code"))
+        };
+        msg.set_credentials(&company, authentication_code);
+        Ok(())
+    }
 
+    #[tracing::instrument(skip_all)]
+    fn validate_form_submission(
+        form_submission: Option<ch_ewf_grpc::form_submission::FormSubmission>
+    ) -> Result<(ch_ewf_grpc::form_submission::FormSubmission, proto::base_types::CompanyType), tonic::Status> {
         let form_submission = match form_submission {
             Some(f) => f,
             None => return Err(tonic::Status::invalid_argument("Form submission required".to_string()))
@@ -144,59 +439,38 @@ impl CHFillingService {
             None => return Err(tonic::Status::invalid_argument("Invalid company type".to_string()))
         };
 
-        let contact_details = form_submission.contact_name.is_empty() && form_submission.contact_number.is_empty();
-        let res = match gov_talk::exec_govtalk_transaction(
-            &self.sender, submission_class,
-            proto::govtalk::GovTalkBody::FormSubmission(Box::new(proto::form_submission::FormSubmission {
-                form_header: proto::form_submission::FormHeader {
-                    company_number: Some(form_submission.company_number),
-                    company_type: Some(company_type.clone()),
-                    company_name: form_submission.company_name.to_uppercase(),
-                    company_authentication_code: Some(form_submission.authentication_code),
-                    package_reference: self.package_reference.clone(),
-                    language: match ch_ewf_grpc::form_submission::Language::from_i32(form_submission.language) {
-                        Some(ch_ewf_grpc::form_submission::Language::English) => proto::form_submission::SubmissionLanguage::English,
-                        Some(ch_ewf_grpc::form_submission::Language::Welsh) => proto::form_submission::SubmissionLanguage::Welsh,
-                        None => return Err(tonic::Status::invalid_argument("Language required".to_string()))
-                    },
-                    form_identifier: form_type.to_string(),
-                    submission_number: submission_number.clone(),
-                    contact_name: if contact_details {
-                        None
-                    } else {
-                        Some(form_submission.contact_name)
-                    },
-                    contact_number: if contact_details {
-                        None
-                    } else {
-                        Some(form_submission.contact_number)
-                    },
-                    customer_reference: form_submission.customer_reference.clone(),
-                },
-                date_signed: match proto_to_chrono(form_submission.date_signed) {
-                    Some(d) => d.date(),
-                    None => return Err(tonic::Status::invalid_argument("Date signed required".to_string()))
-                },
-                form,
-                additional_information: None,
-                documents,
-            })),
-        ).await {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(tonic::Status::unknown(
-                    format!("Transaction ID: {}; error description: {}", e.transaction_id, e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "))
-                ));
-            }
-        };
+        Ok((form_submission, company_type))
+    }
+
+    #[tracing::instrument(
+        skip(self, form_submission, form, documents),
+        fields(submission_id = tracing::field::Empty, transaction_id = tracing::field::Empty)
+    )]
+    async fn form_submission(
+        &self,
+        form_submission: Option<ch_ewf_grpc::form_submission::FormSubmission>,
+        submission_class: &str, form_type: &str,
+        form: proto::form_submission::Form,
+        documents: Vec<proto::form_submission::Document>,
+    ) -> Result<ch_ewf_grpc::form_submission::SubmissionResponse, tonic::Status> {
+        let mut form_submission = form_submission;
+        if let Some(f) = form_submission.as_mut() {
+            self.resolve_managed_company(f).await?;
+        }
 
+        let submission_id = self.submission_id.unwrap_or_else(uuid::Uuid::new_v4);
+        tracing::Span::current().record("submission_id", &tracing::field::display(submission_id));
+
+        let (form_submission, company_type) = Self::validate_form_submission(form_submission)?;
+
+        let contact_details = form_submission.contact_name.is_empty() && form_submission.contact_number.is_empty();
         let new_submission = models::Submission {
             id: submission_id,
-            ch_submission_id: submission_number.clone(),
+            ch_submission_id: String::new(),
             company_number: Some(format!("{}{}", company_type.to_string(), form_submission.company_number)),
-            received_timestamp: res.gateway_timestamp.naive_utc(),
-            customer_reference: form_submission.customer_reference,
-            status: schema::Status::Pending,
+            received_timestamp: chrono::Utc::now().naive_utc(),
+            customer_reference: form_submission.customer_reference.clone(),
+            status: schema::Status::Unsent,
             reject_reference: None,
             examiner_telephone: None,
             examiner_comment: None,
@@ -204,14 +478,96 @@ impl CHFillingService {
             authentication_code: None,
             charge_code: None,
             incorporation_date: None,
+            form_type: Some(form_type.to_string()),
+            authentication_code_encrypted: None,
+            document_request_key: None,
+        };
+        let body = proto::form_submission::FormSubmission {
+            form_header: proto::form_submission::FormHeader {
+                company_number: Some(form_submission.company_number),
+                company_type: Some(company_type),
+                company_name: form_submission.company_name.to_uppercase(),
+                company_authentication_code: Some(form_submission.authentication_code),
+                package_reference: self.package_reference.clone(),
+                language: match ch_ewf_grpc::form_submission::Language::from_i32(form_submission.language) {
+                    Some(ch_ewf_grpc::form_submission::Language::English) => proto::form_submission::SubmissionLanguage::English,
+                    Some(ch_ewf_grpc::form_submission::Language::Welsh) => proto::form_submission::SubmissionLanguage::Welsh,
+                    None => return Err(tonic::Status::invalid_argument("Language required".to_string()))
+                },
+                form_identifier: form_type.to_string(),
+                submission_number: String::new(),
+                contact_name: if contact_details {
+                    None
+                } else {
+                    Some(form_submission.contact_name)
+                },
+                contact_number: if contact_details {
+                    None
+                } else {
+                    Some(form_submission.contact_number)
+                },
+                customer_reference: form_submission.customer_reference,
+            },
+            date_signed: match proto_to_chrono(form_submission.date_signed) {
+                Some(d) => d.date(),
+                None => return Err(tonic::Status::invalid_argument("Date signed required".to_string()))
+            },
+            form,
+            additional_information: None,
+            documents,
         };
 
-        if let Err(err) = diesel::insert_into(schema::submissions::table)
-            .values(new_submission)
-            .execute(&conn) {
-            return Err(tonic::Status::internal(format!("Unable to save submission to DB: {}", err)));
+        self.send_form_submission(new_submission, submission_class, form_type, body).await
+    }
+
+    /// Reserves a submission number, sends the form and records that the gateway received it. A dry run
+    /// only renders the message, with a throwaway submission number and nothing written to the DB.
+    async fn send_form_submission(
+        &self, mut new_submission: models::Submission, submission_class: &str, form_type: &str,
+        mut body: proto::form_submission::FormSubmission,
+    ) -> Result<ch_ewf_grpc::form_submission::SubmissionResponse, tonic::Status> {
+        let transaction_error = |e: gov_talk::GovTalkErrors| tonic::Status::unknown(format!(
+            "Transaction ID: {}; error description: {}",
+            e.transaction_id, e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; ")
+        ));
+
+        if self.sender.is_dry_run() {
+            body.form_header.submission_number = Self::gen_submission_number();
+            return match gov_talk::exec_govtalk_transaction(
+                &self.sender, submission_class,
+                proto::govtalk::GovTalkBody::FormSubmission(Box::new(body)),
+            ).await {
+                Ok(_) => Err(tonic::Status::internal("Dry run sent a message")),
+                Err(e) => Err(transaction_error(e))
+            };
         }
 
+        let conn = match self.connection.get() {
+            Ok(c) => c,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to get DB connection: {}", err)))
+        };
+        let submission_id = new_submission.id;
+
+        Self::reserve_submission(&conn, &mut new_submission)?;
+        let submission_number = new_submission.ch_submission_id;
+        body.form_header.submission_number = submission_number.clone();
+
+        let res = match gov_talk::exec_govtalk_transaction(
+            &self.sender, submission_class,
+            proto::govtalk::GovTalkBody::FormSubmission(Box::new(body)),
+        ).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::Span::current().record("transaction_id", &e.transaction_id.as_str());
+                Self::release_submission(&conn, submission_id);
+                return Err(transaction_error(e));
+            }
+        };
+        tracing::Span::current().record("transaction_id", &res.transaction_id.as_str());
+
+        Self::confirm_submission(&conn, submission_id, res.gateway_timestamp)?;
+        metrics::SUBMISSIONS.with_label_values(&[form_type]).inc();
+
         Ok(ch_ewf_grpc::form_submission::SubmissionResponse {
             transaction_id: res.transaction_id,
             submission_id: submission_id.to_string(),
@@ -219,10 +575,25 @@ impl CHFillingService {
         })
     }
 
-    pub async fn watcher(&self) {
+    pub async fn watcher(&self, mut leader_lock: leader::LeaderLock, reconciliation: ReconciliationConfig) {
+        let reconciliation_interval = std::time::Duration::from_secs(reconciliation.interval_minutes * 60);
+        let mut last_reconciliation: Option<std::time::Instant> = None;
+
         'outer: loop {
             tokio::time::sleep(std::time::Duration::from_secs(30)).await;
 
+            if !leader_lock.is_leader().await {
+                metrics::WATCHER_LEADER.set(0);
+                last_reconciliation = None;
+                continue;
+            }
+            metrics::WATCHER_LEADER.set(1);
+
+            if last_reconciliation.map_or(true, |t| t.elapsed() >= reconciliation_interval) {
+                self.reconcile_stale_submissions(chrono::Duration::hours(reconciliation.stale_after_hours)).await;
+                last_reconciliation = Some(std::time::Instant::now());
+            }
+
             let pending_count: i64 = match schema::submissions::dsl::submissions
                 .filter(schema::submissions::dsl::status.eq(schema::Status::Pending))
                 .count()
@@ -230,11 +601,29 @@ impl CHFillingService {
                 Ok(c) => c,
                 Err(err) => {
                     error!("Unable to access DB: {}", err);
+                    metrics::WATCHER_POLLS.with_label_values(&["db_error"]).inc();
+                    continue;
+                }
+            };
+            let oldest_pending: Option<chrono::NaiveDateTime> = match schema::submissions::dsl::submissions
+                .filter(schema::submissions::dsl::status.eq(schema::Status::Pending))
+                .select(diesel::dsl::min(schema::submissions::dsl::received_timestamp))
+                .get_result_async(&self.connection).await {
+                Ok(c) => c,
+                Err(err) => {
+                    error!("Unable to access DB: {}", err);
+                    metrics::WATCHER_POLLS.with_label_values(&["db_error"]).inc();
                     continue;
                 }
             };
+            metrics::PENDING_SUBMISSIONS.set(pending_count);
+            metrics::OLDEST_PENDING_SUBMISSION_AGE.set(oldest_pending.map(|t| {
+                (chrono::Utc::now().naive_utc() - t).num_milliseconds() as f64 / 1000.0
+            }).unwrap_or(0.0));
 
-            if pending_count > 0 {
+            if pending_count == 0 {
+                metrics::WATCHER_POLLS.with_label_values(&["idle"]).inc();
+            } else {
                 let res = match gov_talk::exec_govtalk_transaction(&self.sender, "GetSubmissionStatus", proto::govtalk::GovTalkBody::GetSubmissionStatus(
                     proto::submission_status::GetSubmissionStatus {
                         reference: None,
@@ -244,6 +633,7 @@ impl CHFillingService {
                     Ok(c) => c,
                     Err(e) => {
                         if e.errors.iter().all(|e| e.code == 8026) {
+                            metrics::WATCHER_POLLS.with_label_values(&["no_updates"]).inc();
                             continue;
                         }
                         error!(
@@ -251,6 +641,7 @@ impl CHFillingService {
                             e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "),
                             e.transaction_id
                         );
+                        metrics::WATCHER_POLLS.with_label_values(&["gateway_error"]).inc();
                         continue;
                     }
                 };
@@ -259,106 +650,16 @@ impl CHFillingService {
                     Some(proto::govtalk::GovTalkBody::SubmissionStatus(s)) => s,
                     _ => {
                         error!("Mismatched response body received for submission status");
+                        metrics::WATCHER_POLLS.with_label_values(&["gateway_error"]).inc();
                         continue;
                     }
                 };
 
                 for status in body.status {
-                    let submission = match schema::submissions::dsl::submissions
-                        .filter(schema::submissions::dsl::ch_submission_id.eq(status.submission_number.clone()))
-                        .get_result_async::<models::Submission>(&self.connection).await
-                        .optional() {
-                        Ok(c) => c,
-                        Err(err) => {
-                            error!("Unable to access DB: {}", err);
-                            continue 'outer;
-                        }
-                    };
-                    if let Some(mut submission) = submission {
-                        let mut new_rejections = vec![];
-                        submission.status = match status.status_code {
-                            proto::submission_status::StatusCode::Pending => schema::Status::Pending,
-                            proto::submission_status::StatusCode::Accepted => schema::Status::Accepted,
-                            proto::submission_status::StatusCode::Rejected => schema::Status::Rejected,
-                            proto::submission_status::StatusCode::Parked => schema::Status::Parked,
-                            proto::submission_status::StatusCode::InternalFailure => schema::Status::InternalFailure,
-                        };
-                        submission.customer_reference = status.customer_reference;
-                        if submission.company_number.is_none() {
-                            submission.company_number = status.company_number;
-                        }
-                        if let Some(rejections) = status.rejections {
-                            submission.reject_reference = rejections.reject_reference;
-                            for rejection in rejections.rejections {
-                                let new_rejection = models::SubmissionRejection {
-                                    id: uuid::Uuid::new_v4(),
-                                    submission_id: submission.id,
-                                    code: rejection.reject_code,
-                                    description: rejection.description,
-                                    instance_number: rejection.instance_number,
-                                };
-                                new_rejections.push(new_rejection);
-                            }
-                        }
-                        if let Some(examiner) = status.examiner {
-                            submission.examiner_telephone = Some(examiner.telephone);
-                            submission.examiner_comment = examiner.comment;
-                        }
-                        match status.details {
-                            Some(proto::submission_status::StatusDetails::Incorporation(i)) => {
-                                let document_id = match self.get_document(&i.document_request_key).await {
-                                    Ok(d) => d,
-                                    Err(err) => {
-                                        error!("Unable to get document: {}", err);
-                                        continue 'outer;
-                                    }
-                                };
-                                submission.document_id = Some(document_id);
-                                submission.incorporation_date = Some(i.incorporation_date.naive_utc());
-                                submission.authentication_code = Some(i.authentication_code)
-                            }
-                            Some(proto::submission_status::StatusDetails::ChangeOfName(c)) => {
-                                let document_id = match self.get_document(&c.document_request_key).await {
-                                    Ok(d) => d,
-                                    Err(err) => {
-                                        error!("Unable to get document: {}", err);
-                                        continue 'outer;
-                                    }
-                                };
-                                submission.document_id = Some(document_id);
-                            }
-                            Some(proto::submission_status::StatusDetails::Charge(c)) => {
-                                let document_id = match self.get_document(&c.document_request_key).await {
-                                    Ok(d) => d,
-                                    Err(err) => {
-                                        error!("Unable to get document: {}", err);
-                                        continue 'outer;
-                                    }
-                                };
-                                submission.document_id = Some(document_id);
-                                submission.charge_code = Some(c.charge_code)
-                            }
-                            None => {}
-                        }
-                        if let Err(err) = self.connection.transaction(|c| {
-                            diesel::update(schema::submissions::table)
-                                .filter(schema::submissions::dsl::id.eq(submission.id))
-                                .set(submission)
-                                .execute(c)?;
-
-                            for rejection in new_rejections {
-                                diesel::insert_into(schema::submission_rejections::table)
-                                    .values(rejection)
-                                    .execute(c)?;
-                            }
-
-                            Ok(())
-                        }).await {
-                            error!("Unable to access DB: {}", err);
-                            continue;
-                        }
-                    } else {
-                        warn!("Unknown submission ID {}", status.submission_number);
+                    if let Err(err) = self.process_submission_status(status).await {
+                        error!("{}", err);
+                        metrics::WATCHER_POLLS.with_label_values(&["processing_error"]).inc();
+                        continue 'outer;
                     }
                 }
 
@@ -366,249 +667,50 @@ impl CHFillingService {
                     Ok(_) => {}
                     Err(e) => {
                         error!("Unable to ack submission status: {}, (trans ID: {})", e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "), e.transaction_id);
+                        metrics::WATCHER_POLLS.with_label_values(&["gateway_error"]).inc();
                         continue;
                     }
                 };
+                metrics::WATCHER_POLLS.with_label_values(&["updated"]).inc();
             }
         }
     }
 
-    async fn get_document(
-        &self, document_key: &str,
-    ) -> Result<uuid::Uuid, String> {
-        let res = match gov_talk::exec_govtalk_transaction(&self.sender, "GetDocument", proto::govtalk::GovTalkBody::GetDocument(
-            proto::document::GetDocument {
-                document_request_key: document_key.to_string()
+    async fn get_submission_status(
+        &self, submission_id: uuid::Uuid,
+    ) -> Result<ch_ewf_grpc::form_submission::SubmissionStatusResponse, tonic::Status> {
+        let submission = match schema::submissions::dsl::submissions
+            .filter(schema::submissions::dsl::id.eq(submission_id))
+            .get_result_async::<models::Submission>(&self.connection).await
+            .optional() {
+            Ok(Some(s)) => s,
+            Ok(None) => {
+                return Err(tonic::Status::not_found("Submission not found"));
             }
-        )).await {
-            Ok(c) => c,
-            Err(e) => {
-                return Err(format!("Unable to get document: {}, (trans ID: {})", e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "), e.transaction_id));
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return Err(tonic::Status::internal("Error accessing database"));
             }
         };
-
-        let body = match res.body {
-            Some(proto::govtalk::GovTalkBody::Document(s)) => s,
-            _ => {
-                return Err("Mismatched response body received for document".to_string());
-            }
-        };
-
-        let filename_id = uuid::Uuid::new_v4();
-        let filename_ext = match body.document_data.content_type {
-            proto::document::ContentType::Pdf => "pdf"
-        };
-        let filename = format!("{}.{}", filename_id, filename_ext);
-        let file_path = self.documents_path.join(&filename);
-        let file_data = match body.document_data.content_encoding {
-            proto::document::ContentEncoding::Base64 => match base64::decode(body.document_data.contents.replace('\n', "")) {
-                Ok(c) => c,
-                Err(err) => return Err(format!("Invalid document encoding: {}", err))
-            }
-        };
-
-        if let Err(err) = tokio::fs::write(file_path, &file_data).await {
-            return Err(format!("Unable to save document: {}", err));
-        }
-
-        let document_id = uuid::Uuid::new_v4();
-        let new_document = models::Documents {
-            id: document_id,
-            company_number: body.company_number,
-            document_date: body.document_date.unwrap_or_else(chrono::Utc::today).naive_utc(),
-            document_type: body.document_type.unwrap_or_default(),
-            document_id: body.document_id,
-            document_filename: body.document_data.filename.unwrap_or_default(),
-            storage_filename: filename,
-        };
-
-        if let Err(err) = diesel::insert_into(schema::documents::table)
-            .values(new_document)
-            .execute_async(&self.connection).await {
-            return Err(format!("Unable to access DB: {}", err));
-        }
-
-        Ok(document_id)
-    }
-}
-
-impl From<proto::e_reminders::EReminders> for ch_ewf_grpc::e_reminders::EReminders {
-    fn from(body: proto::e_reminders::EReminders) -> Self {
-        ch_ewf_grpc::e_reminders::EReminders {
-            recipients: body.recipients.into_iter().map(|r| ch_ewf_grpc::e_reminders::EReminderRecipient {
-                email_address: r.email,
-                activated: r.activated,
-            }).collect()
-        }
-    }
-}
-
-impl TryFrom<ch_ewf_grpc::company_incorporation::Authorizer> for proto::company_incorporation::AuthoriserType {
-    type Error = tonic::Status;
-
-    fn try_from(value: ch_ewf_grpc::company_incorporation::Authorizer) -> Result<Self, Self::Error> {
-        if value.personal_attributes.len() != 3 {
-            return Err(tonic::Status::invalid_argument("Invalid number of personal attributes".to_string()));
-        }
-
-        Ok(proto::company_incorporation::AuthoriserType {
-            name: match value.name {
-                Some(ch_ewf_grpc::company_incorporation::authorizer::Name::Person(p)) =>
-                    proto::company_incorporation::IncorporationPersonName::Person(p.try_into()?),
-                Some(ch_ewf_grpc::company_incorporation::authorizer::Name::Corporate(c)) => {
-                    if c.corporate_name.is_empty() || c.corporate_name.len() > 160 {
-                        return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
-                    }
-                    proto::company_incorporation::IncorporationPersonName::Corporate(proto::company_incorporation::CorporateName {
-                        person_name: match c.person {
-                            Some(p) => p.try_into()?,
-                            None => return Err(tonic::Status::invalid_argument("Person name required".to_string()))
-                        },
-                        corporate_name: c.corporate_name,
-                    })
-                }
-                None => return Err(tonic::Status::invalid_argument("Authorizer name required".to_string()))
-            },
-            authentication: value.personal_attributes.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?,
-        })
-    }
-}
-
-impl TryFrom<ch_ewf_grpc::company_incorporation::HmrcAddress> for proto::corporation_tax_information::Address {
-    type Error = tonic::Status;
-
-    fn try_from(value: ch_ewf_grpc::company_incorporation::HmrcAddress) -> Result<Self, Self::Error> {
-        if value.address_line_1.is_empty() || value.address_line_1.len() > 27 {
-            return Err(tonic::Status::invalid_argument("Invalid address line 1".to_string()));
-        }
-        if value.address_line_2.is_empty() || value.address_line_2.len() > 27 {
-            return Err(tonic::Status::invalid_argument("Invalid address line 2".to_string()));
-        }
-
-        Ok(proto::corporation_tax_information::Address {
-            address_line_1: value.address_line_1,
-            address_line_2: value.address_line_2,
-            address_line_3: if value.address_line_3.is_empty() {
-                None
-            } else {
-                if value.address_line_3.len() > 27 {
-                    return Err(tonic::Status::invalid_argument("Invalid address line 3".to_string()));
-                }
-                Some(value.address_line_3)
-            },
-            address_line_4: if value.address_line_4.is_empty() {
-                None
-            } else {
-                if value.address_line_4.len() > 18 {
-                    return Err(tonic::Status::invalid_argument("Invalid address line 4".to_string()));
-                }
-                Some(value.address_line_4)
-            },
-            post_code: if value.post_code.is_empty() {
-                None
-            } else {
-                if value.post_code.len() > 15 {
-                    return Err(tonic::Status::invalid_argument("Invalid postcode".to_string()));
-                }
-                Some(value.post_code)
-            },
-            country: if value.country.is_empty() {
-                None
-            } else {
-                if value.country.len() > 20 {
-                    return Err(tonic::Status::invalid_argument("Invalid country".to_string()));
-                }
-                Some(value.country)
-            },
-        })
-    }
-}
-
-impl TryFrom<ch_ewf_grpc::company_incorporation::Person> for proto::company_incorporation::IncorporationPerson {
-    type Error = tonic::Status;
-
-    fn try_from(value: ch_ewf_grpc::company_incorporation::Person) -> Result<Self, Self::Error> {
-        if value.personal_attributes.len() != 3 {
-            return Err(tonic::Status::invalid_argument("Invalid number of personal attributes".to_string()));
-        }
-
-        Ok(proto::company_incorporation::IncorporationPerson {
-            name: match value.name {
-                Some(ch_ewf_grpc::company_incorporation::person::Name::Person(p)) =>
-                    proto::company_incorporation::IncorporationPersonName::Person(p.try_into()?),
-                Some(ch_ewf_grpc::company_incorporation::person::Name::Corporate(c)) => {
-                    if c.corporate_name.is_empty() || c.corporate_name.len() > 160 {
-                        return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
-                    }
-                    proto::company_incorporation::IncorporationPersonName::Corporate(proto::company_incorporation::CorporateName {
-                        person_name: match c.person {
-                            Some(p) => p.try_into()?,
-                            None => return Err(tonic::Status::invalid_argument("Person name required".to_string()))
-                        },
-                        corporate_name: c.corporate_name,
-                    })
-                }
-                None => return Err(tonic::Status::invalid_argument("Authorizer name required".to_string()))
-            },
-            address: match value.address {
-                Some(a) => a.try_into()?,
-                None => return Err(tonic::Status::invalid_argument("Person address required".to_string()))
-            },
-            authentication: value.personal_attributes.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?,
-            member_class: if value.member_class.is_empty() {
-                None
-            } else {
-                if value.member_class.len() > 50 {
-                    return Err(tonic::Status::invalid_argument("Invalid member class".to_string()));
-                }
-                Some(value.member_class)
-            },
-        })
-    }
-}
-
-#[tonic::async_trait]
-impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
-    async fn submission_status(
-        &self,
-        request: tonic::Request<ch_ewf_grpc::form_submission::SubmissionStatusRequest>,
-    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionStatusResponse>, tonic::Status> {
-        let msg = request.into_inner();
-
-        let submission_id = match uuid::Uuid::parse_str(&msg.submission_id) {
-            Ok(i) => i,
-            Err(_) => {
-                return Err(tonic::Status::not_found("Invalid submission ID"));
-            }
-        };
-        let submission = match schema::submissions::dsl::submissions
-            .filter(schema::submissions::dsl::id.eq(submission_id))
-            .get_result_async::<models::Submission>(&self.connection).await
-            .optional() {
-            Ok(Some(s)) => s,
-            Ok(None) => {
-                return Err(tonic::Status::not_found("Submission not found"));
-            }
-            Err(err) => {
-                error!("Unable to access DB: {}", err);
-                return Err(tonic::Status::internal("Error accessing database"));
-            }
-        };
-        let rejections = match schema::submission_rejections::dsl::submission_rejections
-            .filter(schema::submission_rejections::dsl::submission_id.eq(submission.id))
-            .get_results_async::<models::SubmissionRejection>(&self.connection).await {
-            Ok(r) => r,
-            Err(err) => {
-                error!("Unable to access DB: {}", err);
-                return Err(tonic::Status::internal("Error accessing database"));
+        let rejections = match schema::submission_rejections::dsl::submission_rejections
+            .filter(schema::submission_rejections::dsl::submission_id.eq(submission.id))
+            .get_results_async::<models::SubmissionRejection>(&self.connection).await {
+            Ok(r) => r,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return Err(tonic::Status::internal("Error accessing database"));
             }
         };
+        let authentication_code = match self.submission_authentication_code(&submission).await {
+            Ok(c) => c,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to decrypt authentication code: {}", err)))
+        };
 
         let reply = ch_ewf_grpc::form_submission::SubmissionStatusResponse {
             status: match submission.status {
                 schema::Status::Accepted => ch_ewf_grpc::form_submission::SubmissionStatus::Accepted.into(),
                 schema::Status::Rejected => ch_ewf_grpc::form_submission::SubmissionStatus::Rejected.into(),
-                schema::Status::Pending => ch_ewf_grpc::form_submission::SubmissionStatus::Pending.into(),
+                schema::Status::Pending | schema::Status::Unsent => ch_ewf_grpc::form_submission::SubmissionStatus::Pending.into(),
                 schema::Status::Parked => ch_ewf_grpc::form_submission::SubmissionStatus::Parked.into(),
                 schema::Status::InternalFailure => ch_ewf_grpc::form_submission::SubmissionStatus::InternalFailure.into(),
             },
@@ -626,7 +728,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
                 submission.incorporation_date
                     .map(|d| chrono::DateTime::from_utc(d.and_hms(0, 0, 0), chrono::Utc))
             ),
-            authentication_code: submission.authentication_code.unwrap_or_default(),
+            authentication_code: authentication_code.unwrap_or_default(),
             reject_reference: submission.reject_reference.unwrap_or_default(),
             rejections: rejections.into_iter().map(|r| ch_ewf_grpc::form_submission::Rejection {
                 reject_code: r.code,
@@ -635,166 +737,1177 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             }).collect(),
         };
 
-        Ok(tonic::Response::new(reply))
+        Ok(reply)
     }
 
-    async fn document(
-        &self,
-        request: tonic::Request<ch_ewf_grpc::form_submission::DocumentRequest>,
-    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::DocumentResponse>, tonic::Status> {
-        let msg = request.into_inner();
+    /// Queries the gateway for the status of a single submission, outside of the status queue
+    async fn refresh_submission(&self, submission_number: &str) -> Result<(), String> {
+        let res = match gov_talk::exec_govtalk_transaction(&self.sender, "GetSubmissionStatus", proto::govtalk::GovTalkBody::GetSubmissionStatus(
+            proto::submission_status::GetSubmissionStatus {
+                reference: Some(proto::submission_status::GetSubmissionStatusReference::SubmissionNumber(
+                    submission_number.to_string()
+                )),
+                presenter_id: self.presenter_id.clone(),
+            }
+        )).await {
+            Ok(c) => c,
+            Err(e) => {
+                if e.errors.iter().all(|e| e.code == 8026) {
+                    return Ok(());
+                }
+                return Err(format!(
+                    "Unable to query submission status: {}, (trans ID: {})",
+                    e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "),
+                    e.transaction_id
+                ));
+            }
+        };
 
-        let document_id = match uuid::Uuid::parse_str(&msg.document_id) {
-            Ok(i) => i,
-            Err(_) => {
-                return Err(tonic::Status::not_found("Invalid document ID"));
+        let body = match res.body {
+            Some(proto::govtalk::GovTalkBody::SubmissionStatus(s)) => s,
+            _ => return Err("Mismatched response body received for submission status".to_string())
+        };
+
+        for status in body.status {
+            self.process_submission_status(status).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Individually queries submissions that have been pending for longer than expected,
+    /// in case their status was lost from the status queue
+    async fn reconcile_stale_submissions(&self, stale_after: chrono::Duration) {
+        let stale_submissions = match schema::submissions::dsl::submissions
+            .filter(schema::submissions::dsl::status.eq(schema::Status::Pending))
+            .filter(schema::submissions::dsl::received_timestamp.lt((chrono::Utc::now() - stale_after).naive_utc()))
+            .get_results_async::<models::Submission>(&self.connection).await {
+            Ok(s) => s,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
             }
         };
-        let document = match schema::documents::dsl::documents
-            .filter(schema::documents::dsl::id.eq(document_id))
-            .get_result_async::<models::Documents>(&self.connection).await
-            .optional() {
-            Ok(Some(s)) => s,
-            Ok(None) => {
-                return Err(tonic::Status::not_found("Document not found"));
+
+        for submission in stale_submissions {
+            match self.refresh_submission(&submission.ch_submission_id).await {
+                Ok(()) => {
+                    metrics::RECONCILIATIONS.with_label_values(&["success"]).inc();
+                }
+                Err(err) => {
+                    error!("Unable to reconcile submission {}: {}", submission.id, err);
+                    metrics::RECONCILIATIONS.with_label_values(&["error"]).inc();
+                }
             }
+        }
+
+        let missing_documents = match schema::submissions::dsl::submissions
+            .filter(schema::submissions::dsl::document_request_key.is_not_null())
+            .filter(schema::submissions::dsl::document_id.is_null())
+            .get_results_async::<models::Submission>(&self.connection).await {
+            Ok(s) => s,
             Err(err) => {
                 error!("Unable to access DB: {}", err);
-                return Err(tonic::Status::internal("Error accessing database"));
+                return;
             }
         };
 
-        let file_path = self.documents_path.join(&document.storage_filename);
-        let file_data = match tokio::fs::read(file_path).await {
-            Ok(d) => d,
+        for submission in missing_documents {
+            if let Err(err) = self.refetch_submission_document(&submission).await {
+                error!("Unable to retry document for submission {}: {}", submission.id, err);
+            }
+        }
+
+        let incomplete_workflows = match schema::incorporation_workflows::dsl::incorporation_workflows
+            .filter(schema::incorporation_workflows::dsl::state.eq(schema::IncorporationWorkflowState::Accepted))
+            .get_results_async::<models::IncorporationWorkflow>(&self.connection).await {
+            Ok(w) => w,
             Err(err) => {
-                error!("Unable to read document: {}", err);
-                return Err(tonic::Status::internal("Error accessing document"));
+                error!("Unable to access DB: {}", err);
+                return;
             }
         };
 
-        let reply = ch_ewf_grpc::form_submission::DocumentResponse {
-            date: chrono_to_proto::<chrono::Utc>(Some(
-                chrono::DateTime::from_utc(document.document_date.and_hms(0, 0, 0), chrono::Utc)
-            )),
-            ch_document_id: document.document_id,
-            content_type: ch_ewf_grpc::form_submission::ContentType::Pdf.into(),
-            ch_filename: document.document_filename,
-            data: file_data,
+        for workflow in incomplete_workflows {
+            self.complete_incorporation_workflow(workflow).await;
+        }
+
+        // Items being submitted when the service stopped are settled first, so any put back to pending are resumed below
+        let stalled_items = match schema::bulk_submission_items::dsl::bulk_submission_items
+            .filter(schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Submitting))
+            .filter(schema::bulk_submission_items::dsl::updated_at.lt((chrono::Utc::now() - stale_after).naive_utc()))
+            .get_results_async::<models::BulkSubmissionItem>(&self.connection).await {
+            Ok(i) => i,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
         };
 
-        Ok(tonic::Response::new(reply))
+        for item in stalled_items {
+            if let Err(err) = self.recover_bulk_item(item).await {
+                error!("Unable to recover bulk submission item: {}", err);
+            }
+        }
+
+        // Bulk submissions interrupted by a restart are left with pending items
+        let stalled_bulk_submissions = match schema::bulk_submission_items::dsl::bulk_submission_items
+            .filter(schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Pending))
+            .filter(schema::bulk_submission_items::dsl::updated_at.lt((chrono::Utc::now() - stale_after).naive_utc()))
+            .select(schema::bulk_submission_items::dsl::bulk_submission_id)
+            .distinct()
+            .get_results_async::<uuid::Uuid>(&self.connection).await {
+            Ok(b) => b,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
+        };
+
+        for bulk_submission_id in stalled_bulk_submissions {
+            let service = self.clone();
+            tokio::task::spawn(async move {
+                service.process_bulk_submission(bulk_submission_id).await
+            });
+        }
     }
 
-    async fn company_data(
-        &self,
-        request: tonic::Request<ch_ewf_grpc::company_data::CompanyDataRequest>,
-    ) -> Result<tonic::Response<ch_ewf_grpc::company_data::CompanyDataResponse>, tonic::Status> {
-        let msg = request.into_inner();
-        Self::check_authentication_code(&msg.authentication_code)?;
+    /// Resolves a managed company ahead of `form_submission`, for forms that need the company number
+    /// before they can be built
+    async fn form_company_number(
+        &self, form_submission: &mut Option<ch_ewf_grpc::form_submission::FormSubmission>,
+    ) -> Result<String, tonic::Status> {
+        match form_submission.as_mut() {
+            Some(f) => {
+                self.resolve_managed_company(f).await?;
+                Self::format_company_number(f.company_number, f.company_type)
+            }
+            None => Err(tonic::Status::invalid_argument("Form submission required".to_string()))
+        }
+    }
 
-        let res = match gov_talk::exec_govtalk_transaction(
-            &self.sender, "CompanyDataRequest",
-            proto::govtalk::GovTalkBody::CompanyDataRequest(proto::company_data::CompanyDataRequest {
-                company_number: msg.company_number,
-                company_type: Self::map_company_type(msg.company_type),
-                company_authentication_code: msg.authentication_code,
-                made_up_date: proto_to_chrono(msg.made_up_date).map(|d| d.date()).unwrap_or_else(|| chrono::Utc::now().date()),
-            }),
-        ).await {
+    /// A statement of capital generated from the capital ledger
+    async fn ledger_statement_of_capital(
+        &self, company_number: String, as_at: chrono::NaiveDate,
+    ) -> Result<Vec<ch_ewf_grpc::base_types::Capital>, tonic::Status> {
+        let statement_of_capital = match self.connection.run(move |c| {
+            capital_ledger::Ledger::load(c, company_number, Some(as_at))
+        }).await {
+            Ok(l) => l.statement_of_capital(),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+        if statement_of_capital.is_empty() {
+            return Err(tonic::Status::failed_precondition("No shares in the capital ledger"));
+        }
+        Ok(statement_of_capital)
+    }
+
+    async fn load_register(
+        &self, msg: ch_ewf_grpc::registers::RegisterRequest,
+    ) -> Result<(schema::RegisterType, String, chrono::NaiveDate, Vec<registers::RegisterRow>), tonic::Status> {
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+        let register: schema::RegisterType = match ch_ewf_grpc::registers::RegisterType::from_i32(msg.register) {
+            Some(r) => r.into(),
+            None => return Err(tonic::Status::invalid_argument("Invalid register"))
+        };
+        let as_at = proto_to_chrono(msg.as_at)
+            .map(|d| d.naive_utc().date())
+            .unwrap_or_else(|| chrono::Utc::today().naive_utc());
+        let include_ceased = msg.include_ceased;
+
+        let query_company_number = company_number.clone();
+        let mut rows = match self.connection.run(move |c| {
+            registers::load_register(c, query_company_number, register, as_at, include_ceased)
+        }).await {
             Ok(r) => r,
-            Err(e) => {
-                return Err(tonic::Status::unknown(
-                    format!("Transaction ID: {}; error description: {}", e.transaction_id, e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "))
-                ));
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+        for row in &mut rows {
+            if let Some(personal_details) = row.entry.personal_details.take() {
+                let personal_details = self.decrypt_at_rest(personal_details, row.entry.personal_details_encrypted).await
+                    .map_err(tonic::Status::internal)?;
+                row.personal = match serde_json::from_slice(&personal_details) {
+                    Ok(p) => p,
+                    Err(err) => return Err(tonic::Status::internal(format!("Invalid register entry: {}", err)))
+                };
             }
+        }
+
+        Ok((register, company_number, as_at, rows))
+    }
+
+    /// Records the register changes made by a submission, which only show in the registers once it's accepted
+    async fn record_register_entries(
+        &self, reply: &ch_ewf_grpc::form_submission::SubmissionResponse, entries: Vec<registers::Entry>,
+    ) {
+        if entries.is_empty() {
+            return;
+        }
+        let submission_id = match uuid::Uuid::parse_str(&reply.submission_id) {
+            Ok(s) => s,
+            Err(_) => return
         };
 
-        let body = match res.body {
-            Some(proto::govtalk::GovTalkBody::CompanyData(d)) => d,
-            _ => {
-                return Err(tonic::Status::internal("Mismatched response message received"));
+        let company_number = match schema::submissions::dsl::submissions.find(submission_id)
+            .select(schema::submissions::dsl::company_number)
+            .get_result_async::<Option<String>>(&self.connection).await {
+            Ok(Some(n)) => n,
+            Ok(None) => return,
+            Err(err) => {
+                error!("Unable to record register entries: {}", err);
+                return;
             }
         };
+        let entries = match self.register_entry_models(entries, submission_id, &company_number).await {
+            Ok(e) => e,
+            Err(err) => {
+                error!("Unable to record register entries: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = diesel::insert_into(schema::register_entries::table)
+            .values(entries)
+            .execute_async(&self.connection).await {
+            error!("Unable to record register entries: {}", err);
+        }
+    }
 
-        let reply = ch_ewf_grpc::company_data::CompanyDataResponse {
-            transaction_id: res.transaction_id,
-            company_number: body.company_number,
-            company_name: body.company_name,
-            category: match body.company_category {
-                proto::company_data::CompanyCategory::Plc => ch_ewf_grpc::company_data::CompanyCategory::Plc.into(),
-                proto::company_data::CompanyCategory::ByShares => ch_ewf_grpc::company_data::CompanyCategory::ByShares.into(),
-                proto::company_data::CompanyCategory::ByGuarantee => ch_ewf_grpc::company_data::CompanyCategory::ByGuarantee.into(),
-                proto::company_data::CompanyCategory::BySharesExemptUnderSection60 => ch_ewf_grpc::company_data::CompanyCategory::BySharesExemptUnderSection60.into(),
-                proto::company_data::CompanyCategory::ByGuaranteeExemptUnderSection60 => ch_ewf_grpc::company_data::CompanyCategory::ByGuaranteeExemptUnderSection60.into(),
-                proto::company_data::CompanyCategory::UnlimitedWithShareCapital => ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithShareCapital.into(),
-                proto::company_data::CompanyCategory::UnlimitedWithoutShareCapital => ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithoutShareCapital.into(),
-                proto::company_data::CompanyCategory::Llp => ch_ewf_grpc::company_data::CompanyCategory::Llp.into(),
-            },
-            jurisdiction: match body.jurisdiction {
-                proto::company_data::CompanyJurisdiction::EnglandAndWales => ch_ewf_grpc::company_data::Jurisdiction::EnglandAndWales.into(),
-                proto::company_data::CompanyJurisdiction::Wales => ch_ewf_grpc::company_data::Jurisdiction::Wales.into(),
-                proto::company_data::CompanyJurisdiction::England => ch_ewf_grpc::company_data::Jurisdiction::England.into(),
-                proto::company_data::CompanyJurisdiction::Scotland => ch_ewf_grpc::company_data::Jurisdiction::Scotland.into(),
-                proto::company_data::CompanyJurisdiction::NorthernIreland => ch_ewf_grpc::company_data::Jurisdiction::NorthernIreland.into(),
-                proto::company_data::CompanyJurisdiction::UnitedKingdom => ch_ewf_grpc::company_data::Jurisdiction::Uk.into(),
-                proto::company_data::CompanyJurisdiction::EuropeanUnion => ch_ewf_grpc::company_data::Jurisdiction::Eu.into(),
-                proto::company_data::CompanyJurisdiction::Other => ch_ewf_grpc::company_data::Jurisdiction::Other.into(),
+    /// Register entries as stored, with the personal details of individuals encrypted if a key is configured
+    async fn register_entry_models(
+        &self, entries: Vec<registers::Entry>, submission_id: uuid::Uuid, company_number: &str,
+    ) -> Result<Vec<models::RegisterEntry>, String> {
+        let mut models = vec![];
+        for entry in entries {
+            let personal_details = match entry.personal_details() {
+                Some(d) => Some(self.encrypt_at_rest(d).await?),
+                None => None
+            };
+            models.push(entry.into_model(submission_id, company_number.to_string(), personal_details));
+        }
+        Ok(models)
+    }
+
+    /// Documents generated for an incorporation that asks for them and doesn't already include them
+    fn generated_incorporation_documents(
+        &self, msg: &ch_ewf_grpc::company_incorporation::CompanyIncorporation,
+    ) -> Result<ch_ewf_grpc::company_incorporation::IncorporationDocuments, tonic::Status> {
+        let date = proto_to_chrono(msg.date_signed.clone())
+            .map(|d| d.naive_utc().date())
+            .unwrap_or_else(|| chrono::Utc::today().naive_utc());
+        let document = |data, filename: &str| ch_ewf_grpc::base_types::Document {
+            data,
+            date: msg.date_signed.clone(),
+            filename: filename.to_string(),
+            content_type: ch_ewf_grpc::base_types::ContentType::Pdf.into(),
+        };
+
+        let memorandum = if msg.generate_memorandum && msg.memorandum.is_none() {
+            Some(document(constitution::memorandum(msg, date).map_err(tonic::Status::invalid_argument)?, "memorandum.pdf"))
+        } else {
+            None
+        };
+        let articles = if msg.generate_articles && msg.articles_doc.is_none() {
+            let model_articles = match &self.model_articles {
+                Some(a) => a,
+                None => return Err(tonic::Status::failed_precondition("Model articles not configured"))
+            };
+            Some(document(constitution::model_articles(msg, model_articles).map_err(tonic::Status::invalid_argument)?, "articles.pdf"))
+        } else {
+            None
+        };
+        let cic36 = match (&msg.community_interest_statement, &msg.cic36) {
+            (Some(statement), None) => Some(document(
+                community_interest::cic36(msg, statement, date).map_err(tonic::Status::invalid_argument)?, "cic36.pdf",
+            )),
+            _ => None
+        };
+
+        Ok(ch_ewf_grpc::company_incorporation::IncorporationDocuments {
+            memorandum,
+            articles,
+            cic36,
+        })
+    }
+
+    /// Files one company's form from a bulk submission, returning the submission it made if any
+    async fn bulk_submit_form(
+        &self, form: ch_ewf_grpc::bulk_submission::bulk_form::Form,
+    ) -> Result<Option<uuid::Uuid>, tonic::Status> {
+        let reply = match form {
+            ch_ewf_grpc::bulk_submission::bulk_form::Form::ChangeRegisteredOffice(f) =>
+                self.change_registered_office(tonic::Request::new(f)).await?.into_inner(),
+            ch_ewf_grpc::bulk_submission::bulk_form::Form::ConfirmationStatement(f) =>
+                self.confirmation_statement(tonic::Request::new(f)).await?.into_inner(),
+            ch_ewf_grpc::bulk_submission::bulk_form::Form::SetEReminders(r) => {
+                self.set_e_reminders(tonic::Request::new(r)).await?;
+                return Ok(None);
+            }
+        };
+        Ok(uuid::Uuid::parse_str(&reply.submission_id).ok())
+    }
+
+    /// Checks a company's form would be sent by building and rendering it as a dry run, which reserves no
+    /// submission number, writes nothing to the DB and isn't counted as a gateway transaction
+    async fn validate_bulk_form(&self, form: ch_ewf_grpc::bulk_submission::bulk_form::Form) -> Result<(), String> {
+        let (sender, rendered) = self.sender.dry_run();
+        let service = CHFillingService {
+            sender,
+            ..self.clone()
+        };
+        let res = service.bulk_submit_form(form).await;
+        let rendered = rendered.lock().unwrap().take();
+        match (rendered, res) {
+            (Some(_), _) => Ok(()),
+            (None, Err(err)) => Err(err.message().to_string()),
+            (None, Ok(_)) => Err("Form wasn't rendered".to_string())
+        }
+    }
+
+    /// Submits the pending items of a bulk submission, with no more than `bulk_submit_permits` in flight
+    /// across all bulk submissions
+    async fn process_bulk_submission(&self, bulk_submission_id: uuid::Uuid) {
+        let item_ids = match schema::bulk_submission_items::dsl::bulk_submission_items
+            .filter(schema::bulk_submission_items::dsl::bulk_submission_id.eq(bulk_submission_id))
+            .filter(schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Pending))
+            .order_by(schema::bulk_submission_items::dsl::position.asc())
+            .select(schema::bulk_submission_items::dsl::id)
+            .get_results_async::<uuid::Uuid>(&self.connection).await {
+            Ok(i) => i,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
+        };
+
+        let mut tasks = vec![];
+        for item_id in item_ids {
+            let permit = match self.bulk_submit_permits.clone().acquire_owned().await {
+                Ok(p) => p,
+                Err(_) => break
+            };
+            let submission_id = match self.claim_bulk_item(item_id).await {
+                Ok(Some(s)) => s,
+                Ok(None) => continue,
+                Err(err) => {
+                    error!("Unable to claim bulk submission item {}: {}", item_id, err);
+                    continue;
+                }
+            };
+            let service = self.clone();
+            tasks.push(tokio::task::spawn(async move {
+                service.submit_bulk_item(item_id, submission_id).await;
+                drop(permit);
+            }));
+        }
+        for task in tasks {
+            if let Err(err) = task.await {
+                error!("Bulk submission {} item failed: {}", bulk_submission_id, err);
+            }
+        }
+    }
+
+    /// Marks a pending item as submitting, stopping it being submitted twice if the bulk submission is
+    /// resumed elsewhere. The ID its submission will be made with is recorded in the same update, so that
+    /// an interrupted item can be matched to exactly the submission it made, if any.
+    async fn claim_bulk_item(&self, item_id: uuid::Uuid) -> Result<Option<uuid::Uuid>, tokio_diesel::AsyncError> {
+        let submission_id = uuid::Uuid::new_v4();
+        let claimed = diesel::update(schema::bulk_submission_items::dsl::bulk_submission_items
+            .find(item_id)
+            .filter(schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Pending)))
+            .set((
+                schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Submitting),
+                schema::bulk_submission_items::dsl::submission_id.eq(Some(submission_id)),
+                schema::bulk_submission_items::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute_async(&self.connection).await?;
+        Ok(if claimed == 1 { Some(submission_id) } else { None })
+    }
+
+    async fn submit_bulk_item(&self, item_id: uuid::Uuid, submission_id: uuid::Uuid) {
+        let item = match schema::bulk_submission_items::dsl::bulk_submission_items
+            .find(item_id)
+            .get_result_async::<models::BulkSubmissionItem>(&self.connection).await {
+            Ok(i) => i,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
+        };
+
+        let form = match item.request {
+            Some(r) => match self.decrypt_at_rest(r, item.request_encrypted).await {
+                Ok(d) => match prost::Message::decode(d.as_slice()) {
+                    Ok(ch_ewf_grpc::bulk_submission::BulkForm { form: Some(f) }) => Ok(f),
+                    Ok(_) => Err("Request has no form".to_string()),
+                    Err(err) => Err(format!("Invalid request: {}", err))
+                },
+                Err(err) => Err(err)
             },
-            trading_on_market: body.trading_on_market,
-            dtr5_applies: body.dtr5_applies,
-            made_up_date: chrono_to_proto(Some(body.made_up_date.and_hms(0, 0, 0))),
-            next_due_date: chrono_to_proto(body.next_due_date.map(|d| d.and_hms(0, 0, 0))),
-            psc_exempt_as_shared_admitted_on_market: body.psc_exempt_as_shares_admitted_on_market,
-            psc_exempt_as_trading_on_regulated_market: body.psc_exempt_as_trading_on_regulated_market,
-            psc_exempt_as_trading_on_uk_regulated_market: body.psc_exempt_as_trading_on_uk_regulated_market,
-            sic_codes: body.sic_codes.codes,
-            registered_office_address: Some(body.registered_office_address.into()),
-            sail_address: body.sail_address.map(Into::into),
-            sail_records: body.sail_records.into_iter().map(|r| match r.record_type {
-                proto::base_types::RecordType::Directors => ch_ewf_grpc::base_types::RecordType::Directors.into(),
-                proto::base_types::RecordType::Members => ch_ewf_grpc::base_types::RecordType::Members.into(),
-                proto::base_types::RecordType::DirectorsServiceContracts => ch_ewf_grpc::base_types::RecordType::DirectorsServiceContracts.into(),
-                proto::base_types::RecordType::DirectorsIndemnities => ch_ewf_grpc::base_types::RecordType::DirectorsIndemnities.into(),
-                proto::base_types::RecordType::Secretaries => ch_ewf_grpc::base_types::RecordType::Secretaries.into(),
-                proto::base_types::RecordType::ResolutionsAndMeetings => ch_ewf_grpc::base_types::RecordType::ResolutionsAndMeetings.into(),
-                proto::base_types::RecordType::DebentureHolders => ch_ewf_grpc::base_types::RecordType::DebentureHolders.into(),
-                proto::base_types::RecordType::RegisterOfChargesEnglandWalesAndNorthernIreland =>
-                    ch_ewf_grpc::base_types::RecordType::RegisterOfChargesEnglandWalesAndNorthernIreland.into(),
-                proto::base_types::RecordType::RegisterOfChargesScotland => ch_ewf_grpc::base_types::RecordType::RegisterOfChargesScotland.into(),
-                proto::base_types::RecordType::OwnSharePurchaseContracts => ch_ewf_grpc::base_types::RecordType::OwnSharePurchaseContracts.into(),
-                proto::base_types::RecordType::OwnShareCapital => ch_ewf_grpc::base_types::RecordType::OwnShareCapital.into(),
-                proto::base_types::RecordType::InvestigationReports => ch_ewf_grpc::base_types::RecordType::InvestigationReports.into(),
-                proto::base_types::RecordType::RegisterOfInterests => ch_ewf_grpc::base_types::RecordType::RegisterOfInterests.into(),
-                proto::base_types::RecordType::LLPMembers => ch_ewf_grpc::base_types::RecordType::LlpMembers.into(),
-                proto::base_types::RecordType::PersonsOfSignificantControl => ch_ewf_grpc::base_types::RecordType::PersonsOfSignificantControl.into(),
+            None => Err("Request missing".to_string())
+        };
+        let service = CHFillingService {
+            submission_id: Some(submission_id),
+            ..self.clone()
+        };
+        let res = match form {
+            Ok(f) => service.bulk_submit_form(f).await.map_err(|err| err.message().to_string()),
+            Err(err) => Err(err)
+        };
+        let (state, submission_id, error) = match res {
+            Ok(s) => (schema::BulkItemState::Submitted, s, None),
+            Err(err) => (schema::BulkItemState::Failed, None, Some(err))
+        };
+
+        if let Err(err) = diesel::update(schema::bulk_submission_items::dsl::bulk_submission_items.find(item_id))
+            .set((
+                schema::bulk_submission_items::dsl::state.eq(state),
+                schema::bulk_submission_items::dsl::submission_id.eq(submission_id),
+                schema::bulk_submission_items::dsl::error.eq(error),
+                schema::bulk_submission_items::dsl::request.eq(None::<Vec<u8>>),
+                schema::bulk_submission_items::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute_async(&self.connection).await {
+            error!("Unable to record bulk submission item {}: {}", item_id, err);
+        }
+    }
+
+    /// Settles an item left submitting by a restart, going by the submission ID recorded when it was claimed.
+    /// Submission numbers are reserved before sending, so with no submission under that ID nothing reached
+    /// the gateway and the item goes back to pending. One still unsent may or may not have reached it, so
+    /// the item fails rather than risk filing the form twice.
+    async fn recover_bulk_item(&self, item: models::BulkSubmissionItem) -> Result<(), tokio_diesel::AsyncError> {
+        let status = match item.submission_id {
+            Some(s) => schema::submissions::dsl::submissions
+                .find(s)
+                .select(schema::submissions::dsl::status)
+                .get_result_async::<schema::Status>(&self.connection).await
+                .optional()?,
+            None => None
+        };
+
+        let item_query = schema::bulk_submission_items::dsl::bulk_submission_items
+            .find(item.id)
+            .filter(schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Submitting));
+        let failed = |error: &'static str| (
+            schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Failed),
+            schema::bulk_submission_items::dsl::submission_id.eq(None::<uuid::Uuid>),
+            schema::bulk_submission_items::dsl::error.eq(Some(error)),
+            schema::bulk_submission_items::dsl::request.eq(None::<Vec<u8>>),
+            schema::bulk_submission_items::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+        );
+        match (item.submission_id, status, item.request.is_some()) {
+            (Some(_), Some(schema::Status::Unsent), _) => {
+                warn!("Bulk submission item {} was interrupted while sending", item.id);
+                diesel::update(item_query)
+                    .set(failed("Interrupted while sending; check whether the form was received before retrying"))
+                    .execute_async(&self.connection).await?;
+            }
+            (Some(s), Some(_), _) => {
+                info!("Bulk submission item {} was submitted as {}", item.id, s);
+                diesel::update(item_query)
+                    .set((
+                        schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Submitted),
+                        schema::bulk_submission_items::dsl::request.eq(None::<Vec<u8>>),
+                        schema::bulk_submission_items::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+                    ))
+                    .execute_async(&self.connection).await?;
+            }
+            (Some(_), None, true) => {
+                info!("Bulk submission item {} wasn't submitted, retrying", item.id);
+                // updated_at is left as is so the item counts as stalled and is resumed straight away
+                diesel::update(item_query)
+                    .set((
+                        schema::bulk_submission_items::dsl::state.eq(schema::BulkItemState::Pending),
+                        schema::bulk_submission_items::dsl::submission_id.eq(None::<uuid::Uuid>),
+                    ))
+                    .execute_async(&self.connection).await?;
+            }
+            (Some(_), None, false) => {
+                diesel::update(item_query)
+                    .set(failed("Interrupted, and the request is no longer available"))
+                    .execute_async(&self.connection).await?;
+            }
+            // Claimed without recording a submission ID, so whether it was sent can't be told
+            (None, _, _) => {
+                diesel::update(item_query)
+                    .set(failed("Interrupted while sending; check whether the form was received before retrying"))
+                    .execute_async(&self.connection).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn bulk_submission_reply(
+        &self, bulk_submission_id: uuid::Uuid,
+    ) -> Result<ch_ewf_grpc::bulk_submission::BulkSubmission, tonic::Status> {
+        let res = self.connection.run(move |c| {
+            let bulk_submission: Option<models::BulkSubmission> = schema::bulk_submissions::dsl::bulk_submissions
+                .find(bulk_submission_id)
+                .get_result(c)
+                .optional()?;
+            let items: Vec<models::BulkSubmissionItem> = schema::bulk_submission_items::dsl::bulk_submission_items
+                .filter(schema::bulk_submission_items::dsl::bulk_submission_id.eq(bulk_submission_id))
+                .order_by(schema::bulk_submission_items::dsl::position.asc())
+                .get_results(c)?;
+            Ok((bulk_submission, items))
+        }).await;
+        let (bulk_submission, items) = match res {
+            Ok((Some(b), i)) => (b, i),
+            Ok((None, _)) => return Err(tonic::Status::not_found("Bulk submission not found")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        let count = |state: schema::BulkItemState| items.iter().filter(|i| i.state == state).count() as u32;
+        Ok(ch_ewf_grpc::bulk_submission::BulkSubmission {
+            id: bulk_submission.id.to_string(),
+            form_type: bulk_submission.form_type,
+            total: items.len() as u32,
+            pending: count(schema::BulkItemState::Pending) + count(schema::BulkItemState::Submitting),
+            submitted: count(schema::BulkItemState::Submitted),
+            failed: count(schema::BulkItemState::Failed),
+            created_at: chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(bulk_submission.created_at, chrono::Utc))),
+            items: items.into_iter().map(|i| ch_ewf_grpc::bulk_submission::BulkSubmissionItem {
+                position: i.position as u32,
+                company_number: i.company_number,
+                state: ch_ewf_grpc::bulk_submission::BulkItemState::from(i.state).into(),
+                submission_id: i.submission_id.map(|s| s.to_string()).unwrap_or_default(),
+                error: i.error.unwrap_or_default(),
             }).collect(),
-            pscs: body.pscs.map(|pscs| match pscs {
-                proto::company_data::CompanyDataPSCs::CompanyStatement(s) => {
-                    ch_ewf_grpc::company_data::company_data_response::Pscs::PscStatement(match s {
-                        proto::psc::CompanyLevelStatement::NoSignificantControl => ch_ewf_grpc::psc::CompanyLevelStatement::NoSignificantControl.into(),
-                        proto::psc::CompanyLevelStatement::StepsNotCompleted => ch_ewf_grpc::psc::CompanyLevelStatement::StepsNotCompleted.into(),
-                    })
+        })
+    }
+
+    async fn load_incorporation_workflow(&self, id: &str) -> Result<models::IncorporationWorkflow, tonic::Status> {
+        let id = match uuid::Uuid::parse_str(id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid workflow ID"))
+        };
+        match schema::incorporation_workflows::dsl::incorporation_workflows
+            .find(id)
+            .get_result_async::<models::IncorporationWorkflow>(&self.connection).await
+            .optional() {
+            Ok(Some(w)) => Ok(w),
+            Ok(None) => Err(tonic::Status::not_found("Workflow not found")),
+            Err(err) => Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        }
+    }
+
+    async fn incorporation_draft(
+        &self, workflow: &models::IncorporationWorkflow,
+    ) -> Result<ch_ewf_grpc::company_incorporation::CompanyIncorporation, String> {
+        let data = self.decrypt_at_rest(workflow.draft.clone(), workflow.draft_encrypted).await?;
+        match prost::Message::decode(data.as_slice()) {
+            Ok(d) => Ok(d),
+            Err(err) => Err(format!("Invalid draft: {}", err))
+        }
+    }
+
+    async fn encode_incorporation_draft(
+        &self, draft: &ch_ewf_grpc::company_incorporation::CompanyIncorporation,
+    ) -> Result<(Vec<u8>, bool), tonic::Status> {
+        match self.encrypt_at_rest(prost::Message::encode_to_vec(draft)).await {
+            Ok(d) => Ok(d),
+            Err(err) => Err(tonic::Status::internal(format!("Unable to encrypt draft: {}", err)))
+        }
+    }
+
+    async fn incorporation_workflow_reply(
+        &self, workflow: models::IncorporationWorkflow,
+    ) -> Result<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow, tonic::Status> {
+        let draft = self.incorporation_draft(&workflow).await.map_err(tonic::Status::internal)?;
+        let authentication_code = match workflow.authentication_code {
+            Some(c) => match self.decrypt_at_rest(c, workflow.authentication_code_encrypted).await.map(String::from_utf8) {
+                Ok(Ok(c)) => c,
+                _ => return Err(tonic::Status::internal("Unable to decrypt authentication code"))
+            },
+            None => String::new()
+        };
+        let (company_number, company_type) = workflow.company_number.as_deref()
+            .and_then(managed_companies::parse_company_number)
+            .unwrap_or((0, schema::CompanyType::EnglandAndWales));
+        let to_proto_time = |t: chrono::NaiveDateTime| chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(t, chrono::Utc)));
+
+        Ok(ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow {
+            id: workflow.id.to_string(),
+            state: ch_ewf_grpc::incorporation_workflow::WorkflowState::from(workflow.state).into(),
+            outstanding: if incorporation_workflow::editable(workflow.state) {
+                incorporation_workflow::outstanding(&draft, &self.name_index)
+            } else {
+                vec![]
+            },
+            draft: Some(draft),
+            e_reminder_emails: workflow.e_reminder_emails,
+            submission_id: workflow.submission_id.map(|s| s.to_string()).unwrap_or_default(),
+            company_number,
+            company_type: ch_ewf_grpc::base_types::CompanyType::from(company_type).into(),
+            authentication_code,
+            incorporation_date: workflow.incorporation_date.and_then(|d| to_proto_time(d.and_hms(0, 0, 0))),
+            certificate_document_id: workflow.certificate_document_id.map(|d| d.to_string()).unwrap_or_default(),
+            managed_company_id: workflow.managed_company_id.map(|c| c.to_string()).unwrap_or_default(),
+            e_reminders_set: workflow.e_reminders_set,
+            registers_recorded: workflow.registers_recorded,
+            follow_up_error: workflow.follow_up_error.unwrap_or_default(),
+            created_at: to_proto_time(workflow.created_at),
+            updated_at: to_proto_time(workflow.updated_at),
+        })
+    }
+
+    /// Applies a step to a workflow's draft, which can only be changed until it's submitted
+    async fn update_incorporation_draft<F>(
+        &self, workflow_id: &str, step: F,
+    ) -> Result<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow, tonic::Status>
+        where F: FnOnce(&mut ch_ewf_grpc::company_incorporation::CompanyIncorporation) -> Result<Option<Vec<String>>, tonic::Status> {
+        let workflow = self.load_incorporation_workflow(workflow_id).await?;
+        if !incorporation_workflow::editable(workflow.state) {
+            return Err(tonic::Status::failed_precondition("Workflow has already been submitted"));
+        }
+        let mut draft = self.incorporation_draft(&workflow).await.map_err(tonic::Status::internal)?;
+        let e_reminder_emails = step(&mut draft)?.unwrap_or(workflow.e_reminder_emails);
+        let (draft, draft_encrypted) = self.encode_incorporation_draft(&draft).await?;
+
+        let workflow = match diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows
+            .find(workflow.id)
+            .filter(schema::incorporation_workflows::dsl::state.eq(workflow.state)))
+            .set((
+                schema::incorporation_workflows::dsl::draft.eq(draft),
+                schema::incorporation_workflows::dsl::draft_encrypted.eq(draft_encrypted),
+                schema::incorporation_workflows::dsl::e_reminder_emails.eq(e_reminder_emails),
+                schema::incorporation_workflows::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result_async::<models::IncorporationWorkflow>(&self.connection).await
+            .optional() {
+            Ok(Some(w)) => w,
+            Ok(None) => return Err(tonic::Status::failed_precondition("Workflow has already been submitted")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        self.incorporation_workflow_reply(workflow).await
+    }
+
+    /// Moves an incorporation workflow along when its submission is accepted or rejected
+    async fn incorporation_submission_updated(&self, submission_id: uuid::Uuid, status: &schema::Status) {
+        let state = match status {
+            schema::Status::Accepted => schema::IncorporationWorkflowState::Accepted,
+            schema::Status::Rejected => schema::IncorporationWorkflowState::Rejected,
+            _ => return
+        };
+
+        let res = self.connection.transaction(move |c| {
+            let submission: models::Submission = schema::submissions::dsl::submissions.find(submission_id).get_result(c)?;
+            let workflow_id: Option<uuid::Uuid> = schema::incorporation_workflows::dsl::incorporation_workflows
+                .filter(schema::incorporation_workflows::dsl::submission_id.eq(submission_id))
+                .select(schema::incorporation_workflows::dsl::id)
+                .get_result(c)
+                .optional()?;
+            Ok((submission, workflow_id))
+        }).await;
+        let (submission, workflow_id) = match res {
+            Ok((s, Some(w))) => (s, w),
+            Ok((_, None)) => return,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
+        };
+
+        // Kept with the workflow, so later filings don't depend on the submission
+        let authentication_code = match self.submission_authentication_code(&submission).await {
+            Ok(Some(a)) => match self.encrypt_at_rest(a.into_bytes()).await {
+                Ok(a) => Some(a),
+                Err(err) => {
+                    error!("Unable to encrypt authentication code: {}", err);
+                    return;
                 }
-                proto::company_data::CompanyDataPSCs::PSCs(p) => {
-                    ch_ewf_grpc::company_data::company_data_response::Pscs::CompanyPscs(ch_ewf_grpc::company_data::CompanyPsCs {
-                        pscs: p.into_iter().map(|p| ch_ewf_grpc::company_data::CompanyPsc {
-                            psc: Some(match p {
-                                proto::company_data::CompanyDataPSC::StatementNotification(n) => {
-                                    ch_ewf_grpc::company_data::company_psc::Psc::StatementNotification(match n {
-                                        proto::psc::PSCLevelStatement::ExistsButNotIdentified =>
-                                            ch_ewf_grpc::psc::PscLevelStatement::ExistsButNotIdentified.into(),
-                                        proto::psc::PSCLevelStatement::DetailsNotConfirmed =>
-                                            ch_ewf_grpc::psc::PscLevelStatement::DetailsNotConfirmed.into(),
-                                        proto::psc::PSCLevelStatement::ContactedButNoResponse =>
-                                            ch_ewf_grpc::psc::PscLevelStatement::ContactedButNoResponse.into(),
-                                        proto::psc::PSCLevelStatement::RestrictionNoticeIssued =>
-                                            ch_ewf_grpc::psc::PscLevelStatement::RestrictionNoticeIssued.into(),
-                                    })
-                                }
-                                proto::company_data::CompanyDataPSC::SuperSecureIndividual(s) => {
-                                    ch_ewf_grpc::company_data::company_psc::Psc::SuperSecureIndividual(s)
-                                }
+            },
+            Ok(None) => None,
+            Err(err) => {
+                error!("Unable to decrypt authentication code: {}", err);
+                return;
+            }
+        };
+
+        let workflow = match diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+            .set((
+                schema::incorporation_workflows::dsl::state.eq(state),
+                schema::incorporation_workflows::dsl::company_number.eq(submission.company_number),
+                schema::incorporation_workflows::dsl::incorporation_date.eq(submission.incorporation_date),
+                schema::incorporation_workflows::dsl::authentication_code.eq(authentication_code.as_ref().map(|(a, _)| a.clone())),
+                schema::incorporation_workflows::dsl::authentication_code_encrypted.eq(authentication_code.map(|(_, e)| e).unwrap_or(false)),
+                schema::incorporation_workflows::dsl::certificate_document_id.eq(submission.document_id),
+                schema::incorporation_workflows::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result_async::<models::IncorporationWorkflow>(&self.connection).await {
+            Ok(w) => w,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return;
+            }
+        };
+
+        if state == schema::IncorporationWorkflowState::Accepted {
+            self.complete_incorporation_workflow(workflow).await;
+        }
+    }
+
+    /// Runs the follow-up actions of an accepted incorporation, marking the workflow completed once they've all succeeded
+    async fn complete_incorporation_workflow(&self, workflow: models::IncorporationWorkflow) {
+        let workflow_id = workflow.id;
+        let (state, follow_up_error) = match self.incorporation_follow_ups(workflow).await {
+            Ok(()) => (schema::IncorporationWorkflowState::Completed, None),
+            Err(err) => {
+                warn!("Incorporation workflow {} follow-ups incomplete: {}", workflow_id, err);
+                (schema::IncorporationWorkflowState::Accepted, Some(err))
+            }
+        };
+        if let Err(err) = diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+            .set((
+                schema::incorporation_workflows::dsl::state.eq(state),
+                schema::incorporation_workflows::dsl::follow_up_error.eq(follow_up_error),
+                schema::incorporation_workflows::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute_async(&self.connection).await {
+            error!("Unable to access DB: {}", err);
+        }
+    }
+
+    /// Each follow-up is recorded as it completes, so only those that failed are tried again
+    async fn incorporation_follow_ups(&self, workflow: models::IncorporationWorkflow) -> Result<(), String> {
+        let workflow_id = workflow.id;
+        let draft = self.incorporation_draft(&workflow).await?;
+        let company_number = match workflow.company_number {
+            Some(n) => n,
+            None => return Err("Incorporation has no company number".to_string())
+        };
+        let (number, company_type) = match managed_companies::parse_company_number(&company_number) {
+            Some(c) => c,
+            None => return Err(format!("Invalid company number {}", company_number))
+        };
+        let authentication_code = match workflow.authentication_code {
+            Some(c) => match self.decrypt_at_rest(c, workflow.authentication_code_encrypted).await.map(String::from_utf8) {
+                Ok(Ok(c)) => c,
+                Ok(Err(_)) => return Err("Invalid authentication code encoding".to_string()),
+                Err(err) => return Err(format!("Unable to decrypt authentication code: {}", err))
+            },
+            None => return Err("Incorporation has no authentication code".to_string())
+        };
+        let mut errors = vec![];
+
+        if let (None, Some(submission_id)) = (workflow.certificate_document_id, workflow.submission_id) {
+            // Fetched by the submission, and retried there if it failed
+            match self.connection.run(move |c| {
+                let document_id: Option<uuid::Uuid> = schema::submissions::dsl::submissions
+                    .find(submission_id)
+                    .select(schema::submissions::dsl::document_id)
+                    .get_result(c)?;
+                if let Some(document_id) = document_id {
+                    diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+                        .set(schema::incorporation_workflows::dsl::certificate_document_id.eq(document_id))
+                        .execute(c)?;
+                }
+                Ok(document_id)
+            }).await {
+                Ok(Some(_)) => {}
+                Ok(None) => errors.push("Incorporation certificate not yet received".to_string()),
+                Err(err) => errors.push(format!("Unable to access DB: {}", err))
+            }
+        }
+
+        // Managed companies need the encryption key, without one the credentials stay only on the workflow
+        if let (None, Some(cipher)) = (workflow.managed_company_id, &self.cipher) {
+            match cipher.encrypt(authentication_code.as_bytes()).await {
+                Ok(encrypted_code) => {
+                    let company = models::ManagedCompany {
+                        id: uuid::Uuid::new_v4(),
+                        company_number: number as i32,
+                        company_type,
+                        company_name: draft.company_name.to_uppercase(),
+                        authentication_code: encrypted_code,
+                        tags: vec![],
+                    };
+                    if let Err(err) = self.connection.transaction(move |c| {
+                        let managed_company_id = match diesel::insert_into(schema::managed_companies::table)
+                            .values(&company)
+                            .on_conflict_do_nothing()
+                            .execute(c)? {
+                            0 => schema::managed_companies::dsl::managed_companies
+                                .filter(schema::managed_companies::dsl::company_number.eq(company.company_number))
+                                .filter(schema::managed_companies::dsl::company_type.eq(company.company_type))
+                                .select(schema::managed_companies::dsl::id)
+                                .get_result(c)?,
+                            _ => company.id
+                        };
+                        diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+                            .set(schema::incorporation_workflows::dsl::managed_company_id.eq(managed_company_id))
+                            .execute(c)
+                    }).await {
+                        errors.push(format!("Unable to create managed company: {}", err));
+                    }
+                }
+                Err(err) => errors.push(format!("Unable to encrypt authentication code: {}", err))
+            }
+        }
+
+        if !workflow.e_reminders_set && !workflow.e_reminder_emails.is_empty() {
+            match self.set_e_reminders(tonic::Request::new(ch_ewf_grpc::e_reminders::SetERemindersRequest {
+                company_number: number,
+                company_type: ch_ewf_grpc::base_types::CompanyType::from(company_type).into(),
+                authentication_code,
+                email_addresses: workflow.e_reminder_emails,
+                managed_company_id: String::new(),
+            })).await {
+                Ok(_) => if let Err(err) = diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+                    .set(schema::incorporation_workflows::dsl::e_reminders_set.eq(true))
+                    .execute_async(&self.connection).await {
+                    errors.push(format!("Unable to access DB: {}", err));
+                },
+                Err(err) => errors.push(format!("Unable to set e-reminders: {}", err.message()))
+            }
+        }
+
+        if !workflow.registers_recorded {
+            match (workflow.submission_id, workflow.incorporation_date) {
+                (Some(submission_id), Some(incorporation_date)) => match self.register_entry_models(
+                    registers::company_incorporation(&draft, incorporation_date), submission_id, &company_number,
+                ).await {
+                    Ok(entries) => if let Err(err) = self.connection.transaction(move |c| {
+                        diesel::insert_into(schema::register_entries::table)
+                            .values(entries)
+                            .execute(c)?;
+                        diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow_id))
+                            .set(schema::incorporation_workflows::dsl::registers_recorded.eq(true))
+                            .execute(c)
+                    }).await {
+                        errors.push(format!("Unable to record registers: {}", err));
+                    },
+                    Err(err) => errors.push(format!("Unable to record registers: {}", err))
+                },
+                _ => errors.push("Incorporation has no incorporation date".to_string())
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Fetches a submission's document again using its stored document request key
+    async fn refetch_submission_document(&self, submission: &models::Submission) -> Result<models::Documents, String> {
+        let document_request_key = match &submission.document_request_key {
+            Some(k) => k,
+            None => return Err("Submission has no document".to_string())
+        };
+        let document_id = self.get_document(document_request_key).await?;
+
+        let submission_id = submission.id;
+        match self.connection.run(move |c| {
+            diesel::update(schema::submissions::dsl::submissions.find(submission_id))
+                .set(schema::submissions::dsl::document_id.eq(document_id))
+                .execute(c)?;
+            schema::documents::dsl::documents.find(document_id).get_result(c)
+        }).await {
+            Ok(d) => Ok(d),
+            Err(err) => Err(format!("Unable to access DB: {}", err))
+        }
+    }
+
+    /// Applies a status reported by the gateway to the matching submission
+    async fn process_submission_status(&self, status: proto::submission_status::Status) -> Result<(), String> {
+        let mut submission = match schema::submissions::dsl::submissions
+            .filter(schema::submissions::dsl::ch_submission_id.eq(status.submission_number.clone()))
+            .get_result_async::<models::Submission>(&self.connection).await
+            .optional() {
+            Ok(Some(c)) => c,
+            Ok(None) => {
+                warn!("Unknown submission ID {}", status.submission_number);
+                return Ok(());
+            }
+            Err(err) => return Err(format!("Unable to access DB: {}", err))
+        };
+
+        let mut new_rejections = vec![];
+        let previous_status = submission.status.clone();
+        submission.status = match status.status_code {
+            proto::submission_status::StatusCode::Pending => schema::Status::Pending,
+            proto::submission_status::StatusCode::Accepted => schema::Status::Accepted,
+            proto::submission_status::StatusCode::Rejected => schema::Status::Rejected,
+            proto::submission_status::StatusCode::Parked => schema::Status::Parked,
+            proto::submission_status::StatusCode::InternalFailure => schema::Status::InternalFailure,
+        };
+        submission.customer_reference = status.customer_reference;
+        if submission.company_number.is_none() {
+            submission.company_number = status.company_number;
+        }
+        if let Some(rejections) = status.rejections {
+            submission.reject_reference = rejections.reject_reference;
+            for rejection in rejections.rejections {
+                let new_rejection = models::SubmissionRejection {
+                    id: uuid::Uuid::new_v4(),
+                    submission_id: submission.id,
+                    code: rejection.reject_code,
+                    description: rejection.description,
+                    instance_number: rejection.instance_number,
+                };
+                new_rejections.push(new_rejection);
+            }
+        }
+        if let Some(examiner) = status.examiner {
+            submission.examiner_telephone = Some(examiner.telephone);
+            submission.examiner_comment = examiner.comment;
+        }
+        let document_request_key = match &status.details {
+            Some(proto::submission_status::StatusDetails::Incorporation(i)) => Some(i.document_request_key.clone()),
+            Some(proto::submission_status::StatusDetails::ChangeOfName(c)) => Some(c.document_request_key.clone()),
+            Some(proto::submission_status::StatusDetails::Charge(c)) => Some(c.document_request_key.clone()),
+            None => None
+        };
+        // A failed fetch shouldn't hold up the rest of the status, the stored key lets it be retried later
+        if let Some(document_request_key) = document_request_key {
+            match self.get_document(&document_request_key).await {
+                Ok(d) => submission.document_id = Some(d),
+                Err(err) => error!("Unable to get document for submission {}: {}", submission.id, err)
+            }
+            submission.document_request_key = Some(document_request_key);
+        }
+        match status.details {
+            Some(proto::submission_status::StatusDetails::Incorporation(i)) => {
+                submission.incorporation_date = Some(i.incorporation_date.naive_utc());
+                match self.encrypt_at_rest(i.authentication_code.into_bytes()).await {
+                    Ok((c, true)) => submission.authentication_code_encrypted = Some(c),
+                    Ok((c, false)) => submission.authentication_code = String::from_utf8(c).ok(),
+                    Err(err) => return Err(format!("Unable to encrypt authentication code: {}", err))
+                }
+            }
+            Some(proto::submission_status::StatusDetails::Charge(c)) => {
+                submission.charge_code = Some(c.charge_code)
+            }
+            Some(proto::submission_status::StatusDetails::ChangeOfName(_)) | None => {}
+        }
+        let form_type = submission.form_type.clone().unwrap_or_default();
+        let new_status = submission.status.clone();
+        let submission_id = submission.id;
+        let db_span = tracing::info_span!("db.update_submission", submission_id = %submission_id);
+        if let Err(err) = self.connection.transaction(|c| {
+            diesel::update(schema::submissions::table)
+                .filter(schema::submissions::dsl::id.eq(submission_id))
+                .set(submission)
+                .execute(c)?;
+
+            if !new_rejections.is_empty() {
+                diesel::delete(schema::submission_rejections::table)
+                    .filter(schema::submission_rejections::dsl::submission_id.eq(submission_id))
+                    .execute(c)?;
+            }
+            for rejection in new_rejections {
+                diesel::insert_into(schema::submission_rejections::table)
+                    .values(rejection)
+                    .execute(c)?;
+            }
+
+            Ok(())
+        }).instrument(db_span).await {
+            return Err(format!("Unable to access DB: {}", err));
+        }
+        if new_status != previous_status && new_status != schema::Status::Pending {
+            metrics::SUBMISSION_RESULTS.with_label_values(&[&form_type, metrics::status_label(&new_status)]).inc();
+        }
+        if new_status != previous_status && form_type == "CompanyIncorporation" {
+            self.incorporation_submission_updated(submission_id, &new_status).await;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_document(
+        &self, document_key: &str,
+    ) -> Result<uuid::Uuid, String> {
+        let res = self.fetch_document(document_key).await;
+        metrics::DOCUMENTS_FETCHED.with_label_values(&[match &res {
+            Ok(_) => "success",
+            Err(_) => "error",
+        }]).inc();
+        res
+    }
+
+    async fn fetch_document(
+        &self, document_key: &str,
+    ) -> Result<uuid::Uuid, String> {
+        let res = match gov_talk::exec_govtalk_transaction(&self.sender, "GetDocument", proto::govtalk::GovTalkBody::GetDocument(
+            proto::document::GetDocument {
+                document_request_key: document_key.to_string()
+            }
+        )).await {
+            Ok(c) => c,
+            Err(e) => {
+                return Err(format!("Unable to get document: {}, (trans ID: {})", e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "), e.transaction_id));
+            }
+        };
+
+        let body = match res.body {
+            Some(proto::govtalk::GovTalkBody::Document(s)) => s,
+            _ => {
+                return Err("Mismatched response body received for document".to_string());
+            }
+        };
+
+        let filename_id = uuid::Uuid::new_v4();
+        let filename_ext = match body.document_data.content_type {
+            proto::document::ContentType::Pdf => "pdf"
+        };
+        let filename = format!("{}.{}", filename_id, filename_ext);
+        let file_data = match body.document_data.content_encoding {
+            proto::document::ContentEncoding::Base64 => match base64::decode(body.document_data.contents.replace('\n', "")) {
+                Ok(c) => c,
+                Err(err) => return Err(format!("Invalid document encoding: {}", err))
+            }
+        };
+
+        let document_size = file_data.len();
+        let content_hash = document_store::content_hash(&file_data);
+        let (file_data, encrypted) = match self.encrypt_at_rest(file_data).await {
+            Ok(d) => d,
+            Err(err) => return Err(format!("Unable to encrypt document: {}", err))
+        };
+        if let Err(err) = self.document_store.put(&filename, file_data).await {
+            return Err(format!("Unable to save document: {}", err));
+        }
+        metrics::DOCUMENT_BYTES_STORED.inc_by(document_size as u64);
+
+        let document_id = uuid::Uuid::new_v4();
+        let new_document = models::Documents {
+            id: document_id,
+            company_number: body.company_number,
+            document_date: body.document_date.unwrap_or_else(chrono::Utc::today).naive_utc(),
+            document_type: body.document_type.unwrap_or_default(),
+            document_id: body.document_id,
+            document_filename: body.document_data.filename.unwrap_or_default(),
+            storage_filename: filename,
+            encrypted,
+            content_hash: Some(content_hash),
+        };
+
+        if let Err(err) = diesel::insert_into(schema::documents::table)
+            .values(new_document)
+            .execute_async(&self.connection)
+            .instrument(tracing::info_span!("db.insert_document", document_id = %document_id))
+            .await {
+            return Err(format!("Unable to access DB: {}", err));
+        }
+
+        Ok(document_id)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn get_company_data(
+        &self, msg: ch_ewf_grpc::company_data::CompanyDataRequest,
+    ) -> Result<ch_ewf_grpc::company_data::CompanyDataResponse, tonic::Status> {
+        let mut msg = msg;
+        self.resolve_managed_company(&mut msg).await?;
+        Self::check_authentication_code(&msg.authentication_code)?;
+
+        let res = match gov_talk::exec_govtalk_transaction(
+            &self.sender, "CompanyDataRequest",
+            proto::govtalk::GovTalkBody::CompanyDataRequest(proto::company_data::CompanyDataRequest {
+                company_number: msg.company_number,
+                company_type: Self::map_company_type(msg.company_type),
+                company_authentication_code: msg.authentication_code,
+                made_up_date: proto_to_chrono(msg.made_up_date).map(|d| d.date()).unwrap_or_else(|| chrono::Utc::now().date()),
+            }),
+        ).await {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(tonic::Status::unknown(
+                    format!("Transaction ID: {}; error description: {}", e.transaction_id, e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "))
+                ));
+            }
+        };
+
+        let body = match res.body {
+            Some(proto::govtalk::GovTalkBody::CompanyData(d)) => d,
+            _ => {
+                return Err(tonic::Status::internal("Mismatched response message received"));
+            }
+        };
+
+        let reply = ch_ewf_grpc::company_data::CompanyDataResponse {
+            transaction_id: res.transaction_id,
+            company_number: body.company_number,
+            company_name: body.company_name,
+            category: match body.company_category {
+                proto::company_data::CompanyCategory::Plc => ch_ewf_grpc::company_data::CompanyCategory::Plc.into(),
+                proto::company_data::CompanyCategory::ByShares => ch_ewf_grpc::company_data::CompanyCategory::ByShares.into(),
+                proto::company_data::CompanyCategory::ByGuarantee => ch_ewf_grpc::company_data::CompanyCategory::ByGuarantee.into(),
+                proto::company_data::CompanyCategory::BySharesExemptUnderSection60 => ch_ewf_grpc::company_data::CompanyCategory::BySharesExemptUnderSection60.into(),
+                proto::company_data::CompanyCategory::ByGuaranteeExemptUnderSection60 => ch_ewf_grpc::company_data::CompanyCategory::ByGuaranteeExemptUnderSection60.into(),
+                proto::company_data::CompanyCategory::UnlimitedWithShareCapital => ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithShareCapital.into(),
+                proto::company_data::CompanyCategory::UnlimitedWithoutShareCapital => ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithoutShareCapital.into(),
+                proto::company_data::CompanyCategory::Llp => ch_ewf_grpc::company_data::CompanyCategory::Llp.into(),
+            },
+            jurisdiction: match body.jurisdiction {
+                proto::company_data::CompanyJurisdiction::EnglandAndWales => ch_ewf_grpc::company_data::Jurisdiction::EnglandAndWales.into(),
+                proto::company_data::CompanyJurisdiction::Wales => ch_ewf_grpc::company_data::Jurisdiction::Wales.into(),
+                proto::company_data::CompanyJurisdiction::England => ch_ewf_grpc::company_data::Jurisdiction::England.into(),
+                proto::company_data::CompanyJurisdiction::Scotland => ch_ewf_grpc::company_data::Jurisdiction::Scotland.into(),
+                proto::company_data::CompanyJurisdiction::NorthernIreland => ch_ewf_grpc::company_data::Jurisdiction::NorthernIreland.into(),
+                proto::company_data::CompanyJurisdiction::UnitedKingdom => ch_ewf_grpc::company_data::Jurisdiction::Uk.into(),
+                proto::company_data::CompanyJurisdiction::EuropeanUnion => ch_ewf_grpc::company_data::Jurisdiction::Eu.into(),
+                proto::company_data::CompanyJurisdiction::Other => ch_ewf_grpc::company_data::Jurisdiction::Other.into(),
+            },
+            trading_on_market: body.trading_on_market,
+            dtr5_applies: body.dtr5_applies,
+            made_up_date: chrono_to_proto(Some(body.made_up_date.and_hms(0, 0, 0))),
+            next_due_date: chrono_to_proto(body.next_due_date.map(|d| d.and_hms(0, 0, 0))),
+            psc_exempt_as_shared_admitted_on_market: body.psc_exempt_as_shares_admitted_on_market,
+            psc_exempt_as_trading_on_regulated_market: body.psc_exempt_as_trading_on_regulated_market,
+            psc_exempt_as_trading_on_uk_regulated_market: body.psc_exempt_as_trading_on_uk_regulated_market,
+            sic_codes: body.sic_codes.codes,
+            registered_office_address: Some(body.registered_office_address.into()),
+            sail_address: body.sail_address.map(Into::into),
+            sail_records: body.sail_records.into_iter().map(|r| match r.record_type {
+                proto::base_types::RecordType::Directors => ch_ewf_grpc::base_types::RecordType::Directors.into(),
+                proto::base_types::RecordType::Members => ch_ewf_grpc::base_types::RecordType::Members.into(),
+                proto::base_types::RecordType::DirectorsServiceContracts => ch_ewf_grpc::base_types::RecordType::DirectorsServiceContracts.into(),
+                proto::base_types::RecordType::DirectorsIndemnities => ch_ewf_grpc::base_types::RecordType::DirectorsIndemnities.into(),
+                proto::base_types::RecordType::Secretaries => ch_ewf_grpc::base_types::RecordType::Secretaries.into(),
+                proto::base_types::RecordType::ResolutionsAndMeetings => ch_ewf_grpc::base_types::RecordType::ResolutionsAndMeetings.into(),
+                proto::base_types::RecordType::DebentureHolders => ch_ewf_grpc::base_types::RecordType::DebentureHolders.into(),
+                proto::base_types::RecordType::RegisterOfChargesEnglandWalesAndNorthernIreland =>
+                    ch_ewf_grpc::base_types::RecordType::RegisterOfChargesEnglandWalesAndNorthernIreland.into(),
+                proto::base_types::RecordType::RegisterOfChargesScotland => ch_ewf_grpc::base_types::RecordType::RegisterOfChargesScotland.into(),
+                proto::base_types::RecordType::OwnSharePurchaseContracts => ch_ewf_grpc::base_types::RecordType::OwnSharePurchaseContracts.into(),
+                proto::base_types::RecordType::OwnShareCapital => ch_ewf_grpc::base_types::RecordType::OwnShareCapital.into(),
+                proto::base_types::RecordType::InvestigationReports => ch_ewf_grpc::base_types::RecordType::InvestigationReports.into(),
+                proto::base_types::RecordType::RegisterOfInterests => ch_ewf_grpc::base_types::RecordType::RegisterOfInterests.into(),
+                proto::base_types::RecordType::LLPMembers => ch_ewf_grpc::base_types::RecordType::LlpMembers.into(),
+                proto::base_types::RecordType::PersonsOfSignificantControl => ch_ewf_grpc::base_types::RecordType::PersonsOfSignificantControl.into(),
+            }).collect(),
+            pscs: body.pscs.map(|pscs| match pscs {
+                proto::company_data::CompanyDataPSCs::CompanyStatement(s) => {
+                    ch_ewf_grpc::company_data::company_data_response::Pscs::PscStatement(match s {
+                        proto::psc::CompanyLevelStatement::NoSignificantControl => ch_ewf_grpc::psc::CompanyLevelStatement::NoSignificantControl.into(),
+                        proto::psc::CompanyLevelStatement::StepsNotCompleted => ch_ewf_grpc::psc::CompanyLevelStatement::StepsNotCompleted.into(),
+                    })
+                }
+                proto::company_data::CompanyDataPSCs::PSCs(p) => {
+                    ch_ewf_grpc::company_data::company_data_response::Pscs::CompanyPscs(ch_ewf_grpc::company_data::CompanyPsCs {
+                        pscs: p.into_iter().map(|p| ch_ewf_grpc::company_data::CompanyPsc {
+                            psc: Some(match p {
+                                proto::company_data::CompanyDataPSC::StatementNotification(n) => {
+                                    ch_ewf_grpc::company_data::company_psc::Psc::StatementNotification(match n {
+                                        proto::psc::PSCLevelStatement::ExistsButNotIdentified =>
+                                            ch_ewf_grpc::psc::PscLevelStatement::ExistsButNotIdentified.into(),
+                                        proto::psc::PSCLevelStatement::DetailsNotConfirmed =>
+                                            ch_ewf_grpc::psc::PscLevelStatement::DetailsNotConfirmed.into(),
+                                        proto::psc::PSCLevelStatement::ContactedButNoResponse =>
+                                            ch_ewf_grpc::psc::PscLevelStatement::ContactedButNoResponse.into(),
+                                        proto::psc::PSCLevelStatement::RestrictionNoticeIssued =>
+                                            ch_ewf_grpc::psc::PscLevelStatement::RestrictionNoticeIssued.into(),
+                                    })
+                                }
+                                proto::company_data::CompanyDataPSC::SuperSecureIndividual(s) => {
+                                    ch_ewf_grpc::company_data::company_psc::Psc::SuperSecureIndividual(s)
+                                }
                                 proto::company_data::CompanyDataPSC::LinkedStatementNotification(l) => {
                                     ch_ewf_grpc::company_data::company_psc::Psc::LinkedStatementNotification(ch_ewf_grpc::psc::LinkedStatement {
                                         statement: match l.statement {
@@ -1079,14 +2192,733 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
                 }).collect(),
             }).collect(),
         };
-        Ok(tonic::Response::new(reply))
+        Ok(reply)
+    }
+}
+
+impl From<proto::e_reminders::EReminders> for ch_ewf_grpc::e_reminders::EReminders {
+    fn from(body: proto::e_reminders::EReminders) -> Self {
+        ch_ewf_grpc::e_reminders::EReminders {
+            recipients: body.recipients.into_iter().map(|r| ch_ewf_grpc::e_reminders::EReminderRecipient {
+                email_address: r.email,
+                activated: r.activated,
+            }).collect()
+        }
+    }
+}
+
+impl TryFrom<ch_ewf_grpc::company_incorporation::Authorizer> for proto::company_incorporation::AuthoriserType {
+    type Error = tonic::Status;
+
+    fn try_from(value: ch_ewf_grpc::company_incorporation::Authorizer) -> Result<Self, Self::Error> {
+        if value.personal_attributes.len() != 3 {
+            return Err(tonic::Status::invalid_argument("Invalid number of personal attributes".to_string()));
+        }
+
+        Ok(proto::company_incorporation::AuthoriserType {
+            name: match value.name {
+                Some(ch_ewf_grpc::company_incorporation::authorizer::Name::Person(p)) =>
+                    proto::company_incorporation::IncorporationPersonName::Person(p.try_into()?),
+                Some(ch_ewf_grpc::company_incorporation::authorizer::Name::Corporate(c)) => {
+                    if c.corporate_name.is_empty() || c.corporate_name.len() > 160 {
+                        return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
+                    }
+                    proto::company_incorporation::IncorporationPersonName::Corporate(proto::company_incorporation::CorporateName {
+                        person_name: match c.person {
+                            Some(p) => p.try_into()?,
+                            None => return Err(tonic::Status::invalid_argument("Person name required".to_string()))
+                        },
+                        corporate_name: c.corporate_name,
+                    })
+                }
+                None => return Err(tonic::Status::invalid_argument("Authorizer name required".to_string()))
+            },
+            authentication: value.personal_attributes.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<ch_ewf_grpc::company_incorporation::HmrcAddress> for proto::corporation_tax_information::Address {
+    type Error = tonic::Status;
+
+    fn try_from(value: ch_ewf_grpc::company_incorporation::HmrcAddress) -> Result<Self, Self::Error> {
+        if value.address_line_1.is_empty() || value.address_line_1.len() > 27 {
+            return Err(tonic::Status::invalid_argument("Invalid address line 1".to_string()));
+        }
+        if value.address_line_2.is_empty() || value.address_line_2.len() > 27 {
+            return Err(tonic::Status::invalid_argument("Invalid address line 2".to_string()));
+        }
+
+        Ok(proto::corporation_tax_information::Address {
+            address_line_1: value.address_line_1,
+            address_line_2: value.address_line_2,
+            address_line_3: if value.address_line_3.is_empty() {
+                None
+            } else {
+                if value.address_line_3.len() > 27 {
+                    return Err(tonic::Status::invalid_argument("Invalid address line 3".to_string()));
+                }
+                Some(value.address_line_3)
+            },
+            address_line_4: if value.address_line_4.is_empty() {
+                None
+            } else {
+                if value.address_line_4.len() > 18 {
+                    return Err(tonic::Status::invalid_argument("Invalid address line 4".to_string()));
+                }
+                Some(value.address_line_4)
+            },
+            post_code: if value.post_code.is_empty() {
+                None
+            } else {
+                if value.post_code.len() > 15 {
+                    return Err(tonic::Status::invalid_argument("Invalid postcode".to_string()));
+                }
+                Some(value.post_code)
+            },
+            country: if value.country.is_empty() {
+                None
+            } else {
+                if value.country.len() > 20 {
+                    return Err(tonic::Status::invalid_argument("Invalid country".to_string()));
+                }
+                Some(value.country)
+            },
+        })
+    }
+}
+
+impl TryFrom<ch_ewf_grpc::company_incorporation::Person> for proto::company_incorporation::IncorporationPerson {
+    type Error = tonic::Status;
+
+    fn try_from(value: ch_ewf_grpc::company_incorporation::Person) -> Result<Self, Self::Error> {
+        if value.personal_attributes.len() != 3 {
+            return Err(tonic::Status::invalid_argument("Invalid number of personal attributes".to_string()));
+        }
+
+        Ok(proto::company_incorporation::IncorporationPerson {
+            name: match value.name {
+                Some(ch_ewf_grpc::company_incorporation::person::Name::Person(p)) =>
+                    proto::company_incorporation::IncorporationPersonName::Person(p.try_into()?),
+                Some(ch_ewf_grpc::company_incorporation::person::Name::Corporate(c)) => {
+                    if c.corporate_name.is_empty() || c.corporate_name.len() > 160 {
+                        return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
+                    }
+                    proto::company_incorporation::IncorporationPersonName::Corporate(proto::company_incorporation::CorporateName {
+                        person_name: match c.person {
+                            Some(p) => p.try_into()?,
+                            None => return Err(tonic::Status::invalid_argument("Person name required".to_string()))
+                        },
+                        corporate_name: c.corporate_name,
+                    })
+                }
+                None => return Err(tonic::Status::invalid_argument("Authorizer name required".to_string()))
+            },
+            address: match value.address {
+                Some(a) => a.try_into()?,
+                None => return Err(tonic::Status::invalid_argument("Person address required".to_string()))
+            },
+            authentication: value.personal_attributes.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?,
+            member_class: if value.member_class.is_empty() {
+                None
+            } else {
+                if value.member_class.len() > 50 {
+                    return Err(tonic::Status::invalid_argument("Invalid member class".to_string()));
+                }
+                Some(value.member_class)
+            },
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
+    async fn submission_status(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::form_submission::SubmissionStatusRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionStatusResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let submission_id = match uuid::Uuid::parse_str(&msg.submission_id) {
+            Ok(i) => i,
+            Err(_) => {
+                return Err(tonic::Status::not_found("Invalid submission ID"));
+            }
+        };
+        Ok(tonic::Response::new(self.get_submission_status(submission_id).await?))
+    }
+
+    async fn refresh_submission_status(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::form_submission::SubmissionStatusRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionStatusResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let submission_id = match uuid::Uuid::parse_str(&msg.submission_id) {
+            Ok(i) => i,
+            Err(_) => {
+                return Err(tonic::Status::not_found("Invalid submission ID"));
+            }
+        };
+        let submission = match schema::submissions::dsl::submissions
+            .filter(schema::submissions::dsl::id.eq(submission_id))
+            .get_result_async::<models::Submission>(&self.connection).await
+            .optional() {
+            Ok(Some(s)) => s,
+            Ok(None) => {
+                return Err(tonic::Status::not_found("Submission not found"));
+            }
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return Err(tonic::Status::internal("Error accessing database"));
+            }
+        };
+
+        if let Err(err) = self.refresh_submission(&submission.ch_submission_id).await {
+            return Err(tonic::Status::unknown(err));
+        }
+
+        Ok(tonic::Response::new(self.get_submission_status(submission_id).await?))
+    }
+
+    async fn document(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::form_submission::DocumentRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::DocumentResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let document = self.load_document(&msg.document_id).await?;
+        let file_data = self.read_document(&document).await?;
+
+        let reply = ch_ewf_grpc::form_submission::DocumentResponse {
+            date: chrono_to_proto::<chrono::Utc>(Some(
+                chrono::DateTime::from_utc(document.document_date.and_hms(0, 0, 0), chrono::Utc)
+            )),
+            ch_document_id: document.document_id,
+            content_type: ch_ewf_grpc::form_submission::ContentType::Pdf.into(),
+            ch_filename: document.document_filename,
+            data: file_data,
+        };
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    type DownloadDocumentStream = std::pin::Pin<Box<
+        dyn tokio_stream::Stream<Item = Result<ch_ewf_grpc::documents::DocumentChunk, tonic::Status>> + Send + Sync + 'static
+    >>;
+
+    async fn download_document(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::documents::DownloadDocumentRequest>,
+    ) -> Result<tonic::Response<Self::DownloadDocumentStream>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let document = self.load_document(&msg.document_id).await?;
+        let (size, content_hash, chunks) = self.stream_document(&document, msg.offset, msg.length).await?;
+
+        let header = ch_ewf_grpc::documents::DocumentChunk {
+            chunk: Some(ch_ewf_grpc::documents::document_chunk::Chunk::Header(ch_ewf_grpc::documents::DocumentHeader {
+                date: chrono_to_proto::<chrono::Utc>(Some(
+                    chrono::DateTime::from_utc(document.document_date.and_hms(0, 0, 0), chrono::Utc)
+                )),
+                ch_document_id: document.document_id,
+                content_type: ch_ewf_grpc::form_submission::ContentType::Pdf.into(),
+                ch_filename: document.document_filename,
+                size,
+                sha256: content_hash,
+            }))
+        };
+        let chunks = tokio_stream::StreamExt::map(chunks, |c| c.map(|data| ch_ewf_grpc::documents::DocumentChunk {
+            chunk: Some(ch_ewf_grpc::documents::document_chunk::Chunk::Data(data))
+        }));
+
+        Ok(tonic::Response::new(Box::pin(tokio_stream::StreamExt::chain(tokio_stream::iter(std::iter::once(Ok(header))), chunks))))
+    }
+
+    async fn document_url(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::documents::DocumentUrlRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::documents::DocumentUrlResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let expires_in = match msg.expires_in_seconds {
+            0 => DEFAULT_DOCUMENT_URL_EXPIRY,
+            e if e > MAX_DOCUMENT_URL_EXPIRY => return Err(tonic::Status::invalid_argument("Expiry too long")),
+            e => e
+        };
+        let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in as i64);
+
+        let document = self.load_document(&msg.document_id).await?;
+        // Encrypted documents have to be decrypted on the way out, so can't be served from the store directly
+        let store_url = if document.encrypted {
+            None
+        } else {
+            self.document_store.presigned_url(
+                &document.storage_filename, std::time::Duration::from_secs(expires_in as u64),
+            )
+        };
+        let url = match (store_url, &self.url_signer) {
+            (Some(u), _) => u,
+            (None, Some(signer)) => signer.sign(&document.id, expires_at),
+            (None, None) => return Err(tonic::Status::failed_precondition("Download URLs are not configured"))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::documents::DocumentUrlResponse {
+            url,
+            expires_at: chrono_to_proto(Some(expires_at)),
+        }))
+    }
+
+    async fn list_documents(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::documents::ListDocumentsRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::documents::ListDocumentsResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let mut query = schema::documents::dsl::documents
+            .order_by(schema::documents::dsl::document_date.desc())
+            .into_boxed();
+        if msg.company_number != 0 {
+            let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+            // Companies House pads company numbers to 8 characters, which we don't always do
+            let prefix_len = company_number.len() - msg.company_number.to_string().len();
+            let padded_company_number = format!(
+                "{}{:0width$}", &company_number[..prefix_len], msg.company_number,
+                width = 8usize.saturating_sub(prefix_len)
+            );
+            query = query.filter(schema::documents::dsl::company_number.eq_any(vec![company_number, padded_company_number]));
+        }
+        if !msg.document_type.is_empty() {
+            query = query.filter(schema::documents::dsl::document_type.eq(msg.document_type));
+        }
+        if let Some(from_date) = proto_to_chrono(msg.from_date) {
+            query = query.filter(schema::documents::dsl::document_date.ge(from_date.naive_utc().date()));
+        }
+        if let Some(to_date) = proto_to_chrono(msg.to_date) {
+            query = query.filter(schema::documents::dsl::document_date.le(to_date.naive_utc().date()));
+        }
+
+        let documents = match query.get_results_async::<models::Documents>(&self.connection).await {
+            Ok(d) => d,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::documents::ListDocumentsResponse {
+            documents: documents.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn refetch_document(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::documents::RefetchDocumentRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::documents::DocumentInfo>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let submission_id = match uuid::Uuid::parse_str(&msg.submission_id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid submission ID"))
+        };
+        let submission = match schema::submissions::dsl::submissions
+            .find(submission_id)
+            .get_result_async::<models::Submission>(&self.connection).await
+            .optional() {
+            Ok(Some(s)) => s,
+            Ok(None) => return Err(tonic::Status::not_found("Submission not found")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+        if submission.document_request_key.is_none() {
+            return Err(tonic::Status::failed_precondition("Submission has no document"));
+        }
+
+        match self.refetch_submission_document(&submission).await {
+            Ok(d) => Ok(tonic::Response::new(d.into())),
+            Err(err) => Err(tonic::Status::unknown(err))
+        }
+    }
+
+    async fn company_data(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::company_data::CompanyDataRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::company_data::CompanyDataResponse>, tonic::Status> {
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+
+        let reply = self.get_company_data(msg).await?;
+
+        if let Some(due) = proto_to_chrono(reply.next_due_date.clone()) {
+            let company_number = company_number.clone();
+            if let Err(err) = self.connection.run(move |c| deadlines::record_confirmation_statement_due(
+                c, company_number, due.naive_utc().date()
+            )).await {
+                error!("Unable to update confirmation statement deadline: {}", err);
+            }
+        }
+
+        let mut rows = company_history::snapshot_rows(uuid::Uuid::new_v4(), company_number, &reply);
+        match self.encrypt_at_rest(std::mem::take(&mut rows.snapshot.data)).await {
+            Ok((data, encrypted)) => {
+                rows.snapshot.data = data;
+                rows.snapshot.data_encrypted = encrypted;
+                if let Err(err) = self.connection.run(move |c| company_history::save_snapshot(c, rows))
+                    .instrument(tracing::info_span!("db.insert_company_snapshot"))
+                    .await {
+                    error!("Unable to save company data snapshot: {}", err);
+                }
+            }
+            Err(err) => error!("Unable to encrypt company data snapshot: {}", err)
+        }
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn company_history(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::company_history::CompanyHistoryRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::company_history::CompanyHistoryResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+
+        let snapshots: Vec<models::CompanySnapshot> = match schema::company_snapshots::dsl::company_snapshots
+            .filter(schema::company_snapshots::dsl::company_number.eq(company_number))
+            .order_by(schema::company_snapshots::dsl::snapshot_timestamp.asc())
+            .get_results_async(&self.connection).await {
+            Ok(s) => s,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        let mut reply = ch_ewf_grpc::company_history::CompanyHistoryResponse {
+            snapshots: Vec::with_capacity(snapshots.len()),
+        };
+        for s in snapshots {
+            reply.snapshots.push(ch_ewf_grpc::company_history::CompanySnapshot {
+                snapshot_id: s.id.to_string(),
+                timestamp: chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(s.snapshot_timestamp, chrono::Utc))),
+                data: Some(self.snapshot_data(&s).await?),
+            });
+        }
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn diff_company_data(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::company_history::DiffCompanyDataRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::company_history::DiffCompanyDataResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let from_id = match uuid::Uuid::parse_str(&msg.from_snapshot_id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid from snapshot ID"))
+        };
+        let to_id = match uuid::Uuid::parse_str(&msg.to_snapshot_id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid to snapshot ID"))
+        };
+
+        let (from, to) = match self.connection.run(move |c| Ok((
+            company_history::load_snapshot(c, from_id)?,
+            company_history::load_snapshot(c, to_id)?,
+        ))).await {
+            Ok((Some(f), Some(t))) => (f, t),
+            Ok(_) => return Err(tonic::Status::not_found("Snapshot not found")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        if from.snapshot.company_number != to.snapshot.company_number {
+            return Err(tonic::Status::invalid_argument("Snapshots are for different companies"));
+        }
+
+        let from_data = self.snapshot_data(&from.snapshot).await?;
+        let to_data = self.snapshot_data(&to.snapshot).await?;
+
+        Ok(tonic::Response::new(company_history::diff(&from, &from_data, &to, &to_data)))
+    }
+
+    async fn create_managed_company(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::managed_companies::CreateManagedCompanyRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::managed_companies::ManagedCompany>, tonic::Status> {
+        let msg = request.into_inner();
+        let cipher = self.cipher()?;
+
+        let (company_number, company_type, authentication_code) = if !msg.incorporation_submission_id.is_empty() {
+            let submission_id = match uuid::Uuid::parse_str(&msg.incorporation_submission_id) {
+                Ok(i) => i,
+                Err(_) => return Err(tonic::Status::invalid_argument("Invalid submission ID"))
+            };
+            let submission = match schema::submissions::dsl::submissions
+                .find(submission_id)
+                .get_result_async::<models::Submission>(&self.connection).await
+                .optional() {
+                Ok(Some(s)) => s,
+                Ok(None) => return Err(tonic::Status::not_found("Submission not found")),
+                Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+            };
+            if submission.status != schema::Status::Accepted {
+                return Err(tonic::Status::failed_precondition("Incorporation not yet accepted"));
+            }
+            let (company_number, company_type) = match submission.company_number.as_deref()
+                .and_then(managed_companies::parse_company_number) {
+                Some(c) => c,
+                None => return Err(tonic::Status::failed_precondition("Incorporation has no company number"))
+            };
+            match self.submission_authentication_code(&submission).await {
+                Ok(Some(a)) => (company_number, company_type, a),
+                Ok(None) => return Err(tonic::Status::failed_precondition("Incorporation has no authentication code")),
+                Err(err) => return Err(tonic::Status::internal(format!("Unable to decrypt authentication code: {}", err)))
+            }
+        } else {
+            match ch_ewf_grpc::base_types::CompanyType::from_i32(msg.company_type) {
+                Some(t) => (msg.company_number, t.into(), msg.authentication_code),
+                None => return Err(tonic::Status::invalid_argument("Invalid company type"))
+            }
+        };
+
+        Self::check_authentication_code(&authentication_code)?;
+        if msg.company_name.is_empty() || msg.company_name.len() > 160 {
+            return Err(tonic::Status::invalid_argument("Invalid company name length"));
+        }
+        if company_number > i32::MAX as u32 {
+            return Err(tonic::Status::invalid_argument("Invalid company number"));
+        }
+        if msg.tags.iter().any(|t| t.is_empty() || t.len() > 64) {
+            return Err(tonic::Status::invalid_argument("Invalid tag"));
+        }
+
+        let company = models::ManagedCompany {
+            id: uuid::Uuid::new_v4(),
+            company_number: company_number as i32,
+            company_type,
+            company_name: msg.company_name,
+            authentication_code: cipher.encrypt(authentication_code.as_bytes()).await
+                .map_err(tonic::Status::internal)?,
+            tags: msg.tags,
+        };
+
+        match diesel::insert_into(schema::managed_companies::table)
+            .values(company.clone())
+            .execute_async(&self.connection).await {
+            Ok(_) => {}
+            Err(tokio_diesel::AsyncError::Error(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _))) =>
+                return Err(tonic::Status::already_exists("Company already managed")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        }
+
+        Ok(tonic::Response::new(company.into()))
+    }
+
+    async fn get_managed_company(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::managed_companies::ManagedCompanyRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::managed_companies::ManagedCompany>, tonic::Status> {
+        let msg = request.into_inner();
+        let company = self.load_managed_company(&msg.id).await?;
+        Ok(tonic::Response::new(company.into()))
+    }
+
+    async fn list_managed_companies(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::managed_companies::ListManagedCompaniesRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::managed_companies::ListManagedCompaniesResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let mut query = schema::managed_companies::dsl::managed_companies
+            .order_by(schema::managed_companies::dsl::company_name.asc())
+            .into_boxed();
+        if !msg.tags.is_empty() {
+            query = query.filter(schema::managed_companies::dsl::tags.contains(msg.tags));
+        }
+
+        let companies = match query.get_results_async::<models::ManagedCompany>(&self.connection).await {
+            Ok(c) => c,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::managed_companies::ListManagedCompaniesResponse {
+            companies: companies.into_iter().map(Into::into).collect()
+        }))
+    }
+
+    async fn update_managed_company(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::managed_companies::UpdateManagedCompanyRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::managed_companies::ManagedCompany>, tonic::Status> {
+        let msg = request.into_inner();
+        let mut company = self.load_managed_company(&msg.id).await?;
+
+        if let Some(company_name) = msg.company_name {
+            if company_name.is_empty() || company_name.len() > 160 {
+                return Err(tonic::Status::invalid_argument("Invalid company name length"));
+            }
+            company.company_name = company_name;
+        }
+        if let Some(authentication_code) = msg.authentication_code {
+            Self::check_authentication_code(&authentication_code)?;
+            company.authentication_code = self.cipher()?.encrypt(authentication_code.as_bytes()).await
+                .map_err(tonic::Status::internal)?;
+        }
+        if let Some(tags) = msg.tags {
+            if tags.tags.iter().any(|t| t.is_empty() || t.len() > 64) {
+                return Err(tonic::Status::invalid_argument("Invalid tag"));
+            }
+            company.tags = tags.tags;
+        }
+
+        let company = match diesel::update(&company)
+            .set(company.clone())
+            .get_result_async::<models::ManagedCompany>(&self.connection).await {
+            Ok(c) => c,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(company.into()))
+    }
+
+    async fn delete_managed_company(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::managed_companies::ManagedCompanyRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::managed_companies::DeleteManagedCompanyResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company = self.load_managed_company(&msg.id).await?;
+
+        if let Err(err) = diesel::delete(schema::managed_companies::dsl::managed_companies.find(company.id))
+            .execute_async(&self.connection).await {
+            return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)));
+        }
+
+        Ok(tonic::Response::new(ch_ewf_grpc::managed_companies::DeleteManagedCompanyResponse {}))
+    }
+
+    async fn bulk_submit(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::bulk_submission::BulkSubmitRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::bulk_submission::BulkSubmission>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let template = match msg.template.and_then(|t| t.form) {
+            Some(t) => t,
+            None => return Err(tonic::Status::invalid_argument("Template required"))
+        };
+        let mut companies = msg.companies;
+        if !msg.companies_import.is_empty() {
+            let format = match ch_ewf_grpc::bulk_submission::ImportFormat::from_i32(msg.import_format) {
+                Some(f) => f,
+                None => return Err(tonic::Status::invalid_argument("Invalid import format"))
+            };
+            match bulk_submission::import(&msg.companies_import, format) {
+                Ok(c) => companies.extend(c),
+                Err(err) => return Err(tonic::Status::invalid_argument(format!("Invalid companies import: {}", err)))
+            }
+        }
+        if companies.is_empty() {
+            return Err(tonic::Status::invalid_argument("Companies required"));
+        }
+        if companies.len() > bulk_submission::MAX_COMPANIES {
+            return Err(tonic::Status::invalid_argument(format!("At most {} companies", bulk_submission::MAX_COMPANIES)));
+        }
+
+        // Every company is checked before any are submitted, so one bad row doesn't leave a batch half filed
+        let mut errors = vec![];
+        let mut company_numbers = std::collections::HashSet::new();
+        let mut forms = vec![];
+        for (i, company) in companies.iter().enumerate() {
+            let company_number = if company.managed_company_id.is_empty() {
+                Self::format_company_number(company.company_number, company.company_type)
+            } else {
+                match self.load_managed_company(&company.managed_company_id).await {
+                    Ok(c) => Self::format_company_number(
+                        c.company_number as u32, ch_ewf_grpc::base_types::CompanyType::from(c.company_type).into(),
+                    ),
+                    Err(err) => Err(err)
+                }
+            };
+            let company_number = match company_number {
+                Ok(n) => n,
+                Err(err) => {
+                    errors.push(format!("Company {}: {}", i + 1, err.message()));
+                    continue;
+                }
+            };
+            if !company_numbers.insert(company_number.clone()) {
+                errors.push(format!("Company {}: {} is listed more than once", i + 1, company_number));
+                continue;
+            }
+            let form = bulk_submission::company_form(&template, company);
+            if let Err(err) = self.validate_bulk_form(form.clone()).await {
+                errors.push(format!("Company {} ({}): {}", i + 1, company_number, err));
+                continue;
+            }
+            forms.push((company_number, form));
+        }
+        if !errors.is_empty() {
+            return Err(tonic::Status::invalid_argument(format!("Invalid bulk submission: {}", errors.join("; "))));
+        }
+
+        let bulk_submission_id = uuid::Uuid::new_v4();
+        let now = chrono::Utc::now().naive_utc();
+        let mut items = vec![];
+        for (position, (company_number, form)) in forms.into_iter().enumerate() {
+            let request = prost::Message::encode_to_vec(&ch_ewf_grpc::bulk_submission::BulkForm {
+                form: Some(form)
+            });
+            let (request, request_encrypted) = self.encrypt_at_rest(request).await.map_err(tonic::Status::internal)?;
+            items.push(models::BulkSubmissionItem {
+                id: uuid::Uuid::new_v4(),
+                bulk_submission_id,
+                position: position as i32,
+                company_number,
+                state: schema::BulkItemState::Pending,
+                request: Some(request),
+                request_encrypted,
+                submission_id: None,
+                error: None,
+                updated_at: now,
+            });
+        }
+        let new_bulk_submission = models::BulkSubmission {
+            id: bulk_submission_id,
+            form_type: bulk_submission::form_type(&template).to_string(),
+            created_at: now,
+        };
+        if let Err(err) = self.connection.transaction(move |c| {
+            diesel::insert_into(schema::bulk_submissions::table)
+                .values(&new_bulk_submission)
+                .execute(c)?;
+            diesel::insert_into(schema::bulk_submission_items::table)
+                .values(&items)
+                .execute(c)?;
+            Ok(())
+        }).await {
+            return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)));
+        }
+
+        let service = self.clone();
+        tokio::task::spawn(async move {
+            service.process_bulk_submission(bulk_submission_id).await
+        });
+
+        Ok(tonic::Response::new(self.bulk_submission_reply(bulk_submission_id).await?))
+    }
+
+    async fn get_bulk_submission(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::bulk_submission::BulkSubmissionRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::bulk_submission::BulkSubmission>, tonic::Status> {
+        let msg = request.into_inner();
+        let bulk_submission_id = match uuid::Uuid::parse_str(&msg.bulk_submission_id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid bulk submission ID"))
+        };
+
+        Ok(tonic::Response::new(self.bulk_submission_reply(bulk_submission_id).await?))
     }
 
     async fn get_e_reminders(
         &self,
         request: tonic::Request<ch_ewf_grpc::e_reminders::GetERemindersRequest>,
     ) -> Result<tonic::Response<ch_ewf_grpc::e_reminders::EReminders>, tonic::Status> {
-        let msg = request.into_inner();
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
         Self::check_authentication_code(&msg.authentication_code)?;
 
         let res = match gov_talk::exec_govtalk_transaction(
@@ -1119,7 +2951,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         &self,
         request: tonic::Request<ch_ewf_grpc::e_reminders::SetERemindersRequest>,
     ) -> Result<tonic::Response<ch_ewf_grpc::e_reminders::EReminders>, tonic::Status> {
-        let msg = request.into_inner();
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
         Self::check_authentication_code(&msg.authentication_code)?;
 
         let res = match gov_talk::exec_govtalk_transaction(
@@ -1153,8 +2986,10 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         &self,
         request: tonic::Request<ch_ewf_grpc::payment_periods::PaymentPeriodsRequest>,
     ) -> Result<tonic::Response<ch_ewf_grpc::payment_periods::PaymentPeriodsResponse>, tonic::Status> {
-        let msg = request.into_inner();
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
         Self::check_authentication_code(&msg.authentication_code)?;
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
 
         let res = match gov_talk::exec_govtalk_transaction(
             &self.sender, "PaymentPeriodsRequest",
@@ -1179,6 +3014,12 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             }
         };
 
+        if let Some(period_end) = body.periods.iter().filter(|p| p.paid).map(|p| p.end_date.naive_utc()).max() {
+            if let Err(err) = self.connection.run(move |c| deadlines::record_paid_period(c, company_number, period_end)).await {
+                error!("Unable to update confirmation statement deadline: {}", err);
+            }
+        }
+
         let reply = ch_ewf_grpc::payment_periods::PaymentPeriodsResponse {
             periods: body.periods.into_iter().map(|p| ch_ewf_grpc::payment_periods::payment_periods_response::PaymentPeriod {
                 start_date: chrono_to_proto(Some(p.start_date.and_hms(0, 0, 0))),
@@ -1193,7 +3034,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         &self,
         request: tonic::Request<ch_ewf_grpc::members_data::MembersRegisterRequest>,
     ) -> Result<tonic::Response<ch_ewf_grpc::members_data::MembersRegisterResponse>, tonic::Status> {
-        let msg = request.into_inner();
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
         Self::check_authentication_code(&msg.authentication_code)?;
 
         let res = match gov_talk::exec_govtalk_transaction(
@@ -1274,7 +3116,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         &self,
         request: tonic::Request<ch_ewf_grpc::charge_search::ChargeSearchRequest>,
     ) -> Result<tonic::Response<ch_ewf_grpc::charge_search::ChargeSearchResponse>, tonic::Status> {
-        let msg = request.into_inner();
+        let mut msg = request.into_inner();
+        self.resolve_managed_company(&mut msg).await?;
         Self::check_authentication_code(&msg.authentication_code)?;
 
         let mut continuation_key = None;
@@ -1352,6 +3195,24 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             return Err(tonic::Status::invalid_argument("State confirmation must be true".to_string()));
         }
 
+        let mut msg = msg;
+        if msg.statement_of_capital_from_ledger {
+            if !msg.statement_of_capital.is_empty() {
+                return Err(tonic::Status::invalid_argument("Statement of capital given as well as from the ledger"));
+            }
+            let review_date = match proto_to_chrono(msg.review_date.clone()) {
+                Some(d) => d.naive_utc().date(),
+                None => return Err(tonic::Status::invalid_argument("Review date required".to_string()))
+            };
+            let company_number = self.form_company_number(&mut msg.form_submission).await?;
+            msg.statement_of_capital = self.ledger_statement_of_capital(company_number, review_date).await?;
+        }
+        if !msg.statement_of_capital.is_empty() {
+            let mut errors = capital::check_statement(&msg.statement_of_capital);
+            errors.extend(capital::check_shareholdings(&msg.statement_of_capital, &msg.shareholdings));
+            capital::into_result(errors)?;
+        }
+
         let reply = self.form_submission(
             msg.form_submission, "ConfirmationStatement", "ConfirmationStatement",
             proto::form_submission::Form::ConfirmationStatement(proto::confirmation_statement::ConfirmationStatement {
@@ -1450,6 +3311,66 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         Ok(tonic::Response::new(reply))
     }
 
+    async fn draft_confirmation_statement(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::company_data::CompanyDataRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::confirmation_statement::ConfirmationStatement>, tonic::Status> {
+        let mut msg = request.into_inner();
+        let managed_company_id = msg.managed_company_id.clone();
+        self.resolve_managed_company(&mut msg).await?;
+        let form_submission = ch_ewf_grpc::form_submission::FormSubmission {
+            company_number: msg.company_number,
+            company_type: msg.company_type,
+            // Don't hand a stored authentication code back to the caller
+            authentication_code: if managed_company_id.is_empty() {
+                msg.authentication_code.clone()
+            } else {
+                String::new()
+            },
+            managed_company_id,
+            ..Default::default()
+        };
+
+        let data = self.get_company_data(msg).await?;
+
+        Ok(tonic::Response::new(ch_ewf_grpc::confirmation_statement::ConfirmationStatement {
+            form_submission: Some(ch_ewf_grpc::form_submission::FormSubmission {
+                company_name: data.company_name,
+                ..form_submission
+            }),
+            state_confirmation: false,
+            review_date: data.made_up_date,
+            trading_on_market: Some(data.trading_on_market),
+            dtr5_applies: Some(data.dtr5_applies),
+            psc_exempt_as_trading_on_regulated_market: Some(data.psc_exempt_as_trading_on_regulated_market),
+            psc_exempt_as_shares_admitted_on_market: Some(data.psc_exempt_as_shared_admitted_on_market),
+            psc_exempt_as_trading_on_uk_regulated_market: Some(data.psc_exempt_as_trading_on_uk_regulated_market),
+            sic_codes: data.sic_codes,
+            statement_of_capital: data.statement_of_capital,
+            shareholdings: data.shareholdings.into_iter().map(|s| ch_ewf_grpc::confirmation_statement::Shareholding {
+                share_class: s.share_class,
+                number_held: s.number_held,
+                transfers: vec![],
+                shareholders: s.shareholders.into_iter().map(|h| ch_ewf_grpc::confirmation_statement::Shareholder {
+                    name: h.name.map(|n| match n {
+                        ch_ewf_grpc::base_types::shareholder::Name::PartsName(p) =>
+                            ch_ewf_grpc::confirmation_statement::shareholder::Name::PartsName(ch_ewf_grpc::confirmation_statement::shareholder::PartsName {
+                                surname: p.surname,
+                                forename: if p.forenames.is_empty() {
+                                    None
+                                } else {
+                                    Some(p.forenames.join(" "))
+                                },
+                            }),
+                        ch_ewf_grpc::base_types::shareholder::Name::AmalgamatedName(n) =>
+                            ch_ewf_grpc::confirmation_statement::shareholder::Name::AmalgamatedName(n),
+                    }),
+                    address: h.address,
+                }).collect(),
+            }).collect(),
+        }))
+    }
+
     async fn change_registered_office(
         &self,
         request: tonic::Request<ch_ewf_grpc::change_registered_office::ChangeRegisteredOffice>,
@@ -1554,6 +3475,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::officer_appointment::OfficerAppointment>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries: Vec<_> = registers::officer_appointment(&msg).into_iter().collect();
 
         if !msg.consent_to_act {
             return Err(tonic::Status::invalid_argument("Consent to act must be given".to_string()));
@@ -1580,6 +3502,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -1588,6 +3512,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::officer_resignation::OfficerResignation>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries: Vec<_> = registers::officer_resignation(&msg).into_iter().collect();
 
         let reply = self.form_submission(
             msg.form_submission, "OfficerResignation", "OfficerResignation",
@@ -1639,6 +3564,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -1785,75 +3712,447 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
                                     None => return Err(tonic::Status::invalid_argument("Corporate change required".to_string()))
                                 };
 
-                                if corporate_change.corporate_name.is_empty() || corporate_change.corporate_name.len() > 160 {
-                                    return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
-                                }
+                                if corporate_change.corporate_name.is_empty() || corporate_change.corporate_name.len() > 160 {
+                                    return Err(tonic::Status::invalid_argument("Invalid corporate name".to_string()));
+                                }
+
+                                proto::officer_change::MemberChange::Corporate(Box::new(proto::officer_change::CorporateMemberChangeType {
+                                    corporate_name: corporate_change.corporate_name.clone(),
+                                    change: Some(proto::officer_change::CorporateMemberChangeDetails {
+                                        change: corporate_change.try_into()?,
+                                        designated: c.designated.map(|d| proto::officer_change::MemberDesignated {
+                                            designated: d.designated,
+                                            consent_to_act: d.consent_to_act,
+                                        }),
+                                    }),
+                                }))
+                            }
+                            None => return Err(tonic::Status::invalid_argument("Member type required".to_string()))
+                        }),
+                    None => return Err(tonic::Status::invalid_argument("Entity changing must be provided".to_string()))
+                },
+            }),
+            vec![]
+        ).await?;
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn reconcile_officers(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::officer_reconciliation::ReconcileOfficersRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::officer_reconciliation::ReconcileOfficersResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let form_submission = match &msg.form_submission {
+            Some(f) => f,
+            None => return Err(tonic::Status::invalid_argument("Form submission required"))
+        };
+
+        let live = self.get_company_data(ch_ewf_grpc::company_data::CompanyDataRequest {
+            company_number: form_submission.company_number,
+            company_type: form_submission.company_type,
+            authentication_code: form_submission.authentication_code.clone(),
+            made_up_date: None,
+            managed_company_id: form_submission.managed_company_id.clone(),
+        }).await?;
+
+        let date = match msg.effective_date.clone() {
+            Some(d) => d,
+            None => chrono_to_proto(Some(chrono::Utc::today().and_hms(0, 0, 0))).unwrap()
+        };
+        let mut filings = officer_reconciliation::plan(&live, &msg, date);
+
+        if msg.execute {
+            for filing in &mut filings {
+                let res = match filing.filing.clone() {
+                    Some(ch_ewf_grpc::officer_reconciliation::planned_filing::Filing::Appointment(a)) =>
+                        self.officer_appointment(tonic::Request::new(a)).await,
+                    Some(ch_ewf_grpc::officer_reconciliation::planned_filing::Filing::Change(c)) =>
+                        self.officer_change(tonic::Request::new(c)).await,
+                    Some(ch_ewf_grpc::officer_reconciliation::planned_filing::Filing::Resignation(r)) =>
+                        self.officer_resignation(tonic::Request::new(r)).await,
+                    None => continue
+                };
+                match res {
+                    Ok(r) => filing.submission = Some(r.into_inner()),
+                    Err(err) => {
+                        // Later filings may depend on this one, so stop here
+                        filing.error = err.message().to_string();
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(tonic::Response::new(ch_ewf_grpc::officer_reconciliation::ReconcileOfficersResponse {
+            filings
+        }))
+    }
+
+    async fn accounting_reference_date(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::accounting_reference_date::AccountingReferenceDate>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = match &msg.form_submission {
+            Some(f) => Some(Self::format_company_number(f.company_number, f.company_type)?),
+            None => None
+        };
+        let current_period_end = proto_to_chrono(msg.current_accounting_reference_date.clone());
+        let new_period_end = proto_to_chrono(msg.new_accounting_reference_date.clone());
+        let shortened = msg.change_to_period == ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::Shorten as i32;
+
+        let reply = self.form_submission(
+            msg.form_submission, "ChangeAccountingReferenceDate", "ChangeAccountingReferenceDate",
+            proto::form_submission::Form::ChangeAccountingReferenceDate(proto::accounting_reference_date::ChangeAccountingReferenceDate {
+                accounting_reference_date: match proto_to_chrono(msg.current_accounting_reference_date) {
+                    Some(d) => d.date(),
+                    None => return Err(tonic::Status::invalid_argument("Current accounting reference date required".to_string()))
+                },
+                change_to_period: match ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::from_i32(msg.change_to_period) {
+                    Some(ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::Shorten) => proto::accounting_reference_date::ChangeToPeriod::Shorten,
+                    Some(ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::Extend) => proto::accounting_reference_date::ChangeToPeriod::Extend,
+                    None => return Err(tonic::Status::invalid_argument("Invalid change to period".to_string()))
+                },
+                amended_accounting_reference_date: match proto_to_chrono(msg.new_accounting_reference_date) {
+                    Some(d) => d.date(),
+                    None => return Err(tonic::Status::invalid_argument("New accounting reference date required".to_string()))
+                },
+                five_year_extension_details: msg.five_year_extension_details.map(|e| -> Result<_, tonic::Status> {
+                    Ok(proto::accounting_reference_date::FiveYearExtensionDetails {
+                        extension_reason: match ch_ewf_grpc::accounting_reference_date::ExtensionReason::from_i32(e.extension_reason) {
+                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::Administration) => proto::accounting_reference_date::ExtensionReason::Administration,
+                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::SecretaryOfState) => proto::accounting_reference_date::ExtensionReason::SecretaryOfState,
+                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::UkParent) => proto::accounting_reference_date::ExtensionReason::UKParent,
+                            None => return Err(tonic::Status::invalid_argument("Invalid extension reason".to_string()))
+                        },
+                        extension_authorised_code: e.extension_authorization_code.map(|c| {
+                            if c.len() != 4 {
+                                return Err(tonic::Status::invalid_argument("Invalid extension authorization code".to_string()));
+                            }
+                            Ok(c)
+                        }).transpose()?,
+                    })
+                }).transpose()?,
+            }),
+            vec![]
+        ).await?;
+
+        if let (Some(company_number), Some(current_period_end), Some(new_period_end), Ok(submission_id)) = (
+            company_number, current_period_end, new_period_end, uuid::Uuid::parse_str(&reply.submission_id)
+        ) {
+            // Only counted towards deadlines once the submission is accepted
+            if let Err(err) = diesel::insert_into(schema::accounting_reference_date_changes::table)
+                .values(models::AccountingReferenceDateChange {
+                    submission_id,
+                    company_number,
+                    current_period_end: current_period_end.naive_utc().date(),
+                    new_period_end: new_period_end.naive_utc().date(),
+                    shortened,
+                    notice_date: chrono::Utc::today().naive_utc(),
+                })
+                .execute_async(&self.connection).await {
+                error!("Unable to record accounting reference date change: {}", err);
+            }
+        }
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn track_company(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::deadlines::TrackCompanyRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::deadlines::ListDeadlinesResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+
+        let company = models::TrackedCompany {
+            company_number: company_number.clone(),
+            company_name: msg.company_name,
+            public_company: msg.public_company,
+            incorporation_date: match proto_to_chrono(msg.incorporation_date) {
+                Some(d) => d.naive_utc().date(),
+                None => return Err(tonic::Status::invalid_argument("Incorporation date required"))
+            },
+            accounting_period_end: match proto_to_chrono(msg.accounting_period_end) {
+                Some(d) => d.naive_utc().date(),
+                None => return Err(tonic::Status::invalid_argument("Accounting period end required"))
+            },
+            last_accounts_made_up_to: proto_to_chrono(msg.last_accounts_made_up_to).map(|d| d.naive_utc().date()),
+            confirmation_statement_made_up_to: proto_to_chrono(msg.confirmation_statement_made_up_to).map(|d| d.naive_utc().date()),
+            confirmation_statement_due: None,
+        };
+
+        let deadlines = match self.connection.run(move |c| {
+            diesel::insert_into(schema::tracked_companies::table)
+                .values(&company)
+                .on_conflict(schema::tracked_companies::dsl::company_number)
+                .do_update()
+                .set(&company)
+                .execute(c)?;
+            deadlines::load_deadlines(c, Some(company_number))
+        }).await {
+            Ok(d) => d,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::deadlines::ListDeadlinesResponse {
+            deadlines: deadlines.into_iter().map(Into::into).collect()
+        }))
+    }
+
+    async fn list_deadlines(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::deadlines::ListDeadlinesRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::deadlines::ListDeadlinesResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = match msg.company {
+            Some(c) => Some(Self::format_company_number(c.company_number, c.company_type)?),
+            None => None
+        };
+        let due_before = proto_to_chrono(msg.due_before).map(|d| d.naive_utc().date());
+
+        let deadlines = match self.connection.run(move |c| deadlines::load_deadlines(c, company_number)).await {
+            Ok(d) => d,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::deadlines::ListDeadlinesResponse {
+            deadlines: deadlines.into_iter()
+                .filter(|d| due_before.map(|b| d.due_date < b).unwrap_or(true))
+                .map(Into::into)
+                .collect()
+        }))
+    }
+
+    async fn get_register(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::registers::RegisterRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::registers::RegisterResponse>, tonic::Status> {
+        let (register, _, _, rows) = self.load_register(request.into_inner()).await?;
+
+        Ok(tonic::Response::new(registers::to_proto(register, rows)))
+    }
+
+    async fn export_register(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::registers::ExportRegisterRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::registers::ExportRegisterResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let format = match ch_ewf_grpc::registers::ExportFormat::from_i32(msg.format) {
+            Some(f) => f,
+            None => return Err(tonic::Status::invalid_argument("Invalid export format"))
+        };
+        let (register, company_number, as_at, rows) = match msg.register {
+            Some(r) => self.load_register(r).await?,
+            None => return Err(tonic::Status::invalid_argument("Register required"))
+        };
+
+        let (data, extension, content_type) = match format {
+            ch_ewf_grpc::registers::ExportFormat::Csv => (registers::to_csv(register, &rows), "csv", "text/csv"),
+            ch_ewf_grpc::registers::ExportFormat::Pdf => (registers::to_pdf(register, &company_number, as_at, &rows), "pdf", "application/pdf"),
+        };
+        let data = match data {
+            Ok(d) => d,
+            Err(err) => return Err(tonic::Status::internal(err))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::registers::ExportRegisterResponse {
+            filename: format!(
+                "{}-{}-{}.{}", company_number, registers::register_name(register).to_lowercase().replace(' ', "-"),
+                as_at.format("%Y-%m-%d"), extension
+            ),
+            content_type: content_type.to_string(),
+            data,
+        }))
+    }
+
+    async fn set_share_class(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::capital::ShareClass>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::capital::ShareClass>, tonic::Status> {
+        let msg = request.into_inner();
+
+        if msg.share_class.is_empty() || msg.share_class.len() > 50 {
+            return Err(tonic::Status::invalid_argument("Invalid share class"));
+        }
+        if msg.currency.len() != 3 {
+            return Err(tonic::Status::invalid_argument("Invalid currency code"));
+        }
+        if msg.nominal_value <= 0.0 || msg.nominal_value > 999999999999999.999999 {
+            return Err(tonic::Status::invalid_argument("Invalid nominal value"));
+        }
+        if msg.prescribed_particulars.is_empty() || msg.prescribed_particulars.len() > 400 {
+            return Err(tonic::Status::invalid_argument("Invalid prescribed particulars"));
+        }
+
+        let share_class = models::ShareClass {
+            id: if msg.id.is_empty() {
+                uuid::Uuid::new_v4()
+            } else {
+                match uuid::Uuid::parse_str(&msg.id) {
+                    Ok(i) => i,
+                    Err(_) => return Err(tonic::Status::invalid_argument("Invalid share class ID"))
+                }
+            },
+            company_number: Self::format_company_number(msg.company_number, msg.company_type)?,
+            share_class: msg.share_class,
+            currency: msg.currency.to_uppercase(),
+            nominal_value: msg.nominal_value,
+            prescribed_particulars: msg.prescribed_particulars,
+        };
+
+        let share_class = match diesel::insert_into(schema::share_classes::table)
+            .values(share_class.clone())
+            .on_conflict(schema::share_classes::dsl::id)
+            .do_update()
+            .set(share_class)
+            .get_result_async::<models::ShareClass>(&self.connection).await {
+            Ok(c) => c,
+            Err(tokio_diesel::AsyncError::Error(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation, _
+            ))) => return Err(tonic::Status::already_exists("Share class already exists")),
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
 
-                                proto::officer_change::MemberChange::Corporate(Box::new(proto::officer_change::CorporateMemberChangeType {
-                                    corporate_name: corporate_change.corporate_name.clone(),
-                                    change: Some(proto::officer_change::CorporateMemberChangeDetails {
-                                        change: corporate_change.try_into()?,
-                                        designated: c.designated.map(|d| proto::officer_change::MemberDesignated {
-                                            designated: d.designated,
-                                            consent_to_act: d.consent_to_act,
-                                        }),
-                                    }),
-                                }))
-                            }
-                            None => return Err(tonic::Status::invalid_argument("Member type required".to_string()))
-                        }),
-                    None => return Err(tonic::Status::invalid_argument("Entity changing must be provided".to_string()))
-                },
-            }),
-            vec![]
-        ).await?;
+        Ok(tonic::Response::new(share_class.into()))
+    }
 
-        Ok(tonic::Response::new(reply))
+    async fn list_share_classes(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::capital::ListShareClassesRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::capital::ListShareClassesResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+
+        let share_classes = match schema::share_classes::dsl::share_classes
+            .filter(schema::share_classes::dsl::company_number.eq(company_number))
+            .order_by((schema::share_classes::dsl::currency.asc(), schema::share_classes::dsl::share_class.asc()))
+            .get_results_async::<models::ShareClass>(&self.connection).await {
+            Ok(c) => c,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::capital::ListShareClassesResponse {
+            share_classes: share_classes.into_iter().map(Into::into).collect(),
+        }))
     }
 
-    async fn accounting_reference_date(
+    async fn record_share_movement(
         &self,
-        request: tonic::Request<ch_ewf_grpc::accounting_reference_date::AccountingReferenceDate>,
-    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
+        request: tonic::Request<ch_ewf_grpc::capital::ShareMovement>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::capital::ShareMovement>, tonic::Status> {
         let msg = request.into_inner();
 
-        let reply = self.form_submission(
-            msg.form_submission, "ChangeAccountingReferenceDate", "ChangeAccountingReferenceDate",
-            proto::form_submission::Form::ChangeAccountingReferenceDate(proto::accounting_reference_date::ChangeAccountingReferenceDate {
-                accounting_reference_date: match proto_to_chrono(msg.current_accounting_reference_date) {
-                    Some(d) => d.date(),
-                    None => return Err(tonic::Status::invalid_argument("Current accounting reference date required".to_string()))
-                },
-                change_to_period: match ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::from_i32(msg.change_to_period) {
-                    Some(ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::Shorten) => proto::accounting_reference_date::ChangeToPeriod::Shorten,
-                    Some(ch_ewf_grpc::accounting_reference_date::ChangeToPeriod::Extend) => proto::accounting_reference_date::ChangeToPeriod::Extend,
-                    None => return Err(tonic::Status::invalid_argument("Invalid change to period".to_string()))
-                },
-                amended_accounting_reference_date: match proto_to_chrono(msg.new_accounting_reference_date) {
-                    Some(d) => d.date(),
-                    None => return Err(tonic::Status::invalid_argument("New accounting reference date required".to_string()))
-                },
-                five_year_extension_details: msg.five_year_extension_details.map(|e| -> Result<_, tonic::Status> {
-                    Ok(proto::accounting_reference_date::FiveYearExtensionDetails {
-                        extension_reason: match ch_ewf_grpc::accounting_reference_date::ExtensionReason::from_i32(e.extension_reason) {
-                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::Administration) => proto::accounting_reference_date::ExtensionReason::Administration,
-                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::SecretaryOfState) => proto::accounting_reference_date::ExtensionReason::SecretaryOfState,
-                            Some(ch_ewf_grpc::accounting_reference_date::ExtensionReason::UkParent) => proto::accounting_reference_date::ExtensionReason::UKParent,
-                            None => return Err(tonic::Status::invalid_argument("Invalid extension reason".to_string()))
-                        },
-                        extension_authorised_code: e.extension_authorization_code.map(|c| {
-                            if c.len() != 4 {
-                                return Err(tonic::Status::invalid_argument("Invalid extension authorization code".to_string()));
-                            }
-                            Ok(c)
-                        }).transpose()?,
-                    })
-                }).transpose()?,
-            }),
-            vec![]
-        ).await?;
+        let share_class_id = match uuid::Uuid::parse_str(&msg.share_class_id) {
+            Ok(i) => i,
+            Err(_) => return Err(tonic::Status::invalid_argument("Invalid share class ID"))
+        };
+        let movement: schema::ShareMovementType = match ch_ewf_grpc::capital::ShareMovementType::from_i32(msg.movement) {
+            Some(m) => m.into(),
+            None => return Err(tonic::Status::invalid_argument("Invalid movement type"))
+        };
+        if msg.member_name.trim().is_empty() || msg.member_name.len() > 160 {
+            return Err(tonic::Status::invalid_argument("Invalid member name"));
+        }
+        if msg.num_shares <= 0.0 || msg.num_shares > 999999999999999.999999 {
+            return Err(tonic::Status::invalid_argument("Invalid number of shares"));
+        }
+        if msg.amount_paid_per_share < 0.0 || msg.amount_unpaid_per_share < 0.0 {
+            return Err(tonic::Status::invalid_argument("Invalid amount paid"));
+        }
+        let from_member_name = match movement {
+            schema::ShareMovementType::Transfer => {
+                if msg.from_member_name.trim().is_empty() || msg.from_member_name.len() > 160 {
+                    return Err(tonic::Status::invalid_argument("Invalid member transferred from"));
+                }
+                Some(msg.from_member_name)
+            }
+            _ => None
+        };
 
-        Ok(tonic::Response::new(reply))
+        let movement = models::ShareMovement {
+            id: uuid::Uuid::new_v4(),
+            share_class_id,
+            movement,
+            member_name: msg.member_name,
+            from_member_name,
+            num_shares: msg.num_shares,
+            amount_paid_per_share: msg.amount_paid_per_share,
+            amount_unpaid_per_share: msg.amount_unpaid_per_share,
+            movement_date: match proto_to_chrono(msg.date) {
+                Some(d) => d.naive_utc().date(),
+                None => return Err(tonic::Status::invalid_argument("Movement date required"))
+            },
+        };
+
+        let movement = match self.connection.transaction(move |c| {
+            let share_class: models::ShareClass = match schema::share_classes::dsl::share_classes
+                .find(share_class_id)
+                .get_result(c)
+                .optional()? {
+                Some(s) => s,
+                None => return Ok(Err(tonic::Status::not_found("Share class not found")))
+            };
+            // Locking the company's share classes serialises movements, so two can't each pass the check alone
+            schema::share_classes::dsl::share_classes
+                .filter(schema::share_classes::dsl::company_number.eq(&share_class.company_number))
+                .select(schema::share_classes::dsl::id)
+                .for_update()
+                .load::<uuid::Uuid>(c)?;
+            let ledger = capital_ledger::Ledger::load(c, share_class.company_number, None)?;
+            if let Err(err) = ledger.check_movement(&movement) {
+                return Ok(Err(tonic::Status::failed_precondition(err)));
+            }
+            diesel::insert_into(schema::share_movements::table)
+                .values(&movement)
+                .execute(c)?;
+            Ok(Ok(movement))
+        }).await {
+            Ok(m) => m?,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(movement.into()))
+    }
+
+    async fn list_share_movements(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::capital::ListShareMovementsRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::capital::ListShareMovementsResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+
+        let ledger = match self.connection.run(move |c| {
+            capital_ledger::Ledger::load(c, company_number, None)
+        }).await {
+            Ok(l) => l,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ch_ewf_grpc::capital::ListShareMovementsResponse {
+            movements: ledger.movements.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_cap_table(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::capital::CapTableRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::capital::CapTable>, tonic::Status> {
+        let msg = request.into_inner();
+        let company_number = Self::format_company_number(msg.company_number, msg.company_type)?;
+        let as_at = proto_to_chrono(msg.as_at)
+            .map(|d| d.naive_utc().date())
+            .unwrap_or_else(|| chrono::Utc::today().naive_utc());
+
+        let ledger = match self.connection.run(move |c| {
+            capital_ledger::Ledger::load(c, company_number, Some(as_at))
+        }).await {
+            Ok(l) => l,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        Ok(tonic::Response::new(ledger.cap_table()))
     }
 
     async fn change_of_name(
@@ -1869,6 +4168,21 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             return Err(tonic::Status::invalid_argument("Notice given required".to_string()));
         }
 
+        let mut msg = msg;
+        if let Some(f) = msg.form_submission.as_mut() {
+            self.resolve_managed_company(f).await?;
+        }
+        // The legal form isn't part of the filing, so is taken from the ending of the current name
+        let current = msg.form_submission.as_ref().and_then(|f| names::kind_from_name(&f.company_name));
+        let kind = if msg.method_of_change == ch_ewf_grpc::change_of_name::MethodOfChange::Llp as i32 {
+            Some(names::CompanyKind::Llp)
+        } else {
+            current.map(|(k, _)| k)
+        };
+        let welsh = current.map(|(_, w)| w).unwrap_or(false);
+        names::check(&msg.proposed_name, kind, welsh, &self.name_index)
+            .into_result(msg.sensitive_words_approved, false)?;
+
         let reply = self.form_submission(
             msg.form_submission, "ChangeOfName", "ChangeOfName",
             proto::form_submission::Form::ChangeOfName(proto::change_of_name::ChangeOfName {
@@ -1894,6 +4208,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::psc_notification::PscNotification>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries: Vec<_> = registers::psc_notification(&msg).into_iter().collect();
 
         let reply = self.form_submission(
             msg.form_submission, "PSCNotification", "PSCNotification",
@@ -1918,6 +4233,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -2025,6 +4342,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::psc_cessation::PscCessation>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries: Vec<_> = registers::psc_cessation(&msg).into_iter().collect();
 
         let reply = self.form_submission(
             msg.form_submission, "PSCCessation", "PSCCessation",
@@ -2065,6 +4383,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -2126,6 +4446,70 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         Ok(tonic::Response::new(reply))
     }
 
+    async fn psc_reconciliation(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::psc_reconciliation::PscReconciliationRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::psc_reconciliation::PscReconciliationResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let form_submission = match &msg.form_submission {
+            Some(f) => f,
+            None => return Err(tonic::Status::invalid_argument("Form submission required"))
+        };
+
+        let live = self.get_company_data(ch_ewf_grpc::company_data::CompanyDataRequest {
+            company_number: form_submission.company_number,
+            company_type: form_submission.company_type,
+            authentication_code: form_submission.authentication_code.clone(),
+            made_up_date: None,
+            managed_company_id: form_submission.managed_company_id.clone(),
+        }).await?;
+
+        let date = match msg.effective_date.clone() {
+            Some(d) => d,
+            None => chrono_to_proto(Some(chrono::Utc::today().and_hms(0, 0, 0))).unwrap()
+        };
+        let mut filings = psc_reconciliation::plan(&live, &msg, date)?;
+
+        if msg.execute {
+            for filing in &mut filings {
+                let res = match filing.filing.clone() {
+                    Some(ch_ewf_grpc::psc_reconciliation::planned_filing::Filing::Notification(n)) =>
+                        self.psc_notification(tonic::Request::new(n)).await,
+                    Some(ch_ewf_grpc::psc_reconciliation::planned_filing::Filing::ChangeDetails(c)) =>
+                        self.psc_change_details(tonic::Request::new(c)).await,
+                    Some(ch_ewf_grpc::psc_reconciliation::planned_filing::Filing::Cessation(c)) =>
+                        self.psc_cessation(tonic::Request::new(c)).await,
+                    Some(ch_ewf_grpc::psc_reconciliation::planned_filing::Filing::StatementNotification(s)) =>
+                        self.psc_statement_notification(tonic::Request::new(s)).await,
+                    Some(ch_ewf_grpc::psc_reconciliation::planned_filing::Filing::StatementWithdrawal(s)) =>
+                        self.psc_statement_withdrawal(tonic::Request::new(s)).await,
+                    None => continue
+                };
+                match res {
+                    Ok(r) => filing.submission = Some(r.into_inner()),
+                    Err(err) => {
+                        // Later filings may depend on this one, so stop here
+                        filing.error = err.message().to_string();
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(tonic::Response::new(ch_ewf_grpc::psc_reconciliation::PscReconciliationResponse {
+            filings
+        }))
+    }
+
+    async fn psc_determination(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::psc_determination::PscDeterminationRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::psc_determination::PscDeterminationResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        Ok(tonic::Response::new(psc_determination::determine(&msg)?))
+    }
+
     async fn register_elect_or_withdraw(
         &self,
         request: tonic::Request<ch_ewf_grpc::register_elect_or_withdraw::RegisterElectOrWithdraw>,
@@ -2239,6 +4623,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::members_register_update::MembersRegisterUpdate>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries = registers::members_register_update(&msg);
 
         let reply = self.form_submission(
             msg.form_submission, "MembersRegisterUpdate", "MembersRegisterUpdate",
@@ -2363,6 +4748,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -2371,10 +4758,24 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::return_allotment_shares::ReturnOfAllotmentShares>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries = registers::return_of_allotment_shares(&msg);
 
+        let mut msg = msg;
         if msg.statement_of_capital.is_empty() {
-            return Err(tonic::Status::invalid_argument("Statement of capital required".to_string()));
+            let as_at = match proto_to_chrono(msg.end_period.clone()).or_else(|| proto_to_chrono(msg.start_period.clone())) {
+                Some(d) => d.naive_utc().date(),
+                None => return Err(tonic::Status::invalid_argument("Start period required".to_string()))
+            };
+            let company_number = self.form_company_number(&mut msg.form_submission).await?;
+            msg.statement_of_capital = self.ledger_statement_of_capital(company_number, as_at).await?;
         }
+        let mut errors = capital::check_statement(&msg.statement_of_capital);
+        errors.extend(capital::check_allotments(
+            &msg.statement_of_capital,
+            &msg.allotments.iter().map(Into::into).collect::<Vec<_>>(),
+            capital::Allotments::Additional,
+        ));
+        capital::into_result(errors)?;
 
         let reply = self.form_submission(
             msg.form_submission, "ReturnOfAllotmentShares", "ReturnofAllotmentShares",
@@ -2428,6 +4829,82 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             vec![]
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn increase_nominal_capital(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::increase_nominal_capital::IncreaseNominalCapital>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
+        use ch_ewf_grpc::increase_nominal_capital::increase_nominal_capital::{ResolutionType, MeetingType};
+
+        let msg = request.into_inner();
+
+        let resolution_date = match proto_to_chrono(msg.resolution_date) {
+            Some(d) => d.date(),
+            None => return Err(tonic::Status::invalid_argument("Resolution date required".to_string()))
+        };
+        if resolution_date > chrono::Utc::today() {
+            return Err(tonic::Status::invalid_argument("Resolution date can't be in the future".to_string()));
+        }
+        if msg.currency.len() != 3 {
+            return Err(tonic::Status::invalid_argument("Invalid currency".to_string()));
+        }
+        if msg.amount_of_increase <= 0.0 || msg.amount_of_increase > 999999999999999.999999 {
+            return Err(tonic::Status::invalid_argument("Invalid amount of increase".to_string()));
+        }
+        if msg.nominal_capital < msg.amount_of_increase || msg.nominal_capital > 999999999999999.999999 {
+            return Err(tonic::Status::invalid_argument("Invalid nominal capital".to_string()));
+        }
+        if msg.conditions.len() > 2000 {
+            return Err(tonic::Status::invalid_argument("Invalid conditions".to_string()));
+        }
+        if msg.statement_of_capital.is_empty() {
+            return Err(tonic::Status::invalid_argument("Statement of capital required".to_string()));
+        }
+        let mut errors = capital::check_statement(&msg.statement_of_capital);
+        for c in msg.statement_of_capital.iter().filter(|c| c.currency == msg.currency) {
+            if c.total_aggregate_nominal_value > msg.nominal_capital {
+                errors.push(format!(
+                    "Issued capital of {} {} is more than the nominal capital of {}",
+                    c.total_aggregate_nominal_value, c.currency, msg.nominal_capital
+                ));
+            }
+        }
+        capital::into_result(errors)?;
+
+        let reply = self.form_submission(
+            msg.form_submission, "IncreaseNominalCapital", "IncreaseNominalCapital",
+            proto::form_submission::Form::IncreaseNominalCapital(proto::increase_nominal_capital::IncreaseNominalCapital {
+                resolution_date,
+                currency: msg.currency,
+                amount_of_increase: msg.amount_of_increase,
+                conditions: msg.conditions,
+                nominal_capital: msg.nominal_capital,
+                resolution: proto::increase_nominal_capital::Resolution {
+                    capital: msg.statement_of_capital.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?,
+                    resolution_type: match ResolutionType::from_i32(msg.resolution_type) {
+                        Some(ResolutionType::Ordinary) => proto::increase_nominal_capital::ResolutionType::Ordinary,
+                        Some(ResolutionType::Special) => proto::increase_nominal_capital::ResolutionType::Special,
+                        Some(ResolutionType::Extraordinary) => proto::increase_nominal_capital::ResolutionType::Extraordinary,
+                        None => return Err(tonic::Status::invalid_argument("Invalid resolution type".to_string()))
+                    },
+                    meeting_type: match MeetingType::from_i32(msg.meeting_type) {
+                        Some(MeetingType::Annual) => proto::increase_nominal_capital::MeetingType::Annual,
+                        Some(MeetingType::ExtraordinaryMeeting) => proto::increase_nominal_capital::MeetingType::Extraordinary,
+                        None => return Err(tonic::Status::invalid_argument("Invalid meeting type".to_string()))
+                    },
+                    address: match msg.meeting_address {
+                        Some(a) => Some(a.try_into()?),
+                        None => None
+                    },
+                },
+            }),
+            vec![]
+        ).await?;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -2436,6 +4913,7 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         request: tonic::Request<ch_ewf_grpc::charge_registration::ChargeRegistration>,
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
+        let register_entries: Vec<_> = registers::charge_registration(&msg).into_iter().collect();
 
         if msg.persons_entitled.is_empty() || msg.persons_entitled.len() > 4 {
             return Err(tonic::Status::invalid_argument("Invalid number of persons entitled".to_string()));
@@ -2539,6 +5017,8 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
             documents
         ).await?;
 
+        self.record_register_entries(&reply, register_entries).await;
+
         Ok(tonic::Response::new(reply))
     }
 
@@ -2623,11 +5103,288 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
                     },
                     interest_in_charge: msg.person_interest_in_charge
                 }
-            }),
-            vec![]
-        ).await?;
+            }),
+            vec![]
+        ).await?;
+
+        Ok(tonic::Response::new(reply))
+    }
+
+    async fn check_company_name(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::names::CheckCompanyNameRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::names::CheckCompanyNameResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let kind = match msg.company_type {
+            Some(ch_ewf_grpc::names::check_company_name_request::CompanyType::IncorporationType(t)) =>
+                match ch_ewf_grpc::company_incorporation::CompanyType::from_i32(t) {
+                    Some(t) => Some(t.into()),
+                    None => return Err(tonic::Status::invalid_argument("Invalid company type".to_string()))
+                },
+            Some(ch_ewf_grpc::names::check_company_name_request::CompanyType::Category(c)) =>
+                match ch_ewf_grpc::company_data::CompanyCategory::from_i32(c) {
+                    Some(c) => Some(c.into()),
+                    None => return Err(tonic::Status::invalid_argument("Invalid company category".to_string()))
+                },
+            None => None
+        };
+        let kind = if msg.community_interest {
+            match kind.map(|k: names::CompanyKind| k.community_interest()) {
+                Some(Some(k)) => Some(k),
+                Some(None) => return Err(tonic::Status::invalid_argument(
+                    "Company type can't be a community interest company".to_string()
+                )),
+                None => Some(names::CompanyKind::CommunityInterest)
+            }
+        } else {
+            kind
+        };
+
+        Ok(tonic::Response::new(names::check(&msg.name, kind, msg.welsh, &self.name_index).into()))
+    }
+
+    async fn create_incorporation_workflow(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::IncorporationDetails>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let mut draft = ch_ewf_grpc::company_incorporation::CompanyIncorporation::default();
+        let e_reminder_emails = incorporation_workflow::set_details(&mut draft, msg, &self.name_index)?;
+        let (draft, draft_encrypted) = self.encode_incorporation_draft(&draft).await?;
+
+        let now = chrono::Utc::now().naive_utc();
+        let workflow = models::IncorporationWorkflow {
+            id: uuid::Uuid::new_v4(),
+            state: schema::IncorporationWorkflowState::Draft,
+            draft,
+            draft_encrypted,
+            e_reminder_emails,
+            submission_id: None,
+            company_number: None,
+            incorporation_date: None,
+            authentication_code: None,
+            authentication_code_encrypted: false,
+            certificate_document_id: None,
+            managed_company_id: None,
+            e_reminders_set: false,
+            registers_recorded: false,
+            follow_up_error: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        if let Err(err) = diesel::insert_into(schema::incorporation_workflows::table)
+            .values(workflow.clone())
+            .execute_async(&self.connection).await {
+            return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)));
+        }
+
+        Ok(tonic::Response::new(self.incorporation_workflow_reply(workflow).await?))
+    }
+
+    async fn get_incorporation_workflow(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflowRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let workflow = self.load_incorporation_workflow(&msg.workflow_id).await?;
+        Ok(tonic::Response::new(self.incorporation_workflow_reply(workflow).await?))
+    }
+
+    async fn list_incorporation_workflows(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::ListIncorporationWorkflowsRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::ListIncorporationWorkflowsResponse>, tonic::Status> {
+        let msg = request.into_inner();
+
+        let states = msg.states.into_iter().map(|s| match ch_ewf_grpc::incorporation_workflow::WorkflowState::from_i32(s) {
+            Some(s) => Ok(s.into()),
+            None => Err(tonic::Status::invalid_argument("Invalid workflow state"))
+        }).collect::<Result<Vec<schema::IncorporationWorkflowState>, _>>()?;
+
+        let mut query = schema::incorporation_workflows::dsl::incorporation_workflows
+            .order_by(schema::incorporation_workflows::dsl::created_at.desc())
+            .into_boxed();
+        if !states.is_empty() {
+            query = query.filter(schema::incorporation_workflows::dsl::state.eq_any(states));
+        }
+        let workflows = match query.get_results_async::<models::IncorporationWorkflow>(&self.connection).await {
+            Ok(w) => w,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
+
+        let mut replies = vec![];
+        for workflow in workflows {
+            replies.push(self.incorporation_workflow_reply(workflow).await?);
+        }
+
+        Ok(tonic::Response::new(ch_ewf_grpc::incorporation_workflow::ListIncorporationWorkflowsResponse {
+            workflows: replies,
+        }))
+    }
+
+    async fn delete_incorporation_workflow(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflowRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::DeleteIncorporationWorkflowResponse>, tonic::Status> {
+        let msg = request.into_inner();
+        let workflow = self.load_incorporation_workflow(&msg.workflow_id).await?;
+        if !incorporation_workflow::editable(workflow.state) {
+            return Err(tonic::Status::failed_precondition("Only unsubmitted workflows can be deleted"));
+        }
+
+        match diesel::delete(schema::incorporation_workflows::dsl::incorporation_workflows
+            .find(workflow.id)
+            .filter(schema::incorporation_workflows::dsl::state.eq(workflow.state)))
+            .execute_async(&self.connection).await {
+            Ok(0) => Err(tonic::Status::failed_precondition("Only unsubmitted workflows can be deleted")),
+            Ok(_) => Ok(tonic::Response::new(ch_ewf_grpc::incorporation_workflow::DeleteIncorporationWorkflowResponse {})),
+            Err(err) => Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        }
+    }
+
+    async fn set_incorporation_details(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SetIncorporationDetailsRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let details = match msg.details {
+            Some(d) => d,
+            None => return Err(tonic::Status::invalid_argument("Details required"))
+        };
+        let name_index = self.name_index.clone();
+
+        Ok(tonic::Response::new(self.update_incorporation_draft(&msg.workflow_id, move |draft| {
+            incorporation_workflow::set_details(draft, details, &name_index).map(Some)
+        }).await?))
+    }
+
+    async fn set_incorporation_officers(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SetIncorporationOfficersRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let appointments = msg.appointments;
+
+        Ok(tonic::Response::new(self.update_incorporation_draft(&msg.workflow_id, move |draft| {
+            incorporation_workflow::set_officers(draft, appointments).map(|_| None)
+        }).await?))
+    }
+
+    async fn set_incorporation_psc(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SetIncorporationPscRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let psc = msg.psc;
+
+        Ok(tonic::Response::new(self.update_incorporation_draft(&msg.workflow_id, move |draft| {
+            incorporation_workflow::set_psc(draft, psc).map(|_| None)
+        }).await?))
+    }
+
+    async fn set_incorporation_capital(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SetIncorporationCapitalRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let workflow_id = msg.workflow_id.clone();
+
+        Ok(tonic::Response::new(self.update_incorporation_draft(&workflow_id, move |draft| {
+            incorporation_workflow::set_capital(draft, msg).map(|_| None)
+        }).await?))
+    }
+
+    async fn set_incorporation_documents(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SetIncorporationDocumentsRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let workflow_id = msg.workflow_id.clone();
+
+        Ok(tonic::Response::new(self.update_incorporation_draft(&workflow_id, move |draft| {
+            incorporation_workflow::set_documents(draft, msg).map(|_| None)
+        }).await?))
+    }
+
+    async fn submit_incorporation_workflow(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::incorporation_workflow::SubmitIncorporationWorkflowRequest>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::incorporation_workflow::IncorporationWorkflow>, tonic::Status> {
+        let msg = request.into_inner();
+        let workflow = self.load_incorporation_workflow(&msg.workflow_id).await?;
+        if !incorporation_workflow::editable(workflow.state) {
+            return Err(tonic::Status::failed_precondition("Workflow has already been submitted"));
+        }
+
+        let mut draft = self.incorporation_draft(&workflow).await.map_err(tonic::Status::internal)?;
+        let outstanding = incorporation_workflow::outstanding(&draft, &self.name_index);
+        if !outstanding.is_empty() {
+            return Err(tonic::Status::failed_precondition(format!("Incorporation not ready: {}", outstanding.join("; "))));
+        }
+        draft.date_signed = msg.date_signed;
+        draft.authorizer = msg.authorizer.map(Into::into);
+        draft.same_day = msg.same_day;
+
+        // Resubmissions after a rejection reference the rejected submission
+        if let (schema::IncorporationWorkflowState::Rejected, Some(submission_id)) = (workflow.state, workflow.submission_id) {
+            match schema::submissions::dsl::submissions.find(submission_id)
+                .select(schema::submissions::dsl::reject_reference)
+                .get_result_async::<Option<String>>(&self.connection).await {
+                Ok(r) => draft.reject_reference = r,
+                Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+            }
+        }
+
+        // Claimed before submitting, so it can't be submitted twice at once
+        match diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows
+            .find(workflow.id)
+            .filter(schema::incorporation_workflows::dsl::state.eq(workflow.state)))
+            .set(schema::incorporation_workflows::dsl::state.eq(schema::IncorporationWorkflowState::Submitted))
+            .execute_async(&self.connection).await {
+            Ok(0) => return Err(tonic::Status::failed_precondition("Workflow has already been submitted")),
+            Ok(_) => {}
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        }
+
+        let reply = match self.company_incorporation(tonic::Request::new(draft)).await {
+            Ok(r) => r.into_inner(),
+            Err(status) => {
+                if let Err(err) = diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow.id))
+                    .set(schema::incorporation_workflows::dsl::state.eq(workflow.state))
+                    .execute_async(&self.connection).await {
+                    error!("Unable to access DB: {}", err);
+                }
+                return Err(status);
+            }
+        };
+        let submission_id = uuid::Uuid::parse_str(&reply.submission_id).ok();
+
+        let workflow = match diesel::update(schema::incorporation_workflows::dsl::incorporation_workflows.find(workflow.id))
+            .set((
+                schema::incorporation_workflows::dsl::submission_id.eq(submission_id),
+                schema::incorporation_workflows::dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result_async::<models::IncorporationWorkflow>(&self.connection).await {
+            Ok(w) => w,
+            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
+        };
 
-        Ok(tonic::Response::new(reply))
+        Ok(tonic::Response::new(self.incorporation_workflow_reply(workflow).await?))
+    }
+
+    async fn generate_incorporation_documents(
+        &self,
+        request: tonic::Request<ch_ewf_grpc::company_incorporation::CompanyIncorporation>,
+    ) -> Result<tonic::Response<ch_ewf_grpc::company_incorporation::IncorporationDocuments>, tonic::Status> {
+        let msg = request.into_inner();
+        if !msg.generate_memorandum && !msg.generate_articles && msg.community_interest_statement.is_none() {
+            return Err(tonic::Status::invalid_argument("No documents to generate"));
+        }
+
+        Ok(tonic::Response::new(self.generated_incorporation_documents(&msg)?))
     }
 
     async fn company_incorporation(
@@ -2636,21 +5393,55 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
     ) -> Result<tonic::Response<ch_ewf_grpc::form_submission::SubmissionResponse>, tonic::Status> {
         let msg = request.into_inner();
 
-        let conn = match self.connection.get() {
-            Ok(c) => c,
-            Err(err) => return Err(tonic::Status::internal(format!("Unable to access DB: {}", err)))
-        };
+        let subscriber_allotments = msg.subscribers.iter()
+            .flat_map(|s| s.allotments.iter().map(Into::into))
+            .collect::<Vec<capital::Allotment>>();
+        if msg.statement_of_capital.is_empty() {
+            if !subscriber_allotments.is_empty() {
+                return Err(tonic::Status::invalid_argument("Statement of capital required for subscribers' shares"));
+            }
+        } else {
+            let mut errors = capital::check_statement(&msg.statement_of_capital);
+            errors.extend(capital::check_allotments(
+                &msg.statement_of_capital, &subscriber_allotments, capital::Allotments::All,
+            ));
+            capital::into_result(errors)?;
+        }
 
-        let submission_number = Self::gen_submission_number(&conn)?;
         let submission_id = uuid::Uuid::new_v4();
+        tracing::Span::current().record("submission_id", &tracing::field::display(submission_id));
 
         if msg.company_name.len() < 3 || msg.company_name.len() > 160 {
             return Err(tonic::Status::invalid_argument("Invalid company name length".to_string()));
         }
+        names::check(
+            &msg.company_name,
+            community_interest::name_kind(&msg),
+            msg.country_of_incorporation == ch_ewf_grpc::company_incorporation::CountryOfIncorporation::Wales as i32,
+            &self.name_index,
+        ).into_result(msg.name_authorization.is_some(), msg.same_name.is_some())?;
         if msg.appointments.is_empty() {
             return Err(tonic::Status::invalid_argument("Appointments required".to_string()));
         }
 
+        let mut msg = msg;
+        let generated = self.generated_incorporation_documents(&msg)?;
+        if generated.memorandum.is_some() {
+            msg.memorandum = generated.memorandum;
+        }
+        if generated.articles.is_some() {
+            msg.articles_doc = generated.articles;
+        }
+        if generated.cic36.is_some() {
+            msg.cic36 = generated.cic36;
+        }
+        let errors = community_interest::check(&msg);
+        if !errors.is_empty() {
+            return Err(tonic::Status::invalid_argument(format!(
+                "Invalid community interest company: {}", errors.join("; ")
+            )));
+        }
+
         let mut documents = vec![];
 
         let has_memorandum = msg.memorandum.is_some();
@@ -2769,374 +5560,358 @@ impl ch_ewf_grpc::ch_filling_server::ChFilling for CHFillingService {
         };
 
         let contact_details = msg.contact_name.is_empty() && msg.contact_number.is_empty();
-        let res = match gov_talk::exec_govtalk_transaction(
-            &self.sender, "CompanyIncorporation",
-            proto::govtalk::GovTalkBody::FormSubmission(Box::new(proto::form_submission::FormSubmission {
-                form_header: proto::form_submission::FormHeader {
-                    company_number: None,
-                    company_type: None,
-                    company_name: msg.company_name.to_uppercase(),
-                    company_authentication_code: None,
-                    package_reference: self.package_reference.clone(),
-                    language: match ch_ewf_grpc::form_submission::Language::from_i32(msg.language) {
-                        Some(ch_ewf_grpc::form_submission::Language::English) => proto::form_submission::SubmissionLanguage::English,
-                        Some(ch_ewf_grpc::form_submission::Language::Welsh) => proto::form_submission::SubmissionLanguage::Welsh,
-                        None => return Err(tonic::Status::invalid_argument("Language required".to_string()))
-                    },
-                    form_identifier: "CompanyIncorporation".to_string(),
-                    submission_number: submission_number.clone(),
-                    contact_name: if contact_details {
-                        None
-                    } else {
-                        Some(msg.contact_name)
-                    },
-                    contact_number: if contact_details {
-                        None
-                    } else {
-                        Some(msg.contact_number)
-                    },
-                    customer_reference: msg.customer_reference.clone(),
+        let new_submission = models::Submission {
+            id: submission_id,
+            ch_submission_id: String::new(),
+            company_number: None,
+            received_timestamp: chrono::Utc::now().naive_utc(),
+            customer_reference: msg.customer_reference.clone(),
+            status: schema::Status::Unsent,
+            reject_reference: None,
+            examiner_telephone: None,
+            examiner_comment: None,
+            document_id: None,
+            incorporation_date: None,
+            authentication_code: None,
+            charge_code: None,
+            form_type: Some("CompanyIncorporation".to_string()),
+            authentication_code_encrypted: None,
+            document_request_key: None,
+        };
+        let body = proto::form_submission::FormSubmission {
+            form_header: proto::form_submission::FormHeader {
+                company_number: None,
+                company_type: None,
+                company_name: msg.company_name.to_uppercase(),
+                company_authentication_code: None,
+                package_reference: self.package_reference.clone(),
+                language: match ch_ewf_grpc::form_submission::Language::from_i32(msg.language) {
+                    Some(ch_ewf_grpc::form_submission::Language::English) => proto::form_submission::SubmissionLanguage::English,
+                    Some(ch_ewf_grpc::form_submission::Language::Welsh) => proto::form_submission::SubmissionLanguage::Welsh,
+                    None => return Err(tonic::Status::invalid_argument("Language required".to_string()))
                 },
-                date_signed: match proto_to_chrono(msg.date_signed) {
-                    Some(d) => d.date(),
-                    None => return Err(tonic::Status::invalid_argument("Date signed required".to_string()))
+                form_identifier: "CompanyIncorporation".to_string(),
+                submission_number: String::new(),
+                contact_name: if contact_details {
+                    None
+                } else {
+                    Some(msg.contact_name)
                 },
-                form: proto::form_submission::Form::CompanyIncorporation(Box::new(proto::company_incorporation::CompanyIncorporation {
-                    company_type,
-                    cic: has_cic36,
-                    registers_held_on_public_record: if msg.registers_held_on_public_record.is_empty() {
-                        None
-                    } else {
-                        let registers = msg.registers_held_on_public_record.into_iter().filter_map(Self::map_register_type).collect::<Vec<_>>();
-
-                        Some(proto::company_incorporation::RegistersHeldOnPublicRecord {
-                            directors: registers.contains(&proto::base_types::RegisterType::Directors),
-                            directors_ura: registers.contains(&proto::base_types::RegisterType::DirectorsUsualResidentialAddress),
-                            secretaries: registers.contains(&proto::base_types::RegisterType::Secretaries),
-                            members: registers.contains(&proto::base_types::RegisterType::Members),
-                            llp_members: registers.contains(&proto::base_types::RegisterType::LLPMembers),
-                            llp_members_ura: registers.contains(&proto::base_types::RegisterType::LLPMembers),
-                            psc: if registers.contains(&proto::base_types::RegisterType::PersonsOfSignificantControl) {
-                                Some(proto::company_incorporation::PSCRegister {
-                                    state_no_objection: true
-                                })
-                            } else {
-                                None
-                            },
-                        })
-                    },
-                    country_of_incorporation: match ch_ewf_grpc::company_incorporation::CountryOfIncorporation::from_i32(msg.country_of_incorporation) {
-                        Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::EnglandAndWales) =>
-                            proto::company_incorporation::CountryOfIncorporation::EnglandAndWales,
-                        Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::Wales) =>
-                            proto::company_incorporation::CountryOfIncorporation::Wales,
-                        Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::Scotland) =>
-                            proto::company_incorporation::CountryOfIncorporation::Scotland,
-                        Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::NorthernIreland) =>
-                            proto::company_incorporation::CountryOfIncorporation::NorthernIreland,
-                        None => return Err(tonic::Status::invalid_argument("Country of incorporation required".to_string()))
-                    },
-                    registered_office: match msg.registered_office {
-                        Some(r) => r.try_into()?,
-                        None => return Err(tonic::Status::invalid_argument("Registered office required".to_string()))
-                    },
-                    data_memorandum: !has_memorandum &&
-                        company_type != proto::company_incorporation::CompanyType::Llp &&
-                        company_type != proto::company_incorporation::CompanyType::LLPOnlyDesignated,
-                    articles: match ch_ewf_grpc::company_incorporation::Articles::from_i32(msg.articles) {
-                        Some(ch_ewf_grpc::company_incorporation::Articles::None) => None,
-                        Some(ch_ewf_grpc::company_incorporation::Articles::ModelByShares) =>
-                            Some(proto::company_incorporation::Articles::ModelByShares),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::ModelByGuarantee) =>
-                            Some(proto::company_incorporation::Articles::ModelByGuarantee),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::ModelPlc) =>
-                            Some(proto::company_incorporation::Articles::ModelPLC),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::AmendedByShares) =>
-                            Some(proto::company_incorporation::Articles::AmendedByShares),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::AmendedByGuarantee) =>
-                            Some(proto::company_incorporation::Articles::AmendedByGuarantee),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::AmendedPlc) =>
-                            Some(proto::company_incorporation::Articles::AmendedPLC),
-                        Some(ch_ewf_grpc::company_incorporation::Articles::Bespoke) =>
-                            Some(proto::company_incorporation::Articles::Bespoke),
-                        None => return Err(tonic::Status::invalid_argument("Articles type required".to_string()))
-                    },
-                    restricted_articles: msg.restricted_articles,
-                    appointments: msg.appointments.into_iter().map(|a| -> Result<_, tonic::Status> {
-                        Ok(proto::company_incorporation::Appointment {
-                            consent_to_act: a.consent_to_act,
-                            appointment: match a.appointment {
-                                Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Director(d)) =>
-                                    proto::company_incorporation::AppointmentType::Director(d.try_into()?),
-                                Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Secretary(s)) =>
-                                    proto::company_incorporation::AppointmentType::Secretary(s.try_into()?),
-                                Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Member(m)) =>
-                                    proto::company_incorporation::AppointmentType::Member(Box::new(m.try_into()?)),
-                                None => return Err(tonic::Status::invalid_argument("Appointment type required".to_string()))
-                            },
-                        })
-                    }).collect::<Result<Vec<_>, _>>()?,
-                    pscs: match msg.psc {
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Psc::PscStatement(s)) =>
-                            proto::company_incorporation::PSCs::NoPSCStatement(match ch_ewf_grpc::company_incorporation::PscStatement::from_i32(s) {
-                                Some(ch_ewf_grpc::company_incorporation::PscStatement::NoPsc) => proto::company_incorporation::NoPSCStatement::NoPSC,
-                                None => return Err(tonic::Status::invalid_argument("Company level PSC statement required".to_string()))
-                            }),
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Psc::Pscs(s)) =>
-                            proto::company_incorporation::PSCs::PSCs(s.pscs.into_iter().map(|p| Ok(proto::company_incorporation::Psc {
-                                notification: proto::company_incorporation::PSCNotification {
-                                    notification: match p.notification {
-                                        Some(n) => n.try_into()?,
-                                        None => return Err(tonic::Status::invalid_argument("PSC notification required".to_string()))
-                                    },
-                                    nature_of_control: match p.nature_of_control {
-                                        Some(n) => n.try_into()?,
-                                        None => return Err(tonic::Status::invalid_argument("PSC nature of controls required".to_string()))
-                                    },
-                                },
-                            })).collect::<Result<Vec<_>, _>>()?),
-                        None => return Err(tonic::Status::invalid_argument("PSCs required".to_string()))
-                    },
-                    statement_of_capital: if msg.statement_of_capital.is_empty() {
-                        None
-                    } else {
-                        Some(proto::base_types::StatementOfCapital {
-                            capital: msg.statement_of_capital.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?
-                        })
-                    },
-                    subscribers: msg.subscribers.into_iter().map(|s| {
-                        if s.allotments.is_empty() {
-                            return Err(tonic::Status::invalid_argument("Subscriber shares required".to_string()));
-                        }
-
-                        Ok(proto::company_incorporation::Subscriber {
-                            person: match s.person {
-                                Some(p) => p.try_into()?,
-                                None => return Err(tonic::Status::invalid_argument("Subscriber person required".to_string()))
-                            },
-                            shares: s.allotments.into_iter().map(|a| {
-                                if a.share_class.is_empty() || a.share_class.len() > 50 {
-                                    return Err(tonic::Status::invalid_argument("Invalid share class"));
-                                }
-                                if a.num_shares < 0.0 || a.num_shares > 999999999999999.999999 {
-                                    return Err(tonic::Status::invalid_argument("Invalid number of shares"));
-                                }
-                                if a.amount_paid_due_per_share < 0.0 || a.amount_paid_due_per_share > 999999999999999.999999 {
-                                    return Err(tonic::Status::invalid_argument("Invalid amount paid due per share"));
-                                }
-                                if a.amount_unpaid_per_share < 0.0 || a.amount_unpaid_per_share > 999999999999999.999999 {
-                                    return Err(tonic::Status::invalid_argument("Invalid amount unpaid per share"));
-                                }
-                                if a.share_currency.len() != 3 {
-                                    return Err(tonic::Status::invalid_argument("Invalid share currency"));
-                                }
-
-                                Ok(proto::base_types::Allotment {
-                                    share_class: a.share_class,
-                                    num_shares: a.num_shares,
-                                    amount_paid_due_per_share: a.amount_paid_due_per_share,
-                                    amount_unpaid_per_share: a.amount_unpaid_per_share,
-                                    share_currency: a.share_currency,
-                                    share_value: a.share_value,
-                                    share_reference: if a.share_reference.is_empty() {
-                                        None
-                                    } else {
-                                        Some(a.share_reference)
-                                    },
-                                })
-                            }).collect::<Result<Vec<_>, _>>()?,
-                            memorandum_statement: match ch_ewf_grpc::company_incorporation::MemorandumStatement::from_i32(s.memorandum_statement) {
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithShares) =>
-                                    Some(proto::company_incorporation::MemorandumStatement::MemberWithShares),
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithoutShares) =>
-                                    Some(proto::company_incorporation::MemorandumStatement::MemberWithoutShares),
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::NoMemorandumStatement) => None,
-                                None => None
-                            },
-                        })
-                    }).collect::<Result<Vec<_>, _>>()?,
-                    guarantors: msg.guarantors.into_iter().map(|s| {
-                        if s.amount_guaranteed.is_empty() || s.amount_guaranteed.len() > 100 {
-                            return Err(tonic::Status::invalid_argument("Invalid amount guaranteed".to_string()));
-                        }
-
-                        Ok(proto::company_incorporation::Guarantor {
-                            person: match s.person {
-                                Some(p) => p.try_into()?,
-                                None => return Err(tonic::Status::invalid_argument("Subscriber person required".to_string()))
-                            },
-                            amount_guaranteed: s.amount_guaranteed,
-                            memorandum_statement: match ch_ewf_grpc::company_incorporation::MemorandumStatement::from_i32(s.memorandum_statement) {
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithShares) =>
-                                    Some(proto::company_incorporation::MemorandumStatement::MemberWithShares),
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithoutShares) =>
-                                    Some(proto::company_incorporation::MemorandumStatement::MemberWithoutShares),
-                                Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::NoMemorandumStatement) => None,
-                                None => None
-                            }
-                        })
-                    }).collect::<Result<Vec<_>, _>>()?,
-                    authoriser: match msg.authorizer {
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Agent(a)) => {
-                            proto::company_incorporation::Authoriser::Agent(proto::company_incorporation::Agent {
-                                authoriser: match a.authorizer {
-                                    Some(a) => a.try_into()?,
-                                    None => return Err(tonic::Status::invalid_argument("Agent authorization required".to_string()))
-                                },
-                                address: match a.address {
-                                    Some(a) => a.try_into()?,
-                                    None => return Err(tonic::Status::invalid_argument("Agent address required".to_string()))
-                                },
-                            })
-                        }
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Solicitor(s)) => {
-                            proto::company_incorporation::Authoriser::Solicitor(s.try_into()?)
-                        }
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Member(m)) => {
-                            proto::company_incorporation::Authoriser::Member(m.try_into()?)
-                        }
-                        Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::AuthorizerSubscribers(s)) => {
-                            proto::company_incorporation::Authoriser::Subscribers(proto::company_incorporation::AuthoriserSubscribers {
-                                subscribers: s.subscribers.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?
+                contact_number: if contact_details {
+                    None
+                } else {
+                    Some(msg.contact_number)
+                },
+                customer_reference: msg.customer_reference.clone(),
+            },
+            date_signed: match proto_to_chrono(msg.date_signed) {
+                Some(d) => d.date(),
+                None => return Err(tonic::Status::invalid_argument("Date signed required".to_string()))
+            },
+            form: proto::form_submission::Form::CompanyIncorporation(Box::new(proto::company_incorporation::CompanyIncorporation {
+                company_type,
+                cic: has_cic36,
+                registers_held_on_public_record: if msg.registers_held_on_public_record.is_empty() {
+                    None
+                } else {
+                    let registers = msg.registers_held_on_public_record.into_iter().filter_map(Self::map_register_type).collect::<Vec<_>>();
+
+                    Some(proto::company_incorporation::RegistersHeldOnPublicRecord {
+                        directors: registers.contains(&proto::base_types::RegisterType::Directors),
+                        directors_ura: registers.contains(&proto::base_types::RegisterType::DirectorsUsualResidentialAddress),
+                        secretaries: registers.contains(&proto::base_types::RegisterType::Secretaries),
+                        members: registers.contains(&proto::base_types::RegisterType::Members),
+                        llp_members: registers.contains(&proto::base_types::RegisterType::LLPMembers),
+                        llp_members_ura: registers.contains(&proto::base_types::RegisterType::LLPMembers),
+                        psc: if registers.contains(&proto::base_types::RegisterType::PersonsOfSignificantControl) {
+                            Some(proto::company_incorporation::PSCRegister {
+                                state_no_objection: true
                             })
-                        }
-                        None => return Err(tonic::Status::invalid_argument("Authorizer required".to_string()))
-                    },
-                    same_day: msg.same_day,
-                    same_name: has_same_name,
-                    name_authorisation: has_name_authorization,
-                    reject_reference: msg.reject_reference.map(|r| {
-                        if r.len() < 8 || r.len() > 9 {
-                            return Err(tonic::Status::invalid_argument("Invalid reject reference".to_string()));
-                        }
-                        Ok(r)
-                    }).transpose()?,
-                    sic_codes: if msg.sic_codes.is_empty() {
-                        None
-                    } else {
-                        Some(proto::base_types::SICCodes {
-                            codes: msg.sic_codes.into_iter().map(|sic| {
-                                if sic.len() > 5 || sic.len() < 4 || sic.chars().map(|c| c.is_numeric()).any(|x| !x) {
-                                    Err(tonic::Status::invalid_argument("Invalid SIC code".to_string()))
-                                } else {
-                                    Ok(sic)
-                                }
-                            }).collect::<Result<Vec<_>, _>>()?
-                        })
-                    },
-                    single_member_company: msg.single_member_company,
-                })),
-                additional_information: msg.corporation_tax_registration.map(|r| Ok(proto::form_submission::AdditionalInformation::CorporationTaxInformation(
-                    proto::corporation_tax_information::CorporationTaxInformation {
-                        abbreviated_company_name: if r.abbreviated_company_name.is_empty() {
-                            None
                         } else {
-                            if r.abbreviated_company_name.len() > 56 {
-                                return Err(tonic::Status::invalid_argument("Invalid abbreviated company name".to_string()));
-                            }
-                            Some(r.abbreviated_company_name)
+                            None
                         },
-                        first_accounting_period_start_date: match proto_to_chrono(r.first_accounting_period_start_date) {
-                            Some(d) => d.date(),
-                            None => return Err(tonic::Status::invalid_argument("First accounting period start date required".to_string()))
+                    })
+                },
+                country_of_incorporation: match ch_ewf_grpc::company_incorporation::CountryOfIncorporation::from_i32(msg.country_of_incorporation) {
+                    Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::EnglandAndWales) =>
+                        proto::company_incorporation::CountryOfIncorporation::EnglandAndWales,
+                    Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::Wales) =>
+                        proto::company_incorporation::CountryOfIncorporation::Wales,
+                    Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::Scotland) =>
+                        proto::company_incorporation::CountryOfIncorporation::Scotland,
+                    Some(ch_ewf_grpc::company_incorporation::CountryOfIncorporation::NorthernIreland) =>
+                        proto::company_incorporation::CountryOfIncorporation::NorthernIreland,
+                    None => return Err(tonic::Status::invalid_argument("Country of incorporation required".to_string()))
+                },
+                registered_office: match msg.registered_office {
+                    Some(r) => r.try_into()?,
+                    None => return Err(tonic::Status::invalid_argument("Registered office required".to_string()))
+                },
+                data_memorandum: !has_memorandum &&
+                    company_type != proto::company_incorporation::CompanyType::Llp &&
+                    company_type != proto::company_incorporation::CompanyType::LLPOnlyDesignated,
+                articles: match ch_ewf_grpc::company_incorporation::Articles::from_i32(msg.articles) {
+                    Some(ch_ewf_grpc::company_incorporation::Articles::None) => None,
+                    Some(ch_ewf_grpc::company_incorporation::Articles::ModelByShares) =>
+                        Some(proto::company_incorporation::Articles::ModelByShares),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::ModelByGuarantee) =>
+                        Some(proto::company_incorporation::Articles::ModelByGuarantee),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::ModelPlc) =>
+                        Some(proto::company_incorporation::Articles::ModelPLC),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::AmendedByShares) =>
+                        Some(proto::company_incorporation::Articles::AmendedByShares),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::AmendedByGuarantee) =>
+                        Some(proto::company_incorporation::Articles::AmendedByGuarantee),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::AmendedPlc) =>
+                        Some(proto::company_incorporation::Articles::AmendedPLC),
+                    Some(ch_ewf_grpc::company_incorporation::Articles::Bespoke) =>
+                        Some(proto::company_incorporation::Articles::Bespoke),
+                    None => return Err(tonic::Status::invalid_argument("Articles type required".to_string()))
+                },
+                restricted_articles: msg.restricted_articles,
+                appointments: msg.appointments.into_iter().map(|a| -> Result<_, tonic::Status> {
+                    Ok(proto::company_incorporation::Appointment {
+                        consent_to_act: a.consent_to_act,
+                        appointment: match a.appointment {
+                            Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Director(d)) =>
+                                proto::company_incorporation::AppointmentType::Director(d.try_into()?),
+                            Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Secretary(s)) =>
+                                proto::company_incorporation::AppointmentType::Secretary(s.try_into()?),
+                            Some(ch_ewf_grpc::company_incorporation::appointment::Appointment::Member(m)) =>
+                                proto::company_incorporation::AppointmentType::Member(Box::new(m.try_into()?)),
+                            None => return Err(tonic::Status::invalid_argument("Appointment type required".to_string()))
                         },
-                        accounts_made_up_date: match proto_to_chrono(r.accounts_made_up_date) {
-                            Some(d) => d.date(),
-                            None => return Err(tonic::Status::invalid_argument("Accounts made up date required".to_string()))
+                    })
+                }).collect::<Result<Vec<_>, _>>()?,
+                pscs: match msg.psc {
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Psc::PscStatement(s)) =>
+                        proto::company_incorporation::PSCs::NoPSCStatement(match ch_ewf_grpc::company_incorporation::PscStatement::from_i32(s) {
+                            Some(ch_ewf_grpc::company_incorporation::PscStatement::NoPsc) => proto::company_incorporation::NoPSCStatement::NoPSC,
+                            None => return Err(tonic::Status::invalid_argument("Company level PSC statement required".to_string()))
+                        }),
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Psc::Pscs(s)) =>
+                        proto::company_incorporation::PSCs::PSCs(s.pscs.into_iter().map(|p| Ok(proto::company_incorporation::Psc {
+                            notification: proto::company_incorporation::PSCNotification {
+                                notification: match p.notification {
+                                    Some(n) => n.try_into()?,
+                                    None => return Err(tonic::Status::invalid_argument("PSC notification required".to_string()))
+                                },
+                                nature_of_control: match p.nature_of_control {
+                                    Some(n) => n.try_into()?,
+                                    None => return Err(tonic::Status::invalid_argument("PSC nature of controls required".to_string()))
+                                },
+                            },
+                        })).collect::<Result<Vec<_>, _>>()?),
+                    None => return Err(tonic::Status::invalid_argument("PSCs required".to_string()))
+                },
+                statement_of_capital: if msg.statement_of_capital.is_empty() {
+                    None
+                } else {
+                    Some(proto::base_types::StatementOfCapital {
+                        capital: msg.statement_of_capital.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?
+                    })
+                },
+                subscribers: msg.subscribers.into_iter().map(|s| {
+                    if s.allotments.is_empty() {
+                        return Err(tonic::Status::invalid_argument("Subscriber shares required".to_string()));
+                    }
+
+                    Ok(proto::company_incorporation::Subscriber {
+                        person: match s.person {
+                            Some(p) => p.try_into()?,
+                            None => return Err(tonic::Status::invalid_argument("Subscriber person required".to_string()))
                         },
-                        ct61_may_apply: r.ct61_may_apply,
-                        principal_place_of_business: match r.principal_place_of_business {
-                            Some(ch_ewf_grpc::company_incorporation::corporation_tax_registration::PrincipalPlaceOfBusiness::PrincipalPlaceOfBusinessSameAsRegisteredOffice(p)) => {
-                                if !p {
-                                    return Err(tonic::Status::invalid_argument("Same as registered office must be true".to_string()));
-                                }
-                                proto::corporation_tax_information::PrincipalPlaceOfBusiness::SameAsRegisteredOffice(true)
+                        shares: s.allotments.into_iter().map(|a| {
+                            if a.share_class.is_empty() || a.share_class.len() > 50 {
+                                return Err(tonic::Status::invalid_argument("Invalid share class"));
                             }
-                            Some(ch_ewf_grpc::company_incorporation::corporation_tax_registration::PrincipalPlaceOfBusiness::PrincipalPlaceOfBusinessAddress(a)) => {
-                                proto::corporation_tax_information::PrincipalPlaceOfBusiness::Address(a.try_into()?)
+                            if a.num_shares < 0.0 || a.num_shares > 999999999999999.999999 {
+                                return Err(tonic::Status::invalid_argument("Invalid number of shares"));
                             }
-                            None => return Err(tonic::Status::invalid_argument("Principal place of business required".to_string()))
-                        },
-                        taken_over_business: r.taken_over_business.map(|t| {
-                            if t.previous_business_name.is_empty() || t.previous_business_name.len() > 100 {
-                                return Err(tonic::Status::invalid_argument("Invalid previous business name".to_string()));
+                            if a.amount_paid_due_per_share < 0.0 || a.amount_paid_due_per_share > 999999999999999.999999 {
+                                return Err(tonic::Status::invalid_argument("Invalid amount paid due per share"));
+                            }
+                            if a.amount_unpaid_per_share < 0.0 || a.amount_unpaid_per_share > 999999999999999.999999 {
+                                return Err(tonic::Status::invalid_argument("Invalid amount unpaid per share"));
                             }
-                            if t.previous_owner_name.is_empty() || t.previous_owner_name.len() > 100 {
-                                return Err(tonic::Status::invalid_argument("Invalid previous owner name".to_string()));
+                            if a.share_currency.len() != 3 {
+                                return Err(tonic::Status::invalid_argument("Invalid share currency"));
                             }
 
-                            Ok(proto::corporation_tax_information::TakenOverBusiness {
-                                previous_business: proto::corporation_tax_information::PreviousBusiness {
-                                    business_name: t.previous_business_name,
-                                    business_type: if t.previous_business_type.is_empty() {
-                                        None
-                                    } else {
-                                        if t.previous_business_type.len() > 50 {
-                                            return Err(tonic::Status::invalid_argument("Invalid previous business type".to_string()));
-                                        }
-                                        Some(t.previous_business_type)
-                                    },
-                                    company_registration_number: if t.previous_company_registration_number.is_empty() {
-                                        None
-                                    } else {
-                                        if t.previous_company_registration_number.len() > 8 {
-                                            return Err(tonic::Status::invalid_argument("Invalid previous company registration number".to_string()));
-                                        }
-                                        Some(t.previous_company_registration_number)
-                                    },
-                                    address: match t.previous_address {
-                                        Some(a) => a.try_into()?,
-                                        None => return Err(tonic::Status::invalid_argument("Previous business address required".to_string()))
-                                    },
-                                },
-                                previous_owner: proto::corporation_tax_information::PreviousOwner {
-                                    owner_name: t.previous_owner_name,
-                                    address: match t.previous_owner_address {
-                                        Some(a) => a.try_into()?,
-                                        None => return Err(tonic::Status::invalid_argument("Previous owner address required".to_string()))
-                                    },
+                            Ok(proto::base_types::Allotment {
+                                share_class: a.share_class,
+                                num_shares: a.num_shares,
+                                amount_paid_due_per_share: a.amount_paid_due_per_share,
+                                amount_unpaid_per_share: a.amount_unpaid_per_share,
+                                share_currency: a.share_currency,
+                                share_value: a.share_value,
+                                share_reference: if a.share_reference.is_empty() {
+                                    None
+                                } else {
+                                    Some(a.share_reference)
                                 },
                             })
-                        }).transpose()?,
+                        }).collect::<Result<Vec<_>, _>>()?,
+                        memorandum_statement: match ch_ewf_grpc::company_incorporation::MemorandumStatement::from_i32(s.memorandum_statement) {
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithShares) =>
+                                Some(proto::company_incorporation::MemorandumStatement::MemberWithShares),
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithoutShares) =>
+                                Some(proto::company_incorporation::MemorandumStatement::MemberWithoutShares),
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::NoMemorandumStatement) => None,
+                            None => None
+                        },
+                    })
+                }).collect::<Result<Vec<_>, _>>()?,
+                guarantors: msg.guarantors.into_iter().map(|s| {
+                    if s.amount_guaranteed.is_empty() || s.amount_guaranteed.len() > 100 {
+                        return Err(tonic::Status::invalid_argument("Invalid amount guaranteed".to_string()));
+                    }
+
+                    Ok(proto::company_incorporation::Guarantor {
+                        person: match s.person {
+                            Some(p) => p.try_into()?,
+                            None => return Err(tonic::Status::invalid_argument("Subscriber person required".to_string()))
+                        },
+                        amount_guaranteed: s.amount_guaranteed,
+                        memorandum_statement: match ch_ewf_grpc::company_incorporation::MemorandumStatement::from_i32(s.memorandum_statement) {
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithShares) =>
+                                Some(proto::company_incorporation::MemorandumStatement::MemberWithShares),
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::MemberWithoutShares) =>
+                                Some(proto::company_incorporation::MemorandumStatement::MemberWithoutShares),
+                            Some(ch_ewf_grpc::company_incorporation::MemorandumStatement::NoMemorandumStatement) => None,
+                            None => None
+                        }
+                    })
+                }).collect::<Result<Vec<_>, _>>()?,
+                authoriser: match msg.authorizer {
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Agent(a)) => {
+                        proto::company_incorporation::Authoriser::Agent(proto::company_incorporation::Agent {
+                            authoriser: match a.authorizer {
+                                Some(a) => a.try_into()?,
+                                None => return Err(tonic::Status::invalid_argument("Agent authorization required".to_string()))
+                            },
+                            address: match a.address {
+                                Some(a) => a.try_into()?,
+                                None => return Err(tonic::Status::invalid_argument("Agent address required".to_string()))
+                            },
+                        })
+                    }
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Solicitor(s)) => {
+                        proto::company_incorporation::Authoriser::Solicitor(s.try_into()?)
                     }
-                ))).transpose()?,
-                documents,
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::Member(m)) => {
+                        proto::company_incorporation::Authoriser::Member(m.try_into()?)
+                    }
+                    Some(ch_ewf_grpc::company_incorporation::company_incorporation::Authorizer::AuthorizerSubscribers(s)) => {
+                        proto::company_incorporation::Authoriser::Subscribers(proto::company_incorporation::AuthoriserSubscribers {
+                            subscribers: s.subscribers.into_iter().map(TryInto::try_into).collect::<Result<Vec<_>, _>>()?
+                        })
+                    }
+                    None => return Err(tonic::Status::invalid_argument("Authorizer required".to_string()))
+                },
+                same_day: msg.same_day,
+                same_name: has_same_name,
+                name_authorisation: has_name_authorization,
+                reject_reference: msg.reject_reference.map(|r| {
+                    if r.len() < 8 || r.len() > 9 {
+                        return Err(tonic::Status::invalid_argument("Invalid reject reference".to_string()));
+                    }
+                    Ok(r)
+                }).transpose()?,
+                sic_codes: if msg.sic_codes.is_empty() {
+                    None
+                } else {
+                    Some(proto::base_types::SICCodes {
+                        codes: msg.sic_codes.into_iter().map(|sic| {
+                            if sic.len() > 5 || sic.len() < 4 || sic.chars().map(|c| c.is_numeric()).any(|x| !x) {
+                                Err(tonic::Status::invalid_argument("Invalid SIC code".to_string()))
+                            } else {
+                                Ok(sic)
+                            }
+                        }).collect::<Result<Vec<_>, _>>()?
+                    })
+                },
+                single_member_company: msg.single_member_company,
             })),
-        ).await {
-            Ok(r) => r,
-            Err(e) => {
-                return Err(tonic::Status::unknown(
-                    format!("Transaction ID: {}; error description: {}", e.transaction_id, e.errors.into_iter().map(|e| e.msg).collect::<Vec<_>>().join("; "))
-                ));
-            }
-        };
+            additional_information: msg.corporation_tax_registration.map(|r| Ok(proto::form_submission::AdditionalInformation::CorporationTaxInformation(
+                proto::corporation_tax_information::CorporationTaxInformation {
+                    abbreviated_company_name: if r.abbreviated_company_name.is_empty() {
+                        None
+                    } else {
+                        if r.abbreviated_company_name.len() > 56 {
+                            return Err(tonic::Status::invalid_argument("Invalid abbreviated company name".to_string()));
+                        }
+                        Some(r.abbreviated_company_name)
+                    },
+                    first_accounting_period_start_date: match proto_to_chrono(r.first_accounting_period_start_date) {
+                        Some(d) => d.date(),
+                        None => return Err(tonic::Status::invalid_argument("First accounting period start date required".to_string()))
+                    },
+                    accounts_made_up_date: match proto_to_chrono(r.accounts_made_up_date) {
+                        Some(d) => d.date(),
+                        None => return Err(tonic::Status::invalid_argument("Accounts made up date required".to_string()))
+                    },
+                    ct61_may_apply: r.ct61_may_apply,
+                    principal_place_of_business: match r.principal_place_of_business {
+                        Some(ch_ewf_grpc::company_incorporation::corporation_tax_registration::PrincipalPlaceOfBusiness::PrincipalPlaceOfBusinessSameAsRegisteredOffice(p)) => {
+                            if !p {
+                                return Err(tonic::Status::invalid_argument("Same as registered office must be true".to_string()));
+                            }
+                            proto::corporation_tax_information::PrincipalPlaceOfBusiness::SameAsRegisteredOffice(true)
+                        }
+                        Some(ch_ewf_grpc::company_incorporation::corporation_tax_registration::PrincipalPlaceOfBusiness::PrincipalPlaceOfBusinessAddress(a)) => {
+                            proto::corporation_tax_information::PrincipalPlaceOfBusiness::Address(a.try_into()?)
+                        }
+                        None => return Err(tonic::Status::invalid_argument("Principal place of business required".to_string()))
+                    },
+                    taken_over_business: r.taken_over_business.map(|t| {
+                        if t.previous_business_name.is_empty() || t.previous_business_name.len() > 100 {
+                            return Err(tonic::Status::invalid_argument("Invalid previous business name".to_string()));
+                        }
+                        if t.previous_owner_name.is_empty() || t.previous_owner_name.len() > 100 {
+                            return Err(tonic::Status::invalid_argument("Invalid previous owner name".to_string()));
+                        }
 
-        let new_submission = models::Submission {
-            id: submission_id,
-            ch_submission_id: submission_number.clone(),
-            company_number: None,
-            received_timestamp: res.gateway_timestamp.naive_utc(),
-            customer_reference: msg.customer_reference,
-            status: schema::Status::Pending,
-            reject_reference: None,
-            examiner_telephone: None,
-            examiner_comment: None,
-            document_id: None,
-            incorporation_date: None,
-            authentication_code: None,
-            charge_code: None,
+                        Ok(proto::corporation_tax_information::TakenOverBusiness {
+                            previous_business: proto::corporation_tax_information::PreviousBusiness {
+                                business_name: t.previous_business_name,
+                                business_type: if t.previous_business_type.is_empty() {
+                                    None
+                                } else {
+                                    if t.previous_business_type.len() > 50 {
+                                        return Err(tonic::Status::invalid_argument("Invalid previous business type".to_string()));
+                                    }
+                                    Some(t.previous_business_type)
+                                },
+                                company_registration_number: if t.previous_company_registration_number.is_empty() {
+                                    None
+                                } else {
+                                    if t.previous_company_registration_number.len() > 8 {
+                                        return Err(tonic::Status::invalid_argument("Invalid previous company registration number".to_string()));
+                                    }
+                                    Some(t.previous_company_registration_number)
+                                },
+                                address: match t.previous_address {
+                                    Some(a) => a.try_into()?,
+                                    None => return Err(tonic::Status::invalid_argument("Previous business address required".to_string()))
+                                },
+                            },
+                            previous_owner: proto::corporation_tax_information::PreviousOwner {
+                                owner_name: t.previous_owner_name,
+                                address: match t.previous_owner_address {
+                                    Some(a) => a.try_into()?,
+                                    None => return Err(tonic::Status::invalid_argument("Previous owner address required".to_string()))
+                                },
+                            },
+                        })
+                    }).transpose()?,
+                }
+            ))).transpose()?,
+            documents,
         };
 
-        if let Err(err) = diesel::insert_into(schema::submissions::table)
-            .values(new_submission)
-            .execute(&conn) {
-            return Err(tonic::Status::internal(format!("Unable to save submission to DB: {}", err)));
-        }
-
-        Ok(tonic::Response::new(ch_ewf_grpc::form_submission::SubmissionResponse {
-            transaction_id: res.transaction_id,
-            submission_id: submission_id.to_string(),
-            ch_submission_number: submission_number,
-        }))
+        Ok(tonic::Response::new(
+            self.send_form_submission(new_submission, "CompanyIncorporation", "CompanyIncorporation", body).await?
+        ))
     }
 }
\ No newline at end of file
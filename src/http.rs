@@ -0,0 +1,196 @@
+use super::{metrics, deadlines, grpc};
+use tokio_diesel::AsyncConnection;
+use std::convert::Infallible;
+use tokio_stream::StreamExt;
+use hmac::Mac;
+
+#[derive(Clone)]
+pub struct HTTPService {
+    pub connection: r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>,
+    pub calendar_token: Option<String>,
+    pub filling: grpc::CHFillingService,
+}
+
+impl HTTPService {
+    async fn handle(self, req: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, Infallible> {
+        Ok(match (req.method(), req.uri().path()) {
+            (&hyper::Method::GET, "/metrics") => self.metrics(),
+            (&hyper::Method::GET, "/deadlines.ics") => self.deadlines_calendar(&req).await,
+            (&hyper::Method::GET, p) if p.starts_with("/documents/") => self.document(&req).await,
+            _ => Self::error_response(hyper::StatusCode::NOT_FOUND, "Not found"),
+        })
+    }
+
+    fn metrics(&self) -> hyper::Response<hyper::Body> {
+        metrics::update_pool_metrics(&self.connection);
+
+        match metrics::render() {
+            Ok(m) => hyper::Response::builder()
+                .header(hyper::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+                .body(hyper::Body::from(m))
+                .unwrap(),
+            Err(err) => {
+                error!("Unable to render metrics: {}", err);
+                Self::error_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Unable to render metrics")
+            }
+        }
+    }
+
+    fn query_param(req: &hyper::Request<hyper::Body>, name: &str) -> Option<String> {
+        req.uri().query()?.split('&').find_map(|p| {
+            let mut parts = p.splitn(2, '=');
+            if parts.next().and_then(percent_decode).as_deref() == Some(name) {
+                parts.next().and_then(percent_decode)
+            } else {
+                None
+            }
+        })
+    }
+
+    async fn deadlines_calendar(&self, req: &hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+        match (&self.calendar_token, Self::query_param(req, "token")) {
+            (Some(token), Some(given)) if token_matches(token, &given) => {}
+            (None, _) => return Self::error_response(hyper::StatusCode::NOT_FOUND, "Not found"),
+            _ => return Self::error_response(hyper::StatusCode::FORBIDDEN, "Invalid token"),
+        }
+        let company_number = Self::query_param(req, "company_number").map(|c| c.to_uppercase());
+
+        match self.connection.run(move |c| deadlines::load_deadlines(c, company_number)).await {
+            Ok(d) => hyper::Response::builder()
+                .header(hyper::header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+                .body(hyper::Body::from(deadlines::render_ical(&d)))
+                .unwrap(),
+            Err(err) => {
+                error!("Unable to load deadlines: {}", err);
+                Self::error_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Unable to load deadlines")
+            }
+        }
+    }
+
+    async fn document(&self, req: &hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+        let signer = match &self.filling.url_signer {
+            Some(s) => s,
+            None => return Self::error_response(hyper::StatusCode::NOT_FOUND, "Not found"),
+        };
+        let document_id = match uuid::Uuid::parse_str(req.uri().path().trim_start_matches("/documents/")) {
+            Ok(i) => i,
+            Err(_) => return Self::error_response(hyper::StatusCode::NOT_FOUND, "Not found"),
+        };
+        let expires = Self::query_param(req, "expires").and_then(|e| e.parse::<i64>().ok());
+        match (expires, Self::query_param(req, "signature")) {
+            (Some(expires), Some(signature)) if signer.verify(&document_id, expires, &signature) => {}
+            _ => return Self::error_response(hyper::StatusCode::FORBIDDEN, "Invalid or expired signature"),
+        }
+
+        let document = match self.filling.load_document(&document_id.to_string()).await {
+            Ok(d) => d,
+            Err(err) if err.code() == tonic::Code::NotFound => return Self::error_response(hyper::StatusCode::NOT_FOUND, "Not found"),
+            Err(_) => return Self::error_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Unable to load document"),
+        };
+        let (size, _, mut chunks) = match self.filling.stream_document(&document, 0, 0).await {
+            Ok(d) => d,
+            Err(_) => return Self::error_response(hyper::StatusCode::INTERNAL_SERVER_ERROR, "Unable to load document"),
+        };
+
+        // An error part way through aborts the response, so the client doesn't take it as complete
+        let (mut sender, body) = hyper::Body::channel();
+        tokio::spawn(async move {
+            while let Some(chunk) = chunks.next().await {
+                match chunk {
+                    Ok(c) => if sender.send_data(c.into()).await.is_err() {
+                        return;
+                    },
+                    Err(_) => {
+                        sender.abort();
+                        return;
+                    }
+                }
+            }
+        });
+
+        let filename = document.document_filename.replace(|c: char| c == '"' || c == '\\' || c.is_control(), "_");
+        hyper::Response::builder()
+            .header(hyper::header::CONTENT_TYPE, "application/pdf")
+            .header(hyper::header::CONTENT_LENGTH, size)
+            .header(hyper::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+            .header(hyper::header::CACHE_CONTROL, "private, no-store")
+            .body(body)
+            .unwrap()
+    }
+
+    fn error_response(status: hyper::StatusCode, msg: &'static str) -> hyper::Response<hyper::Body> {
+        hyper::Response::builder()
+            .status(status)
+            .header(hyper::header::CONTENT_TYPE, "text/plain")
+            .body(hyper::Body::from(msg))
+            .unwrap()
+    }
+}
+
+/// Decodes a query string component, where spaces may also be given as `+`
+fn percent_decode(value: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                if !hex.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+                out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b)
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Compares a secret token without taking longer the more of it is right, by checking an HMAC of the given
+/// token against one of the expected token
+fn token_matches(expected: &str, given: &str) -> bool {
+    let mac = |token: &str| {
+        let mut mac = Hmac::new_from_slice(expected.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(token.as_bytes());
+        mac
+    };
+    mac(given).verify_slice(&mac(expected).finalize().into_bytes()).is_ok()
+}
+
+type Hmac = hmac::Hmac<sha2::Sha256>;
+
+pub async fn serve(socket: std::net::SocketAddr, service: HTTPService) -> Result<(), hyper::Error> {
+    let make_svc = hyper::service::make_service_fn(move |_conn| {
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(hyper::service::service_fn(move |req| service.clone().handle(req)))
+        }
+    });
+
+    hyper::Server::bind(&socket).serve(make_svc).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_params_are_decoded() {
+        let req = hyper::Request::get("/deadlines.ics?token=a%2Bb%3D&company_number=EW+123&bad=%zz")
+            .body(hyper::Body::empty())
+            .unwrap();
+        assert_eq!(HTTPService::query_param(&req, "token").as_deref(), Some("a+b="));
+        assert_eq!(HTTPService::query_param(&req, "company_number").as_deref(), Some("EW 123"));
+        assert_eq!(HTTPService::query_param(&req, "bad"), None);
+        assert_eq!(HTTPService::query_param(&req, "missing"), None);
+    }
+
+    #[test]
+    fn tokens_compared() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secret", "secreT"));
+        assert!(!token_matches("secret", "secret2"));
+        assert!(!token_matches("secret", ""));
+    }
+}
@@ -0,0 +1,324 @@
+use super::{ch_ewf_grpc, schema, names, capital, community_interest};
+use ch_ewf_grpc::company_incorporation::{self as incorporation, CompanyType};
+use ch_ewf_grpc::incorporation_workflow as workflow;
+
+const MAX_E_REMINDER_EMAILS: usize = 5;
+
+impl From<schema::IncorporationWorkflowState> for workflow::WorkflowState {
+    fn from(value: schema::IncorporationWorkflowState) -> Self {
+        match value {
+            schema::IncorporationWorkflowState::Draft => workflow::WorkflowState::Draft,
+            schema::IncorporationWorkflowState::Submitted => workflow::WorkflowState::Submitted,
+            schema::IncorporationWorkflowState::Rejected => workflow::WorkflowState::Rejected,
+            schema::IncorporationWorkflowState::Accepted => workflow::WorkflowState::Accepted,
+            schema::IncorporationWorkflowState::Completed => workflow::WorkflowState::Completed,
+        }
+    }
+}
+
+impl From<workflow::WorkflowState> for schema::IncorporationWorkflowState {
+    fn from(value: workflow::WorkflowState) -> Self {
+        match value {
+            workflow::WorkflowState::Draft => schema::IncorporationWorkflowState::Draft,
+            workflow::WorkflowState::Submitted => schema::IncorporationWorkflowState::Submitted,
+            workflow::WorkflowState::Rejected => schema::IncorporationWorkflowState::Rejected,
+            workflow::WorkflowState::Accepted => schema::IncorporationWorkflowState::Accepted,
+            workflow::WorkflowState::Completed => schema::IncorporationWorkflowState::Completed,
+        }
+    }
+}
+
+impl From<workflow::submit_incorporation_workflow_request::Authorizer> for incorporation::company_incorporation::Authorizer {
+    fn from(value: workflow::submit_incorporation_workflow_request::Authorizer) -> Self {
+        match value {
+            workflow::submit_incorporation_workflow_request::Authorizer::Agent(a) =>
+                incorporation::company_incorporation::Authorizer::Agent(a),
+            workflow::submit_incorporation_workflow_request::Authorizer::Solicitor(a) =>
+                incorporation::company_incorporation::Authorizer::Solicitor(a),
+            workflow::submit_incorporation_workflow_request::Authorizer::Member(a) =>
+                incorporation::company_incorporation::Authorizer::Member(a),
+            workflow::submit_incorporation_workflow_request::Authorizer::AuthorizerSubscribers(a) =>
+                incorporation::company_incorporation::Authorizer::AuthorizerSubscribers(a),
+        }
+    }
+}
+
+/// Whether a workflow's draft can still be changed and submitted
+pub fn editable(state: schema::IncorporationWorkflowState) -> bool {
+    state == schema::IncorporationWorkflowState::Draft || state == schema::IncorporationWorkflowState::Rejected
+}
+
+fn is_llp(company_type: i32) -> bool {
+    company_type == CompanyType::Llp as i32 || company_type == CompanyType::LlpOnlyDesignated as i32
+}
+
+fn is_guarantee(company_type: i32) -> bool {
+    company_type == CompanyType::LimitedByGuarantee as i32 || company_type == CompanyType::LimitedByGuaranteeExempt as i32
+}
+
+fn check_document(document: &Option<ch_ewf_grpc::base_types::Document>, name: &str) -> Result<(), tonic::Status> {
+    if let Some(d) = document {
+        if d.data.is_empty() {
+            return Err(tonic::Status::invalid_argument(format!("{} has no content", name)));
+        }
+        if d.filename.len() > 32 {
+            return Err(tonic::Status::invalid_argument(format!("{} filename too long", name)));
+        }
+        if ch_ewf_grpc::base_types::ContentType::from_i32(d.content_type).is_none() {
+            return Err(tonic::Status::invalid_argument(format!("{} content type required", name)));
+        }
+    }
+    Ok(())
+}
+
+/// Sets the company details of a draft, returning the e-reminder recipients
+pub fn set_details(
+    draft: &mut incorporation::CompanyIncorporation, details: workflow::IncorporationDetails, name_index: &names::NameIndex,
+) -> Result<Vec<String>, tonic::Status> {
+    if details.company_name.len() < 3 || details.company_name.len() > 160 {
+        return Err(tonic::Status::invalid_argument("Invalid company name length"));
+    }
+    let company_type = match CompanyType::from_i32(details.company_type) {
+        Some(t) => t,
+        None => return Err(tonic::Status::invalid_argument("Invalid company type"))
+    };
+    if incorporation::CountryOfIncorporation::from_i32(details.country_of_incorporation).is_none() {
+        return Err(tonic::Status::invalid_argument("Invalid country of incorporation"));
+    }
+    if ch_ewf_grpc::form_submission::Language::from_i32(details.language).is_none() {
+        return Err(tonic::Status::invalid_argument("Invalid language"));
+    }
+    if incorporation::Articles::from_i32(details.articles).is_none() {
+        return Err(tonic::Status::invalid_argument("Invalid articles type"));
+    }
+    if details.sic_codes.len() > 4 {
+        return Err(tonic::Status::invalid_argument("At most 4 SIC codes"));
+    }
+    if details.sic_codes.iter().any(|sic| sic.len() > 5 || sic.len() < 4 || !sic.chars().all(|c| c.is_numeric())) {
+        return Err(tonic::Status::invalid_argument("Invalid SIC code"));
+    }
+    if details.e_reminder_emails.len() > MAX_E_REMINDER_EMAILS {
+        return Err(tonic::Status::invalid_argument(format!("At most {} e-reminder email addresses", MAX_E_REMINDER_EMAILS)));
+    }
+    if details.e_reminder_emails.iter().any(|e| e.len() > 254 || !e.contains('@')) {
+        return Err(tonic::Status::invalid_argument("Invalid e-reminder email address"));
+    }
+
+    // Sensitive words and same as names are left outstanding until the documents approving them are added
+    let welsh = details.country_of_incorporation == incorporation::CountryOfIncorporation::Wales as i32;
+    let kind: names::CompanyKind = company_type.into();
+    let kind = if details.community_interest_statement.is_some() || draft.cic36.is_some() {
+        kind.community_interest().unwrap_or(kind)
+    } else {
+        kind
+    };
+    names::check(&details.company_name, Some(kind), welsh, name_index).into_result(true, true)?;
+
+    draft.company_name = details.company_name;
+    draft.company_type = details.company_type;
+    draft.country_of_incorporation = details.country_of_incorporation;
+    draft.language = details.language;
+    draft.registered_office = details.registered_office;
+    draft.articles = details.articles;
+    draft.restricted_articles = details.restricted_articles;
+    draft.sic_codes = details.sic_codes;
+    draft.registers_held_on_public_record = details.registers_held_on_public_record;
+    draft.single_member_company = details.single_member_company;
+    draft.contact_name = details.contact_name;
+    draft.contact_number = details.contact_number;
+    draft.customer_reference = details.customer_reference;
+    draft.corporation_tax_registration = details.corporation_tax_registration;
+    draft.community_interest_statement = details.community_interest_statement;
+    Ok(details.e_reminder_emails)
+}
+
+pub fn set_officers(
+    draft: &mut incorporation::CompanyIncorporation, appointments: Vec<incorporation::Appointment>,
+) -> Result<(), tonic::Status> {
+    if appointments.is_empty() {
+        return Err(tonic::Status::invalid_argument("Appointments required"));
+    }
+    for appointment in &appointments {
+        if appointment.appointment.is_none() {
+            return Err(tonic::Status::invalid_argument("Appointment type required"));
+        }
+        if !appointment.consent_to_act {
+            return Err(tonic::Status::invalid_argument("Every officer must consent to act"));
+        }
+    }
+    draft.appointments = appointments;
+    Ok(())
+}
+
+pub fn set_psc(
+    draft: &mut incorporation::CompanyIncorporation, psc: Option<workflow::set_incorporation_psc_request::Psc>,
+) -> Result<(), tonic::Status> {
+    draft.psc = match psc {
+        Some(workflow::set_incorporation_psc_request::Psc::PscStatement(s)) => {
+            if incorporation::PscStatement::from_i32(s).is_none() {
+                return Err(tonic::Status::invalid_argument("Invalid PSC statement"));
+            }
+            Some(incorporation::company_incorporation::Psc::PscStatement(s))
+        }
+        Some(workflow::set_incorporation_psc_request::Psc::Pscs(p)) => {
+            if p.pscs.is_empty() {
+                return Err(tonic::Status::invalid_argument("PSCs required, or a PSC statement"));
+            }
+            for psc in &p.pscs {
+                if psc.notification.as_ref().and_then(|n| n.psc.as_ref()).is_none() {
+                    return Err(tonic::Status::invalid_argument("PSC details required"));
+                }
+                if psc.nature_of_control.as_ref().and_then(|n| n.nature_of_controls.as_ref()).is_none() {
+                    return Err(tonic::Status::invalid_argument("Nature of control required"));
+                }
+            }
+            Some(incorporation::company_incorporation::Psc::Pscs(p))
+        }
+        None => return Err(tonic::Status::invalid_argument("PSCs or a PSC statement required"))
+    };
+    Ok(())
+}
+
+pub fn set_capital(
+    draft: &mut incorporation::CompanyIncorporation, msg: workflow::SetIncorporationCapitalRequest,
+) -> Result<(), tonic::Status> {
+    if msg.subscribers.iter().any(|s| s.person.is_none()) || msg.guarantors.iter().any(|g| g.person.is_none()) {
+        return Err(tonic::Status::invalid_argument("Subscriber and guarantor details required"));
+    }
+    let subscriber_allotments = msg.subscribers.iter()
+        .flat_map(|s| s.allotments.iter().map(Into::into))
+        .collect::<Vec<capital::Allotment>>();
+    if msg.statement_of_capital.is_empty() {
+        if !subscriber_allotments.is_empty() {
+            return Err(tonic::Status::invalid_argument("Statement of capital required for subscribers' shares"));
+        }
+    } else {
+        let mut errors = capital::check_statement(&msg.statement_of_capital);
+        errors.extend(capital::check_allotments(
+            &msg.statement_of_capital, &subscriber_allotments, capital::Allotments::All,
+        ));
+        capital::into_result(errors)?;
+    }
+
+    draft.statement_of_capital = msg.statement_of_capital;
+    draft.subscribers = msg.subscribers;
+    draft.guarantors = msg.guarantors;
+    Ok(())
+}
+
+pub fn set_documents(
+    draft: &mut incorporation::CompanyIncorporation, msg: workflow::SetIncorporationDocumentsRequest,
+) -> Result<(), tonic::Status> {
+    check_document(&msg.memorandum, "Memorandum")?;
+    check_document(&msg.articles_doc, "Articles")?;
+    check_document(&msg.same_name, "Same name document")?;
+    check_document(&msg.name_authorization, "Name authorisation")?;
+    check_document(&msg.cic36, "CIC36")?;
+
+    draft.memorandum = msg.memorandum;
+    draft.articles_doc = msg.articles_doc;
+    draft.same_name = msg.same_name;
+    draft.name_authorization = msg.name_authorization;
+    draft.cic36 = msg.cic36;
+    draft.generate_memorandum = msg.generate_memorandum;
+    draft.generate_articles = msg.generate_articles;
+    Ok(())
+}
+
+/// Everything stopping a draft from being submitted. Each step is checked as it's set, so this covers
+/// what's missing and what depends on more than one step.
+pub fn outstanding(draft: &incorporation::CompanyIncorporation, name_index: &names::NameIndex) -> Vec<String> {
+    use incorporation::appointment::Appointment;
+
+    let mut outstanding = vec![];
+    let company_type = draft.company_type;
+
+    if draft.registered_office.is_none() {
+        outstanding.push("Registered office required".to_string());
+    }
+
+    let welsh = draft.country_of_incorporation == incorporation::CountryOfIncorporation::Wales as i32;
+    let name_check = names::check(
+        &draft.company_name, community_interest::name_kind(draft), welsh, name_index,
+    );
+    outstanding.extend(name_check.problems);
+    if !name_check.sensitive_words.is_empty() && draft.name_authorization.is_none() {
+        outstanding.push(format!(
+            "Name contains sensitive words, a name authorisation document is required: {}",
+            name_check.sensitive_words.iter().map(|(w, _)| *w).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    if let Some(same_as) = name_check.same_as {
+        if draft.same_name.is_none() {
+            outstanding.push(format!("Name is the same as {}, a same name document is required", same_as));
+        }
+    }
+
+    let count = |f: fn(&Appointment) -> bool| draft.appointments.iter()
+        .filter(|a| a.appointment.as_ref().map(f).unwrap_or(false))
+        .count();
+    let directors = count(|a| matches!(a, Appointment::Director(_)));
+    let secretaries = count(|a| matches!(a, Appointment::Secretary(_)));
+    let members = count(|a| matches!(a, Appointment::Member(_)));
+    if draft.appointments.is_empty() {
+        outstanding.push("Officers required".to_string());
+    } else if is_llp(company_type) {
+        if directors + secretaries > 0 {
+            outstanding.push("LLPs have members rather than directors or secretaries".to_string());
+        }
+        if members < 2 {
+            outstanding.push("LLPs need at least two members".to_string());
+        }
+    } else {
+        if members > 0 {
+            outstanding.push("Only LLPs have members as officers".to_string());
+        }
+        if company_type == CompanyType::Plc as i32 {
+            if directors < 2 || secretaries < 1 {
+                outstanding.push("Public companies need at least two directors and a secretary".to_string());
+            }
+        } else if directors < 1 {
+            outstanding.push("At least one director required".to_string());
+        }
+    }
+
+    if draft.psc.is_none() {
+        outstanding.push("PSCs or a PSC statement required".to_string());
+    }
+
+    if is_llp(company_type) {
+        if !draft.statement_of_capital.is_empty() || !draft.subscribers.is_empty() || !draft.guarantors.is_empty() {
+            outstanding.push("LLPs have no subscribers, guarantors or share capital".to_string());
+        }
+    } else if is_guarantee(company_type) {
+        if draft.guarantors.is_empty() {
+            outstanding.push("Guarantors required".to_string());
+        }
+        if !draft.statement_of_capital.is_empty() {
+            outstanding.push("Companies limited by guarantee have no share capital".to_string());
+        }
+    } else {
+        if draft.subscribers.is_empty() {
+            outstanding.push("Subscribers required".to_string());
+        }
+        if draft.statement_of_capital.is_empty() {
+            outstanding.push("Statement of capital required".to_string());
+        }
+        if !draft.guarantors.is_empty() {
+            outstanding.push("Only companies limited by guarantee have guarantors".to_string());
+        }
+    }
+
+    let articles = incorporation::Articles::from_i32(draft.articles);
+    let needs_articles = matches!(articles, Some(incorporation::Articles::AmendedByShares)
+        | Some(incorporation::Articles::AmendedByGuarantee) | Some(incorporation::Articles::AmendedPlc)
+        | Some(incorporation::Articles::Bespoke));
+    if draft.generate_articles && needs_articles {
+        outstanding.push("Only model articles can be generated".to_string());
+    } else if needs_articles && draft.articles_doc.is_none() && !community_interest::is_community_interest(draft) {
+        outstanding.push("Amended or bespoke articles require an articles document".to_string());
+    }
+    outstanding.extend(community_interest::check(draft));
+
+    outstanding
+}
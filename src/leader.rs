@@ -0,0 +1,80 @@
+use diesel::prelude::*;
+
+/// Arbitrary key for the Postgres advisory lock held by the instance running the submission watcher
+const WATCHER_LOCK_ID: i64 = 0x4348_4557_4600;
+
+#[derive(QueryableByName)]
+struct AdvisoryLock {
+    #[sql_type = "diesel::sql_types::Bool"]
+    locked: bool,
+}
+
+/// Leader election for the submission watcher.
+///
+/// The advisory lock is held on a dedicated connection outside the pool, so that it is released
+/// by Postgres as soon as the leading instance goes away.
+pub struct LeaderLock {
+    database_url: String,
+    connection: Option<diesel::pg::PgConnection>,
+}
+
+impl LeaderLock {
+    pub fn new(database_url: &str) -> Self {
+        Self {
+            database_url: database_url.to_string(),
+            connection: None,
+        }
+    }
+
+    pub async fn is_leader(&mut self) -> bool {
+        // Connecting and querying block, so are kept off the async runtime
+        let connection = self.connection.take();
+        let database_url = self.database_url.clone();
+        match tokio::task::spawn_blocking(move || Self::check_leader(&database_url, connection)).await {
+            Ok((connection, leader)) => {
+                self.connection = connection;
+                leader
+            }
+            Err(err) => {
+                error!("Unable to check submission watcher leadership: {}", err);
+                false
+            }
+        }
+    }
+
+    fn check_leader(
+        database_url: &str, connection: Option<diesel::pg::PgConnection>,
+    ) -> (Option<diesel::pg::PgConnection>, bool) {
+        if let Some(conn) = connection {
+            match diesel::sql_query("SELECT 1").execute(&conn) {
+                Ok(_) => return (Some(conn), true),
+                Err(err) => warn!("Lost submission watcher leadership: {}", err)
+            }
+        }
+
+        let conn = match diesel::pg::PgConnection::establish(database_url) {
+            Ok(c) => c,
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                return (None, false);
+            }
+        };
+
+        match diesel::sql_query("SELECT pg_try_advisory_lock($1) AS locked")
+            .bind::<diesel::sql_types::BigInt, _>(WATCHER_LOCK_ID)
+            .get_result::<AdvisoryLock>(&conn) {
+            Ok(l) if l.locked => {
+                info!("Acquired submission watcher leadership");
+                (Some(conn), true)
+            }
+            Ok(_) => {
+                debug!("Submission watcher on standby, another instance is leader");
+                (None, false)
+            }
+            Err(err) => {
+                error!("Unable to access DB: {}", err);
+                (None, false)
+            }
+        }
+    }
+}
@@ -8,6 +8,10 @@ extern crate diesel_derive_enum;
 extern crate diesel_migrations;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate prometheus;
 
 use std::str::FromStr;
 
@@ -20,6 +24,28 @@ mod base_types;
 mod members;
 mod officers;
 mod grpc;
+mod metrics;
+mod http;
+mod telemetry;
+mod leader;
+mod cli;
+mod company_history;
+mod officer_reconciliation;
+mod psc_reconciliation;
+mod psc_determination;
+mod deadlines;
+mod crypto;
+mod managed_companies;
+mod document_store;
+mod registers;
+mod capital_ledger;
+mod capital;
+mod pdf;
+mod names;
+mod incorporation_workflow;
+mod constitution;
+mod community_interest;
+mod bulk_submission;
 
 pub mod ch_ewf_grpc {
     #![allow(unknown_lints, clippy::all)]
@@ -125,6 +151,12 @@ pub mod ch_ewf_grpc {
     pub mod return_allotment_shares {
         tonic::include_proto!("ch_ewf.return_allotment_shares");
     }
+    pub mod increase_nominal_capital {
+        tonic::include_proto!("ch_ewf.increase_nominal_capital");
+    }
+    pub mod bulk_submission {
+        tonic::include_proto!("ch_ewf.bulk_submission");
+    }
 
     pub mod company_incorporation {
         tonic::include_proto!("ch_ewf.company_incorporation");
@@ -141,6 +173,50 @@ pub mod ch_ewf_grpc {
     pub mod charge_search {
         tonic::include_proto!("ch_ewf.charge_search");
     }
+
+    pub mod company_history {
+        tonic::include_proto!("ch_ewf.company_history");
+    }
+
+    pub mod officer_reconciliation {
+        tonic::include_proto!("ch_ewf.officer_reconciliation");
+    }
+
+    pub mod psc_reconciliation {
+        tonic::include_proto!("ch_ewf.psc_reconciliation");
+    }
+
+    pub mod deadlines {
+        tonic::include_proto!("ch_ewf.deadlines");
+    }
+
+    pub mod managed_companies {
+        tonic::include_proto!("ch_ewf.managed_companies");
+    }
+
+    pub mod documents {
+        tonic::include_proto!("ch_ewf.documents");
+    }
+
+    pub mod registers {
+        tonic::include_proto!("ch_ewf.registers");
+    }
+
+    pub mod capital {
+        tonic::include_proto!("ch_ewf.capital");
+    }
+
+    pub mod psc_determination {
+        tonic::include_proto!("ch_ewf.psc_determination");
+    }
+
+    pub mod names {
+        tonic::include_proto!("ch_ewf.names");
+    }
+
+    pub mod incorporation_workflow {
+        tonic::include_proto!("ch_ewf.incorporation_workflow");
+    }
 }
 
 pub fn establish_connection(database_url: String) -> r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>> {
@@ -157,13 +233,36 @@ struct Config {
     presenter_email: String,
     package_reference: String,
     database_url: String,
-    documents_path: std::path::PathBuf,
+    #[serde(default)]
+    documents_path: Option<std::path::PathBuf>,
+    #[serde(default)]
+    document_store: Option<document_store::DocumentStoreConfig>,
+    #[serde(default)]
+    document_urls: Option<document_store::DocumentURLConfig>,
+    #[serde(default)]
+    encryption_key_path: Option<std::path::PathBuf>,
+    #[serde(default)]
+    company_names_index: Option<std::path::PathBuf>,
+    #[serde(default)]
+    model_articles_path: Option<std::path::PathBuf>,
+    #[serde(default = "default_bulk_submit_concurrency")]
+    bulk_submit_concurrency: usize,
     #[serde(default = "default_listen_url")]
     listen_socket: std::net::SocketAddr,
     #[serde(default)]
+    http_listen_socket: Option<std::net::SocketAddr>,
+    #[serde(default)]
+    calendar_token: Option<String>,
+    #[serde(default)]
     test_mode: bool,
     #[serde(default)]
-    tls: Option<TLSConfig>
+    standby: bool,
+    #[serde(default)]
+    reconciliation: grpc::ReconciliationConfig,
+    #[serde(default)]
+    tls: Option<TLSConfig>,
+    #[serde(default)]
+    tracing: Option<telemetry::TracingConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -173,6 +272,10 @@ struct TLSConfig {
     client_ca_path: Option<std::path::PathBuf>,
 }
 
+fn default_bulk_submit_concurrency() -> usize {
+    4
+}
+
 fn default_listen_url() -> std::net::SocketAddr {
     std::net::SocketAddr::new(
         std::net::Ipv6Addr::from_str("::1").unwrap().into(), 50051
@@ -181,16 +284,7 @@ fn default_listen_url() -> std::net::SocketAddr {
 
 #[tokio::main]
 async fn main() {
-    let args = clap::Command::new("AS207960 Companies House eFiller")
-        .author("Q Misell <q@as207960.net>")
-        .version(env!("CARGO_PKG_VERSION"))
-        .arg(clap::Arg::new("settings")
-            .short('s')
-            .long("settings")
-            .takes_value(true)
-            .required(false)
-            .help("Location of the settings file"))
-        .get_matches();
+    let args = cli::command().get_matches();
 
     if systemd_journal_logger::connected_to_journal() {
         systemd_journal_logger::init().unwrap();
@@ -210,6 +304,11 @@ async fn main() {
         .try_deserialize()
         .unwrap();
 
+    if let Some(tracing_config) = &settings.tracing {
+        telemetry::init(tracing_config);
+    }
+
+    let leader_lock = leader::LeaderLock::new(&settings.database_url);
     let connection = establish_connection(settings.database_url);
 
     let sender = gov_talk::GovTalkSender::new(
@@ -217,40 +316,102 @@ async fn main() {
         settings.test_mode
     );
 
-    let mut server_builder = tonic::transport::Server::builder();
-    if let Some(tls_config) = settings.tls {
-        let mut server_tls_config = tonic::transport::server::ServerTlsConfig::new();
-        let tls_cert = tokio::fs::read(tls_config.server_tls_cert_path).await.expect("Unable to read server TLS certificate");
-        let tls_key = tokio::fs::read(tls_config.server_tls_key_path).await.expect("Unable to read server TLS key");
-        server_tls_config = server_tls_config.identity(tonic::transport::Identity::from_pem(tls_cert, tls_key));
-        if let Some(client_ca_path) = tls_config.client_ca_path {
-            let client_ca = tokio::fs::read(client_ca_path).await.expect("Unable to read client CA certificate");
-            server_tls_config = server_tls_config.client_ca_root(tonic::transport::Certificate::from_pem(client_ca));
-        }
-        server_builder = server_builder.tls_config(server_tls_config).expect("Unable to apply TLS config");
-    }
+    let cipher = settings.encryption_key_path.as_ref().map(|path| {
+        std::sync::Arc::new(crypto::Cipher::from_key_file(path).expect("Unable to load encryption key"))
+    });
 
+    let name_index = std::sync::Arc::new(match &settings.company_names_index {
+        Some(path) => names::NameIndex::load(path).expect("Unable to load company names index"),
+        None => names::NameIndex::default()
+    });
 
-    info!("Migrating database...");
-    embedded_migrations::run(&connection.get().expect("Unable to get DB connection"))
-        .expect("Unable to apply migrations");
+    let model_articles = settings.model_articles_path.as_ref().map(|path| {
+        std::sync::Arc::new(constitution::ModelArticles::load(path).expect("Unable to load model articles"))
+    });
+
+    let document_store = match (settings.document_store, settings.documents_path) {
+        (Some(store), _) => store,
+        (None, Some(path)) => document_store::DocumentStoreConfig::Local { path },
+        (None, None) => panic!("One of document_store or documents_path must be set")
+    }.build().expect("Unable to configure document store");
 
     let service = grpc::CHFillingService {
         sender,
         connection,
-        documents_path: settings.documents_path,
+        document_store,
+        url_signer: settings.document_urls.as_ref().map(|c| std::sync::Arc::new(document_store::URLSigner::new(c))),
         presenter_id: settings.presenter_id,
         package_reference: settings.package_reference,
+        cipher,
+        name_index,
+        model_articles,
+        bulk_submit_permits: std::sync::Arc::new(tokio::sync::Semaphore::new(settings.bulk_submit_concurrency)),
+        submission_id: None,
     };
-    let w_service = service.clone();
-    let server = server_builder
-        .add_service(ch_ewf_grpc::ch_filling_server::ChFillingServer::new(service));
 
-    info!("Starting submission watcher...");
-    tokio::task::spawn(async move {
-        w_service.watcher().await
-    });
+    match args.subcommand() {
+        None | Some(("serve", _)) => {
+            let mut server_builder = tonic::transport::Server::builder()
+                .trace_fn(telemetry::grpc_request_span);
+            if let Some(tls_config) = settings.tls {
+                let mut server_tls_config = tonic::transport::server::ServerTlsConfig::new();
+                let tls_cert = tokio::fs::read(tls_config.server_tls_cert_path).await.expect("Unable to read server TLS certificate");
+                let tls_key = tokio::fs::read(tls_config.server_tls_key_path).await.expect("Unable to read server TLS key");
+                server_tls_config = server_tls_config.identity(tonic::transport::Identity::from_pem(tls_cert, tls_key));
+                if let Some(client_ca_path) = tls_config.client_ca_path {
+                    let client_ca = tokio::fs::read(client_ca_path).await.expect("Unable to read client CA certificate");
+                    server_tls_config = server_tls_config.client_ca_root(tonic::transport::Certificate::from_pem(client_ca));
+                }
+                server_builder = server_builder.tls_config(server_tls_config).expect("Unable to apply TLS config");
+            }
+
+            info!("Migrating database...");
+            embedded_migrations::run(&service.connection.get().expect("Unable to get DB connection"))
+                .expect("Unable to apply migrations");
+
+            let http_service = http::HTTPService {
+                connection: service.connection.clone(),
+                calendar_token: settings.calendar_token,
+                filling: service.clone(),
+            };
+
+            let w_service = service.clone();
+            let reconciliation = settings.reconciliation;
+            let server = server_builder
+                .add_service(ch_ewf_grpc::ch_filling_server::ChFillingServer::new(service));
+
+            if settings.standby {
+                info!("Running in standby, not starting submission watcher");
+            } else {
+                info!("Starting submission watcher...");
+                tokio::task::spawn(async move {
+                    w_service.watcher(leader_lock, reconciliation).await
+                });
+            }
+
+            if let Some(http_listen_socket) = settings.http_listen_socket {
+                info!("Starting HTTP server...");
+                tokio::task::spawn(async move {
+                    http::serve(http_listen_socket, http_service).await.expect("Unable to start HTTP listener")
+                });
+            }
+
+            info!("Starting server...");
+            server.serve(settings.listen_socket).await.expect("Unable to start listener");
+        }
+        Some(("migrate", _)) => {
+            info!("Migrating database...");
+            embedded_migrations::run(&service.connection.get().expect("Unable to get DB connection"))
+                .expect("Unable to apply migrations");
+        }
+        Some(_) => {
+            if let Err(err) = cli::run(service, &args).await {
+                error!("{}", err);
+                telemetry::shutdown();
+                std::process::exit(1);
+            }
+        }
+    }
 
-    info!("Starting server...");
-    server.serve(settings.listen_socket).await.expect("Unable to start listener");
+    telemetry::shutdown();
 }
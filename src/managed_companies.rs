@@ -0,0 +1,107 @@
+use super::{ch_ewf_grpc, models, schema};
+
+impl From<ch_ewf_grpc::base_types::CompanyType> for schema::CompanyType {
+    fn from(value: ch_ewf_grpc::base_types::CompanyType) -> Self {
+        match value {
+            ch_ewf_grpc::base_types::CompanyType::CompanyEnglandAndWales => schema::CompanyType::EnglandAndWales,
+            ch_ewf_grpc::base_types::CompanyType::CompanyScotland => schema::CompanyType::Scotland,
+            ch_ewf_grpc::base_types::CompanyType::CompanyNorthernIreland => schema::CompanyType::NorthernIreland,
+            ch_ewf_grpc::base_types::CompanyType::CompanyIreland => schema::CompanyType::Ireland,
+            ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipEnglandAndWales => schema::CompanyType::LlpEnglandAndWales,
+            ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipScotland => schema::CompanyType::LlpScotland,
+            ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipNorthernIreland => schema::CompanyType::LlpNorthernIreland,
+        }
+    }
+}
+
+impl From<schema::CompanyType> for ch_ewf_grpc::base_types::CompanyType {
+    fn from(value: schema::CompanyType) -> Self {
+        match value {
+            schema::CompanyType::EnglandAndWales => ch_ewf_grpc::base_types::CompanyType::CompanyEnglandAndWales,
+            schema::CompanyType::Scotland => ch_ewf_grpc::base_types::CompanyType::CompanyScotland,
+            schema::CompanyType::NorthernIreland => ch_ewf_grpc::base_types::CompanyType::CompanyNorthernIreland,
+            schema::CompanyType::Ireland => ch_ewf_grpc::base_types::CompanyType::CompanyIreland,
+            schema::CompanyType::LlpEnglandAndWales => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipEnglandAndWales,
+            schema::CompanyType::LlpScotland => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipScotland,
+            schema::CompanyType::LlpNorthernIreland => ch_ewf_grpc::base_types::CompanyType::LimitedLiabilityPartnershipNorthernIreland,
+        }
+    }
+}
+
+impl From<models::ManagedCompany> for ch_ewf_grpc::managed_companies::ManagedCompany {
+    fn from(value: models::ManagedCompany) -> Self {
+        ch_ewf_grpc::managed_companies::ManagedCompany {
+            id: value.id.to_string(),
+            company_number: value.company_number as u32,
+            company_type: ch_ewf_grpc::base_types::CompanyType::from(value.company_type).into(),
+            company_name: value.company_name,
+            tags: value.tags,
+        }
+    }
+}
+
+/// Splits a company number as returned by Companies House, such as `SC123456`, into its type and number
+pub fn parse_company_number(company_number: &str) -> Option<(u32, schema::CompanyType)> {
+    let split = company_number.find(|c: char| c.is_ascii_digit())?;
+    let (prefix, number) = company_number.split_at(split);
+    let company_type = match prefix {
+        "" => schema::CompanyType::EnglandAndWales,
+        "SC" => schema::CompanyType::Scotland,
+        "NI" => schema::CompanyType::NorthernIreland,
+        "R" => schema::CompanyType::Ireland,
+        "OC" => schema::CompanyType::LlpEnglandAndWales,
+        "SO" => schema::CompanyType::LlpScotland,
+        "NC" => schema::CompanyType::LlpNorthernIreland,
+        _ => return None
+    };
+    Some((number.parse().ok()?, company_type))
+}
+
+/// Requests that can name a managed company in place of passing its credentials
+pub trait CompanyCredentials {
+    fn managed_company_id(&self) -> &str;
+
+    fn set_credentials(&mut self, company: &models::ManagedCompany, authentication_code: String);
+}
+
+macro_rules! company_credentials {
+    ($($t:ty),*) => {
+        $(impl CompanyCredentials for $t {
+            fn managed_company_id(&self) -> &str {
+                &self.managed_company_id
+            }
+
+            fn set_credentials(&mut self, company: &models::ManagedCompany, authentication_code: String) {
+                self.company_number = company.company_number as u32;
+                self.company_type = ch_ewf_grpc::base_types::CompanyType::from(company.company_type).into();
+                self.authentication_code = authentication_code;
+                self.managed_company_id = String::new();
+            }
+        })*
+    }
+}
+
+company_credentials!(
+    ch_ewf_grpc::company_data::CompanyDataRequest,
+    ch_ewf_grpc::e_reminders::GetERemindersRequest,
+    ch_ewf_grpc::e_reminders::SetERemindersRequest,
+    ch_ewf_grpc::members_data::MembersRegisterRequest,
+    ch_ewf_grpc::payment_periods::PaymentPeriodsRequest,
+    ch_ewf_grpc::charge_search::ChargeSearchRequest
+);
+
+impl CompanyCredentials for ch_ewf_grpc::form_submission::FormSubmission {
+    fn managed_company_id(&self) -> &str {
+        &self.managed_company_id
+    }
+
+    fn set_credentials(&mut self, company: &models::ManagedCompany, authentication_code: String) {
+        self.company_number = company.company_number as u32;
+        self.company_type = ch_ewf_grpc::base_types::CompanyType::from(company.company_type).into();
+        if self.company_name.is_empty() {
+            self.company_name = company.company_name.clone();
+        }
+        self.authentication_code = authentication_code;
+        self.managed_company_id = String::new();
+    }
+}
@@ -0,0 +1,95 @@
+use prometheus::{IntCounter, IntCounterVec, IntGauge, Gauge, HistogramVec};
+
+lazy_static! {
+    pub static ref GATEWAY_TRANSACTIONS: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_gateway_transactions_total",
+        "GovTalk gateway transactions by message class and outcome",
+        &["class", "outcome"]
+    ).unwrap();
+    pub static ref GATEWAY_LATENCY: HistogramVec = register_histogram_vec!(
+        "ch_ewf_gateway_transaction_duration_seconds",
+        "GovTalk gateway transaction latency by message class",
+        &["class"],
+        vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+    ).unwrap();
+    pub static ref SUBMISSIONS: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_submissions_total",
+        "Forms submitted to the gateway by form type",
+        &["form_type"]
+    ).unwrap();
+    pub static ref SUBMISSION_RESULTS: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_submission_results_total",
+        "Submission status changes reported by the gateway by form type and status",
+        &["form_type", "status"]
+    ).unwrap();
+    pub static ref WATCHER_POLLS: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_watcher_polls_total",
+        "Submission watcher poll cycles by outcome",
+        &["outcome"]
+    ).unwrap();
+    pub static ref WATCHER_LEADER: IntGauge = register_int_gauge!(
+        "ch_ewf_watcher_leader",
+        "Whether this instance currently holds the submission watcher lock"
+    ).unwrap();
+    pub static ref PENDING_SUBMISSIONS: IntGauge = register_int_gauge!(
+        "ch_ewf_pending_submissions",
+        "Number of submissions awaiting a final status"
+    ).unwrap();
+    pub static ref OLDEST_PENDING_SUBMISSION_AGE: Gauge = register_gauge!(
+        "ch_ewf_oldest_pending_submission_age_seconds",
+        "Age of the oldest submission awaiting a final status"
+    ).unwrap();
+    pub static ref RECONCILIATIONS: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_reconciliations_total",
+        "Individual status queries for stale pending submissions by outcome",
+        &["outcome"]
+    ).unwrap();
+    pub static ref DOCUMENTS_FETCHED: IntCounterVec = register_int_counter_vec!(
+        "ch_ewf_documents_fetched_total",
+        "Documents fetched from the gateway by outcome",
+        &["outcome"]
+    ).unwrap();
+    pub static ref DOCUMENT_BYTES_STORED: IntCounter = register_int_counter!(
+        "ch_ewf_document_bytes_stored_total",
+        "Bytes of document data written to storage"
+    ).unwrap();
+    pub static ref DB_POOL_CONNECTIONS: IntGauge = register_int_gauge!(
+        "ch_ewf_db_pool_connections",
+        "Connections currently open in the database pool"
+    ).unwrap();
+    pub static ref DB_POOL_IDLE_CONNECTIONS: IntGauge = register_int_gauge!(
+        "ch_ewf_db_pool_idle_connections",
+        "Idle connections in the database pool"
+    ).unwrap();
+    pub static ref DB_POOL_MAX_CONNECTIONS: IntGauge = register_int_gauge!(
+        "ch_ewf_db_pool_max_connections",
+        "Maximum size of the database pool"
+    ).unwrap();
+}
+
+pub fn status_label(status: &super::schema::Status) -> &'static str {
+    match status {
+        super::schema::Status::Pending => "pending",
+        super::schema::Status::Accepted => "accepted",
+        super::schema::Status::Rejected => "rejected",
+        super::schema::Status::Parked => "parked",
+        super::schema::Status::InternalFailure => "internal_failure",
+        super::schema::Status::Unsent => "unsent",
+    }
+}
+
+pub fn update_pool_metrics(pool: &r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::pg::PgConnection>>) {
+    let state = pool.state();
+    DB_POOL_CONNECTIONS.set(state.connections as i64);
+    DB_POOL_IDLE_CONNECTIONS.set(state.idle_connections as i64);
+    DB_POOL_MAX_CONNECTIONS.set(pool.max_size() as i64);
+}
+
+pub fn render() -> Result<Vec<u8>, prometheus::Error> {
+    use prometheus::Encoder;
+
+    let encoder = prometheus::TextEncoder::new();
+    let mut buffer = vec![];
+    encoder.encode(&prometheus::gather(), &mut buffer)?;
+    Ok(buffer)
+}
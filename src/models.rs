@@ -15,7 +15,10 @@ pub struct Submission {
     pub document_id: Option<uuid::Uuid>,
     pub incorporation_date: Option<chrono::NaiveDate>,
     pub authentication_code: Option<String>,
-    pub charge_code: Option<String>
+    pub charge_code: Option<String>,
+    pub form_type: Option<String>,
+    pub authentication_code_encrypted: Option<Vec<u8>>,
+    pub document_request_key: Option<String>,
 }
 
 #[derive(Insertable, Queryable, Identifiable, AsChangeset, Clone, Debug)]
@@ -38,4 +41,212 @@ pub struct Documents {
     pub document_id: String,
     pub document_filename: String,
     pub storage_filename: String,
-}
\ No newline at end of file
+    pub encrypted: bool,
+    pub content_hash: Option<String>,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="company_snapshots"]
+pub struct CompanySnapshot {
+    pub id: uuid::Uuid,
+    pub company_number: String,
+    pub company_name: String,
+    pub transaction_id: String,
+    pub made_up_date: Option<chrono::NaiveDate>,
+    pub snapshot_timestamp: chrono::NaiveDateTime,
+    pub data: Vec<u8>,
+    pub data_encrypted: bool,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_officers"]
+pub struct CompanySnapshotOfficer {
+    pub id: uuid::Uuid,
+    pub snapshot_id: uuid::Uuid,
+    pub role: String,
+    pub name: String,
+    pub corporate: bool,
+    pub date_of_birth: Option<chrono::NaiveDate>,
+    pub appointment_date: Option<chrono::NaiveDate>,
+    pub resignation_date: Option<chrono::NaiveDate>,
+    pub designated: bool,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_pscs"]
+pub struct CompanySnapshotPSC {
+    pub id: uuid::Uuid,
+    pub snapshot_id: uuid::Uuid,
+    pub kind: String,
+    pub name: String,
+    pub notification_date: Option<chrono::NaiveDate>,
+    pub cessation_date: Option<chrono::NaiveDate>,
+    pub nature_of_controls: Vec<String>,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_shareholdings"]
+pub struct CompanySnapshotShareholding {
+    pub id: uuid::Uuid,
+    pub snapshot_id: uuid::Uuid,
+    pub share_class: String,
+    pub shareholders: String,
+    pub number_held: f64,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_sic_codes"]
+pub struct CompanySnapshotSICCode {
+    pub snapshot_id: uuid::Uuid,
+    pub sic_code: String,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_registered_offices"]
+pub struct CompanySnapshotRegisteredOffice {
+    pub snapshot_id: uuid::Uuid,
+    pub premise: String,
+    pub street: String,
+    pub thoroughfare: String,
+    pub post_town: String,
+    pub county: String,
+    pub country: String,
+    pub postcode: String,
+    pub care_of_name: String,
+    pub po_box: String,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug, PartialEq)]
+#[table_name="company_snapshot_capital"]
+pub struct CompanySnapshotCapital {
+    pub id: uuid::Uuid,
+    pub snapshot_id: uuid::Uuid,
+    pub currency: String,
+    pub share_class: String,
+    pub prescribed_particulars: String,
+    pub num_shares: f64,
+    pub aggregate_nominal_value: f64,
+    pub total_amount_unpaid: f64,
+}
+
+#[derive(Insertable, Queryable, Identifiable, AsChangeset, Clone, Debug)]
+#[table_name="tracked_companies"]
+#[primary_key(company_number)]
+#[changeset_options(treat_none_as_null="true")]
+pub struct TrackedCompany {
+    pub company_number: String,
+    pub company_name: String,
+    pub public_company: bool,
+    pub incorporation_date: chrono::NaiveDate,
+    pub accounting_period_end: chrono::NaiveDate,
+    pub last_accounts_made_up_to: Option<chrono::NaiveDate>,
+    pub confirmation_statement_made_up_to: Option<chrono::NaiveDate>,
+    pub confirmation_statement_due: Option<chrono::NaiveDate>,
+}
+
+#[derive(Insertable, Queryable, Clone, Debug)]
+#[table_name="accounting_reference_date_changes"]
+pub struct AccountingReferenceDateChange {
+    pub submission_id: uuid::Uuid,
+    pub company_number: String,
+    pub current_period_end: chrono::NaiveDate,
+    pub new_period_end: chrono::NaiveDate,
+    pub shortened: bool,
+    pub notice_date: chrono::NaiveDate,
+}
+
+#[derive(Insertable, Queryable, Identifiable, AsChangeset, Clone, Debug)]
+#[table_name="managed_companies"]
+pub struct ManagedCompany {
+    pub id: uuid::Uuid,
+    pub company_number: i32,
+    pub company_type: super::schema::CompanyType,
+    pub company_name: String,
+    pub authentication_code: Vec<u8>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="register_entries"]
+pub struct RegisterEntry {
+    pub id: uuid::Uuid,
+    pub submission_id: uuid::Uuid,
+    pub company_number: String,
+    pub register: super::schema::RegisterType,
+    pub entry_key: String,
+    pub entry_date: chrono::NaiveDate,
+    pub removal: bool,
+    pub details: serde_json::Value,
+    pub personal_details: Option<Vec<u8>>,
+    pub personal_details_encrypted: bool,
+}
+
+#[derive(Insertable, Queryable, Identifiable, AsChangeset, Clone, Debug)]
+#[table_name="share_classes"]
+pub struct ShareClass {
+    pub id: uuid::Uuid,
+    pub company_number: String,
+    pub share_class: String,
+    pub currency: String,
+    pub nominal_value: f64,
+    pub prescribed_particulars: String,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="share_movements"]
+pub struct ShareMovement {
+    pub id: uuid::Uuid,
+    pub share_class_id: uuid::Uuid,
+    pub movement: super::schema::ShareMovementType,
+    pub member_name: String,
+    pub from_member_name: Option<String>,
+    pub num_shares: f64,
+    pub amount_paid_per_share: f64,
+    pub amount_unpaid_per_share: f64,
+    pub movement_date: chrono::NaiveDate,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="incorporation_workflows"]
+pub struct IncorporationWorkflow {
+    pub id: uuid::Uuid,
+    pub state: super::schema::IncorporationWorkflowState,
+    pub draft: Vec<u8>,
+    pub draft_encrypted: bool,
+    pub e_reminder_emails: Vec<String>,
+    pub submission_id: Option<uuid::Uuid>,
+    pub company_number: Option<String>,
+    pub incorporation_date: Option<chrono::NaiveDate>,
+    pub authentication_code: Option<Vec<u8>>,
+    pub authentication_code_encrypted: bool,
+    pub certificate_document_id: Option<uuid::Uuid>,
+    pub managed_company_id: Option<uuid::Uuid>,
+    pub e_reminders_set: bool,
+    pub registers_recorded: bool,
+    pub follow_up_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="bulk_submissions"]
+pub struct BulkSubmission {
+    pub id: uuid::Uuid,
+    pub form_type: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, Identifiable, Clone, Debug)]
+#[table_name="bulk_submission_items"]
+pub struct BulkSubmissionItem {
+    pub id: uuid::Uuid,
+    pub bulk_submission_id: uuid::Uuid,
+    pub position: i32,
+    pub company_number: String,
+    pub state: super::schema::BulkItemState,
+    pub request: Option<Vec<u8>>,
+    pub request_encrypted: bool,
+    pub submission_id: Option<uuid::Uuid>,
+    pub error: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,475 @@
+use super::ch_ewf_grpc;
+
+/// Punctuation and symbols Companies House allows in names, besides letters, digits and spaces
+const PERMITTED_PUNCTUATION: &str = "&@£$€¥*=#%+‘’'()[]{}<>!«»?“”\"\\/,.:;-";
+/// Allowed in a name, but not within its first three characters
+const NOT_LEADING: &str = "*=#%+";
+
+/// Words and expressions that need approval, with the body whose view must be sought where there is one
+const SENSITIVE_WORDS: &[(&str, Option<&str>)] = &[
+    ("ACCREDIT", None), ("ACCREDITATION", None), ("ACCREDITED", None), ("ACCREDITING", None),
+    ("ADJUDICATOR", None), ("ASSOCIATION", None), ("ASSURANCE", None), ("ASSURER", None),
+    ("AUDIT OFFICE", None), ("AUDITOR GENERAL", None), ("AUTHORITY", None), ("BENEVOLENT", None),
+    ("BOARD", None), ("CHAMBER OF COMMERCE", None), ("CHAMBER OF INDUSTRY", None), ("CHAMBER OF TRADE", None),
+    ("CHARITABLE", Some("Charity Commission")), ("CHARITY", Some("Charity Commission")),
+    ("CHARTER", None), ("CHARTERED", None),
+    ("CHEMIST", Some("General Pharmaceutical Council")), ("CHEMISTRY", Some("General Pharmaceutical Council")),
+    ("COMMISSION", None), ("COMMISSIONER", None), ("CO-OPERATIVE", None), ("COOPERATIVE", None),
+    ("COUNCIL", None), ("COURT", None),
+    ("DENTAL", Some("General Dental Council")), ("DENTISTRY", Some("General Dental Council")),
+    ("DUKE", Some("Cabinet Office")), ("ENGLAND", None), ("ENGLISH", None), ("FEDERATION", None),
+    ("FOUNDATION", None), ("FRIENDLY SOCIETY", None), ("FUND", None), ("GOVERNMENT", None),
+    ("HEALTH CENTRE", Some("Department of Health and Social Care")),
+    ("HEALTH SERVICE", Some("Department of Health and Social Care")),
+    ("HEALTH VISITOR", Some("Nursing and Midwifery Council")),
+    ("HIS MAJESTY", Some("Cabinet Office")), ("HER MAJESTY", Some("Cabinet Office")), ("HM", Some("Cabinet Office")),
+    ("INSPECTORATE", None), ("INSTITUTE", None), ("INSTITUTION", None), ("INSURANCE", None), ("INSURER", None),
+    ("IRELAND", None), ("IRISH", None), ("JUDICIAL", None), ("KING", Some("Cabinet Office")),
+    ("LICENSING", None),
+    ("MIDWIFE", Some("Nursing and Midwifery Council")), ("MIDWIFERY", Some("Nursing and Midwifery Council")),
+    ("MINISTRY", None), ("NHS", Some("Department of Health and Social Care")), ("NORTHERN IRELAND", None),
+    ("NURSE", Some("Nursing and Midwifery Council")), ("NURSING", Some("Nursing and Midwifery Council")),
+    ("OMBUDSMAN", None), ("PARLIAMENT", None), ("PARLIAMENTARIAN", None), ("PATENT", None), ("PATENTEE", None),
+    ("POLICE", Some("Home Office")), ("POST OFFICE", None),
+    ("PRINCE", Some("Cabinet Office")), ("PRINCESS", Some("Cabinet Office")), ("QUEEN", Some("Cabinet Office")),
+    ("REASSURANCE", None), ("REASSURER", None), ("REGISTER", None), ("REGISTERED", None), ("REGULATOR", None),
+    ("REINSURANCE", None), ("REINSURER", None),
+    ("ROYAL", Some("Cabinet Office")), ("ROYALE", Some("Cabinet Office")), ("ROYALTY", Some("Cabinet Office")),
+    ("SCOTLAND", None), ("SCOTTISH", None), ("SHEFFIELD", None), ("SOCIETY", None), ("SPECIAL SCHOOL", None),
+    ("STANDARDS", None), ("STOCK EXCHANGE", None), ("TRADE UNION", None), ("TRUST", None),
+    ("UNITED KINGDOM", None), ("UNIVERSITY", Some("Office for Students")), ("WALES", None), ("WELSH", None),
+    ("WINDSOR", Some("Cabinet Office")), ("ARCHITECT", Some("Architects Registration Board")),
+    ("CYMRU", None), ("CYMREIG", None), ("CYMRAEG", None), ("LLYWODRAETH", None), ("SENEDD", None),
+];
+
+/// Name endings for each legal form, English then Welsh
+const PRIVATE_SUFFIXES: (&[&str], &[&str]) = (&["LIMITED", "LTD"], &["CYFYNGEDIG", "CYF"]);
+const PUBLIC_SUFFIXES: (&[&str], &[&str]) = (
+    &["PUBLIC LIMITED COMPANY", "PLC"], &["CWMNI CYFYNGEDIG CYHOEDDUS", "CCC"]
+);
+const LLP_SUFFIXES: (&[&str], &[&str]) = (
+    &["LIMITED LIABILITY PARTNERSHIP", "LLP"], &["PARTNERIAETH ATEBOLRWYDD CYFYNGEDIG", "PAC"]
+);
+const CIC_SUFFIXES: (&[&str], &[&str]) = (&["COMMUNITY INTEREST COMPANY", "CIC"], &["CWMNI BUDDIANT CYMUNEDOL", "CBC"]);
+const PUBLIC_CIC_SUFFIXES: (&[&str], &[&str]) = (
+    &["COMMUNITY INTEREST PUBLIC LIMITED COMPANY", "COMMUNITY INTEREST PLC"],
+    &["CWMNI BUDDIANT CYMUNEDOL CYHOEDDUS CYFYNGEDIG", "CBC CYHOEDDUS CYFYNGEDIG"]
+);
+
+/// Endings ignored when deciding if two names are the same
+const SAME_AS_ENDINGS: &[&str] = &[
+    "LIMITED", "LTD", "PUBLIC LIMITED COMPANY", "PLC", "LIMITED LIABILITY PARTNERSHIP", "LLP",
+    "CYFYNGEDIG", "CYF", "CWMNI CYFYNGEDIG CYHOEDDUS", "CCC", "PARTNERIAETH ATEBOLRWYDD CYFYNGEDIG", "PAC",
+    "UNLIMITED", "ANGYFYNGEDIG", "COMMUNITY INTEREST COMPANY", "CIC", "COMMUNITY INTEREST PLC",
+    "COMMUNITY INTEREST PUBLIC LIMITED COMPANY", "CWMNI BUDDIANT CYMUNEDOL", "CBC",
+    "CWMNI BUDDIANT CYMUNEDOL CYHOEDDUS CYFYNGEDIG", "CBC CYHOEDDUS CYFYNGEDIG",
+    "COMPANY", "CO", "AND COMPANY", "AND CO", "UK", "GB", "GREAT BRITAIN", "UNITED KINGDOM",
+    "COM", "CO UK", "NET", "ORG", "ORG UK", "EU", "INFO", "BIZ",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompanyKind {
+    PrivateLimited,
+    /// Exempt from ending its name with "limited" under section 60
+    PrivateLimitedExempt,
+    Public,
+    Llp,
+    Unlimited,
+    CommunityInterest,
+    PublicCommunityInterest,
+}
+
+impl CompanyKind {
+    /// The community interest form of a company, for those that can be one
+    pub fn community_interest(self) -> Option<Self> {
+        match self {
+            CompanyKind::PrivateLimited => Some(CompanyKind::CommunityInterest),
+            CompanyKind::Public => Some(CompanyKind::PublicCommunityInterest),
+            CompanyKind::CommunityInterest | CompanyKind::PublicCommunityInterest => Some(self),
+            _ => None
+        }
+    }
+}
+
+impl From<ch_ewf_grpc::company_incorporation::CompanyType> for CompanyKind {
+    fn from(value: ch_ewf_grpc::company_incorporation::CompanyType) -> Self {
+        match value {
+            ch_ewf_grpc::company_incorporation::CompanyType::LimitedByShares => CompanyKind::PrivateLimited,
+            ch_ewf_grpc::company_incorporation::CompanyType::LimitedByGuarantee => CompanyKind::PrivateLimited,
+            ch_ewf_grpc::company_incorporation::CompanyType::LimitedByGuaranteeExempt => CompanyKind::PrivateLimitedExempt,
+            ch_ewf_grpc::company_incorporation::CompanyType::Plc => CompanyKind::Public,
+            ch_ewf_grpc::company_incorporation::CompanyType::Llp => CompanyKind::Llp,
+            ch_ewf_grpc::company_incorporation::CompanyType::LlpOnlyDesignated => CompanyKind::Llp,
+        }
+    }
+}
+
+impl From<ch_ewf_grpc::company_data::CompanyCategory> for CompanyKind {
+    fn from(value: ch_ewf_grpc::company_data::CompanyCategory) -> Self {
+        match value {
+            ch_ewf_grpc::company_data::CompanyCategory::Plc => CompanyKind::Public,
+            ch_ewf_grpc::company_data::CompanyCategory::ByShares => CompanyKind::PrivateLimited,
+            ch_ewf_grpc::company_data::CompanyCategory::ByGuarantee => CompanyKind::PrivateLimited,
+            ch_ewf_grpc::company_data::CompanyCategory::BySharesExemptUnderSection60 => CompanyKind::PrivateLimitedExempt,
+            ch_ewf_grpc::company_data::CompanyCategory::ByGuaranteeExemptUnderSection60 => CompanyKind::PrivateLimitedExempt,
+            ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithShareCapital => CompanyKind::Unlimited,
+            ch_ewf_grpc::company_data::CompanyCategory::UnlimitedWithoutShareCapital => CompanyKind::Unlimited,
+            ch_ewf_grpc::company_data::CompanyCategory::Llp => CompanyKind::Llp,
+        }
+    }
+}
+
+/// Uppercase words of a name, splitting on anything that isn't a letter or digit
+fn words(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_uppercase)
+        .collect()
+}
+
+fn ends_with_phrase(words: &[String], phrase: &str) -> bool {
+    let phrase = phrase.split(' ').collect::<Vec<_>>();
+    words.len() >= phrase.len() && words[words.len() - phrase.len()..].iter().zip(&phrase).all(|(a, b)| a == b)
+}
+
+fn ending<'a>(words: &[String], suffixes: &[&'a str]) -> Option<&'a str> {
+    suffixes.iter().copied().find(|s| ends_with_phrase(words, s))
+}
+
+/// Works out a company's legal form from the ending of its current name, and whether it's in Welsh
+pub fn kind_from_name(name: &str) -> Option<(CompanyKind, bool)> {
+    let words = words(name);
+    // Longer endings are checked first: "community interest plc" ends with "plc", and the Welsh LLP
+    // and public CIC endings end with "cyfyngedig"
+    for (kind, (english, welsh)) in &[
+        (CompanyKind::PublicCommunityInterest, PUBLIC_CIC_SUFFIXES),
+        (CompanyKind::CommunityInterest, CIC_SUFFIXES),
+        (CompanyKind::Public, PUBLIC_SUFFIXES),
+        (CompanyKind::Llp, LLP_SUFFIXES),
+        (CompanyKind::PrivateLimited, PRIVATE_SUFFIXES),
+    ] {
+        if ending(&words, english).is_some() {
+            return Some((*kind, false));
+        }
+        if ending(&words, welsh).is_some() {
+            return Some((*kind, true));
+        }
+    }
+    None
+}
+
+/// Normalises a name following the "same as" rules, so names differing only in case, punctuation,
+/// common endings and the like compare equal
+pub fn normalise(name: &str) -> String {
+    let name = name.to_uppercase()
+        .replace('&', " AND ")
+        .replace('+', " PLUS ")
+        .replace('@', " AT ")
+        .replace('%', " PERCENT ")
+        .replace('£', " POUND ")
+        .replace('$', " DOLLAR ")
+        .replace('€', " EURO ")
+        .replace('¥', " YEN ");
+    let mut words = words(&name);
+    if words.first().map(|w| w == "THE").unwrap_or(false) {
+        words.remove(0);
+    }
+    if words.first().map(|w| w == "WWW").unwrap_or(false) {
+        words.remove(0);
+    }
+    loop {
+        let found = SAME_AS_ENDINGS.iter()
+            .filter(|e| ends_with_phrase(&words, e))
+            .map(|e| e.split(' ').count())
+            .max();
+        match found {
+            Some(n) if n < words.len() => words.truncate(words.len() - n),
+            _ => break
+        }
+    }
+    words.concat()
+}
+
+/// Normalised existing company names, to find names that are the same as a proposed one
+#[derive(Default)]
+pub struct NameIndex {
+    names: std::collections::HashMap<String, String>,
+}
+
+impl NameIndex {
+    /// Loads names from either a Companies House basic company data CSV, using its `CompanyName` column,
+    /// or a file of one name per line
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        let mut index = NameIndex::default();
+        let is_csv = path.extension().map(|e| e.eq_ignore_ascii_case("csv")).unwrap_or(false);
+        if is_csv {
+            let mut reader = match csv::Reader::from_path(path) {
+                Ok(r) => r,
+                Err(err) => return Err(format!("Unable to read name index: {}", err))
+            };
+            let column = match reader.headers() {
+                Ok(h) => match h.iter().position(|h| h.trim() == "CompanyName") {
+                    Some(c) => c,
+                    None => return Err("Name index has no CompanyName column".to_string())
+                },
+                Err(err) => return Err(format!("Unable to read name index: {}", err))
+            };
+            for record in reader.records() {
+                match record {
+                    Ok(r) => if let Some(name) = r.get(column) {
+                        index.insert(name);
+                    },
+                    Err(err) => return Err(format!("Unable to read name index: {}", err))
+                }
+            }
+        } else {
+            let names = match std::fs::read_to_string(path) {
+                Ok(n) => n,
+                Err(err) => return Err(format!("Unable to read name index: {}", err))
+            };
+            for name in names.lines() {
+                index.insert(name);
+            }
+        }
+        Ok(index)
+    }
+
+    fn insert(&mut self, name: &str) {
+        let name = name.trim();
+        if !name.is_empty() {
+            self.names.entry(normalise(name)).or_insert_with(|| name.to_string());
+        }
+    }
+
+    /// An existing name that's the same as the proposed one
+    pub fn same_as(&self, name: &str) -> Option<&str> {
+        self.names.get(&normalise(name)).map(String::as_str)
+    }
+}
+
+#[derive(Default)]
+pub struct NameCheck {
+    /// Problems that mean the name can't be registered as given
+    pub problems: Vec<String>,
+    /// Sensitive words in the name, and the body to consult if there is one
+    pub sensitive_words: Vec<(&'static str, Option<&'static str>)>,
+    pub same_as: Option<String>,
+}
+
+fn check_characters(name: &str, check: &mut NameCheck) {
+    let mut invalid = name.chars()
+        .filter(|c| !(c.is_ascii_alphanumeric() || *c == ' ' || PERMITTED_PUNCTUATION.contains(*c)
+            // Latin letters with accents
+            || (c.is_alphabetic() && ('\u{C0}'..='\u{24F}').contains(c))))
+        .collect::<Vec<_>>();
+    invalid.dedup();
+    if !invalid.is_empty() {
+        check.problems.push(format!(
+            "Name contains characters that aren't permitted: {}", invalid.into_iter().collect::<String>()
+        ));
+    }
+    if let Some(c) = name.chars().take(3).find(|c| NOT_LEADING.contains(*c)) {
+        check.problems.push(format!("{} can't be used in the first three characters of a name", c));
+    }
+}
+
+fn check_suffix(words: &[String], kind: CompanyKind, welsh: bool, check: &mut NameCheck) {
+    let allowed = |suffixes: (&[&'static str], &[&'static str])| -> Vec<&'static str> {
+        let mut allowed = suffixes.0.to_vec();
+        if welsh {
+            allowed.extend(suffixes.1);
+        }
+        allowed
+    };
+    let (required, description): (Option<Vec<&str>>, &str) = match kind {
+        CompanyKind::PrivateLimited => (Some(allowed(PRIVATE_SUFFIXES)), "a private limited company"),
+        CompanyKind::PrivateLimitedExempt => (None, "an exempt private limited company"),
+        CompanyKind::Public => (Some(allowed(PUBLIC_SUFFIXES)), "a public limited company"),
+        CompanyKind::Llp => (Some(allowed(LLP_SUFFIXES)), "an LLP"),
+        CompanyKind::Unlimited => (None, "an unlimited company"),
+        CompanyKind::CommunityInterest => (Some(allowed(CIC_SUFFIXES)), "a community interest company"),
+        CompanyKind::PublicCommunityInterest =>
+            (Some(allowed(PUBLIC_CIC_SUFFIXES)), "a public community interest company"),
+    };
+
+    let found = [PUBLIC_CIC_SUFFIXES, CIC_SUFFIXES, PUBLIC_SUFFIXES, LLP_SUFFIXES, PRIVATE_SUFFIXES].iter()
+        .flat_map(|(english, welsh)| english.iter().chain(welsh.iter()))
+        .copied()
+        .find(|s| ends_with_phrase(words, s));
+    match (&required, found) {
+        (Some(required), Some(found)) if !required.contains(&found) => check.problems.push(format!(
+            "The name of {} must end with one of {}, not {}", description, required.join(", "), found
+        )),
+        (Some(required), None) => check.problems.push(format!(
+            "The name of {} must end with one of {}", description, required.join(", ")
+        )),
+        (None, Some(found)) if kind == CompanyKind::Unlimited => check.problems.push(format!(
+            "The name of {} can't end with {}", description, found
+        )),
+        _ => {}
+    }
+
+    // Indications of legal form are only allowed as the ending
+    let mut body = &words[..words.len() - found.map(|f| f.split(' ').count()).unwrap_or(0)];
+    if kind == CompanyKind::Unlimited && body.last().map(|w| w == "UNLIMITED" || w == "ANGYFYNGEDIG").unwrap_or(false) {
+        body = &body[..body.len() - 1];
+    }
+    for form in &["LIMITED", "LTD", "PLC", "LLP", "UNLIMITED", "CYFYNGEDIG", "CCC", "PAC", "CIC", "CBC"] {
+        if body.iter().any(|w| w == form) {
+            check.problems.push(format!("{} can only be used at the end of a name", form));
+        }
+    }
+}
+
+fn sensitive_words(words: &[String]) -> Vec<(&'static str, Option<&'static str>)> {
+    SENSITIVE_WORDS.iter().copied().filter(|(word, _)| {
+        let phrase = word.split(|c: char| !c.is_alphanumeric()).collect::<Vec<_>>();
+        words.windows(phrase.len()).any(|w| {
+            let last = phrase.len() - 1;
+            w.iter().zip(&phrase).enumerate().all(|(i, (a, b))| {
+                // Plurals and possessives of the final word count too
+                a == b || (i == last && a.strip_suffix('S') == Some(*b))
+            })
+        })
+    }).collect()
+}
+
+/// Checks a proposed name against the naming rules. The legal form is only checked if known.
+pub fn check(name: &str, kind: Option<CompanyKind>, welsh: bool, index: &NameIndex) -> NameCheck {
+    let mut check = NameCheck::default();
+    let words = words(name);
+    if words.is_empty() {
+        check.problems.push("Name has no letters or digits".to_string());
+        return check;
+    }
+
+    check_characters(name, &mut check);
+    if let Some(kind) = kind {
+        check_suffix(&words, kind, welsh, &mut check);
+    }
+    check.sensitive_words = sensitive_words(&words);
+    check.same_as = index.same_as(name).map(str::to_string);
+    check
+}
+
+impl NameCheck {
+    /// Turns the check into an error for a filing, given which approvals the filing includes
+    pub fn into_result(self, sensitive_words_approved: bool, same_as_approved: bool) -> Result<(), tonic::Status> {
+        let mut problems = self.problems;
+        if !sensitive_words_approved && !self.sensitive_words.is_empty() {
+            problems.push(format!(
+                "Name contains sensitive words that need approval: {}",
+                self.sensitive_words.iter().map(|(w, _)| *w).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if !same_as_approved {
+            if let Some(same_as) = self.same_as {
+                problems.push(format!("Name is the same as {}", same_as));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(tonic::Status::invalid_argument(format!("Name not permitted: {}", problems.join("; "))))
+        }
+    }
+}
+
+impl From<NameCheck> for ch_ewf_grpc::names::CheckCompanyNameResponse {
+    fn from(value: NameCheck) -> Self {
+        ch_ewf_grpc::names::CheckCompanyNameResponse {
+            available: value.problems.is_empty() && value.same_as.is_none(),
+            problems: value.problems,
+            sensitive_words: value.sensitive_words.into_iter().map(|(word, body)| ch_ewf_grpc::names::SensitiveWord {
+                word: word.to_string(),
+                relevant_body: body.unwrap_or_default().to_string(),
+            }).collect(),
+            same_as: value.same_as.unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensitive(name: &str) -> Vec<&'static str> {
+        sensitive_words(&words(name)).into_iter().map(|(w, _)| w).collect()
+    }
+
+    #[test]
+    fn normalise_same_as() {
+        assert_eq!(normalise("The Widget Company Limited"), "WIDGET");
+        assert_eq!(normalise("widget co. ltd"), "WIDGET");
+        assert_eq!(normalise("WWW.WIDGET.CO.UK LTD"), "WIDGET");
+        assert_eq!(normalise("Smith & Jones Ltd"), normalise("SMITH AND JONES LIMITED"));
+        assert_eq!(normalise("1+1 Ltd"), "1PLUS1");
+        assert_ne!(normalise("Widget Services Ltd"), normalise("Widget Ltd"));
+    }
+
+    #[test]
+    fn normalise_keeps_a_word() {
+        // A name made up only of endings isn't reduced to nothing
+        assert_eq!(normalise("Company Limited"), "COMPANY");
+        assert_eq!(normalise("UK Ltd"), "UK");
+    }
+
+    #[test]
+    fn name_index() {
+        let mut index = NameIndex::default();
+        index.insert("THE WIDGET COMPANY LIMITED");
+        assert_eq!(index.same_as("Widget Ltd"), Some("THE WIDGET COMPANY LIMITED"));
+        assert_eq!(index.same_as("Widgets Ltd"), None);
+    }
+
+    #[test]
+    fn kind_from_endings() {
+        assert_eq!(kind_from_name("Widget Ltd"), Some((CompanyKind::PrivateLimited, false)));
+        assert_eq!(kind_from_name("Widget Cyf"), Some((CompanyKind::PrivateLimited, true)));
+        assert_eq!(kind_from_name("Widget PLC"), Some((CompanyKind::Public, false)));
+        assert_eq!(kind_from_name("Widget Partneriaeth Atebolrwydd Cyfyngedig"), Some((CompanyKind::Llp, true)));
+        assert_eq!(kind_from_name("Widget Community Interest PLC"), Some((CompanyKind::PublicCommunityInterest, false)));
+        assert_eq!(kind_from_name("Widget CIC"), Some((CompanyKind::CommunityInterest, false)));
+        assert_eq!(kind_from_name("Widget"), None);
+    }
+
+    #[test]
+    fn sensitive_word_matching() {
+        assert_eq!(sensitive("Royal Widgets Ltd"), vec!["ROYAL"]);
+        assert_eq!(sensitive("Kings Cross Widgets Ltd"), vec!["KING"]);
+        assert_eq!(sensitive("Widget Trust's Ltd"), vec!["TRUST"]);
+        assert_eq!(sensitive("Widget Post Office Ltd"), vec!["POST OFFICE"]);
+        assert_eq!(sensitive("NHS-Widget Ltd"), vec!["NHS"]);
+        assert!(sensitive("Post Widget Office Ltd").is_empty());
+        assert!(sensitive("Kingdom Widgets Ltd").is_empty());
+        assert!(sensitive("Trusty Widgets Ltd").is_empty());
+    }
+
+    #[test]
+    fn sensitive_word_bodies() {
+        assert_eq!(sensitive_words(&words("Charity Shop Ltd")), vec![("CHARITY", Some("Charity Commission"))]);
+        assert_eq!(sensitive_words(&words("Widget Foundation Ltd")), vec![("FOUNDATION", None)]);
+    }
+
+    #[test]
+    fn suffix_checks() {
+        let index = NameIndex::default();
+        assert!(check("Widget Ltd", Some(CompanyKind::PrivateLimited), false, &index).problems.is_empty());
+        assert_eq!(check("Widget", Some(CompanyKind::PrivateLimited), false, &index).problems.len(), 1);
+        assert_eq!(check("Widget PLC", Some(CompanyKind::PrivateLimited), false, &index).problems.len(), 1);
+        assert_eq!(check("Widget Cyf", Some(CompanyKind::PrivateLimited), false, &index).problems.len(), 1);
+        assert!(check("Widget Cyf", Some(CompanyKind::PrivateLimited), true, &index).problems.is_empty());
+        assert!(check("Widget Unlimited", Some(CompanyKind::Unlimited), false, &index).problems.is_empty());
+        assert_eq!(check("Widget Ltd", Some(CompanyKind::Unlimited), false, &index).problems.len(), 1);
+        assert_eq!(check("Limited Widgets Ltd", Some(CompanyKind::PrivateLimited), false, &index).problems.len(), 1);
+    }
+
+    #[test]
+    fn character_checks() {
+        let index = NameIndex::default();
+        assert!(check("Café Widgets Ltd", None, false, &index).problems.is_empty());
+        assert_eq!(check("*Widgets Ltd", None, false, &index).problems.len(), 1);
+        assert_eq!(check("Widgets ™ Ltd", None, false, &index).problems.len(), 1);
+        assert_eq!(check("---", None, false, &index).problems, vec!["Name has no letters or digits"]);
+    }
+}
@@ -0,0 +1,436 @@
+use super::ch_ewf_grpc;
+use super::company_history::person_name;
+use super::grpc::proto_to_chrono;
+use ch_ewf_grpc::{company_data, officer_appointment, officer_change, officer_reconciliation, officer_resignation};
+use ch_ewf_grpc::base_types;
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum OfficerKey {
+    Person(String, Option<chrono::NaiveDate>),
+    Corporate(String),
+}
+
+fn normalise(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Identifies a person the same way the change and resignation forms do, by name (ignoring title) and date of birth
+fn person_key(name: &Option<base_types::PersonName>, date_of_birth: &Option<prost_types::Timestamp>) -> OfficerKey {
+    let name = match name {
+        Some(n) => normalise(&n.forenames.iter()
+            .map(|f| f.as_str())
+            .chain(std::iter::once(n.surname.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")),
+        None => String::new()
+    };
+    OfficerKey::Person(name, proto_to_chrono(date_of_birth.clone()).map(|d| d.naive_utc().date()))
+}
+
+fn changed_string(live: &str, desired: &str) -> Option<String> {
+    if !desired.is_empty() && normalise(live) != normalise(desired) {
+        Some(desired.to_string())
+    } else {
+        None
+    }
+}
+
+fn changed<T: PartialEq + Clone>(live: &Option<T>, desired: &Option<T>) -> Option<T> {
+    if desired.is_some() && desired != live {
+        desired.clone()
+    } else {
+        None
+    }
+}
+
+struct Context<'a> {
+    form_submission: &'a Option<ch_ewf_grpc::form_submission::FormSubmission>,
+    date: Option<prost_types::Timestamp>,
+    consent_to_act: bool,
+}
+
+#[derive(Default)]
+struct Plan {
+    appointments: Vec<officer_reconciliation::PlannedFiling>,
+    changes: Vec<officer_reconciliation::PlannedFiling>,
+    resignations: Vec<officer_reconciliation::PlannedFiling>,
+}
+
+impl Context<'_> {
+    fn appointment(
+        &self, form: &str, name: &str, appointment: officer_appointment::officer_appointment::Appointment,
+    ) -> officer_reconciliation::PlannedFiling {
+        officer_reconciliation::PlannedFiling {
+            form: form.to_string(),
+            description: format!("Appoint {}", name),
+            filing: Some(officer_reconciliation::planned_filing::Filing::Appointment(officer_appointment::OfficerAppointment {
+                form_submission: self.form_submission.clone(),
+                appointment_date: self.date.clone(),
+                consent_to_act: self.consent_to_act,
+                appointment: Some(appointment),
+            })),
+            submission: None,
+            error: String::new(),
+        }
+    }
+
+    fn change(
+        &self, form: &str, name: &str, change: officer_change::officer_change::Change,
+    ) -> officer_reconciliation::PlannedFiling {
+        officer_reconciliation::PlannedFiling {
+            form: form.to_string(),
+            description: format!("Change details of {}", name),
+            filing: Some(officer_reconciliation::planned_filing::Filing::Change(officer_change::OfficerChange {
+                form_submission: self.form_submission.clone(),
+                date_of_change: self.date.clone(),
+                change: Some(change),
+            })),
+            submission: None,
+            error: String::new(),
+        }
+    }
+
+    fn resignation(
+        &self, form: &str, name: &str, resignation: officer_resignation::officer_resignation::Resignation,
+    ) -> officer_reconciliation::PlannedFiling {
+        officer_reconciliation::PlannedFiling {
+            form: form.to_string(),
+            description: format!("Terminate appointment of {}", name),
+            filing: Some(officer_reconciliation::planned_filing::Filing::Resignation(officer_resignation::OfficerResignation {
+                form_submission: self.form_submission.clone(),
+                resignation_date: self.date.clone(),
+                resignation: Some(resignation),
+            })),
+            submission: None,
+            error: String::new(),
+        }
+    }
+}
+
+fn corporate_change(live: &base_types::CorporateOfficer, desired: &base_types::CorporateOfficer) -> Option<officer_change::CorporateChange> {
+    let new_address = changed(&live.address, &desired.address);
+    let new_company_identification = changed(&live.company_identification, &desired.company_identification);
+    if new_address.is_none() && new_company_identification.is_none() {
+        return None;
+    }
+    Some(officer_change::CorporateChange {
+        corporate_name: live.corporate_name.clone(),
+        new_corporate_name: None,
+        new_address,
+        new_company_identification,
+    })
+}
+
+fn plan_directors(ctx: &Context, live: &[company_data::Director], desired: &[officer_appointment::Director], plan: &mut Plan) {
+    let mut live = live.iter()
+        .filter(|d| d.resignation_date.is_none())
+        .filter_map(|d| d.value.as_ref())
+        .map(|d| (match d {
+            company_data::director::Value::Person(p) => person_key(&p.person, &p.date_of_birth),
+            company_data::director::Value::Corporate(c) => OfficerKey::Corporate(normalise(&c.corporate_name)),
+        }, d))
+        .collect::<Vec<_>>();
+
+    for director in desired {
+        let (key, name) = match &director.director {
+            Some(officer_appointment::director::Director::Person(p)) => (person_key(&p.person, &p.date_of_birth), person_name(&p.person)),
+            Some(officer_appointment::director::Director::Corporate(c)) => match &c.corporate {
+                Some(c) => (OfficerKey::Corporate(normalise(&c.corporate_name)), c.corporate_name.clone()),
+                None => continue
+            },
+            None => continue
+        };
+
+        let existing = match live.iter().position(|(k, _)| k == &key) {
+            Some(i) => live.remove(i).1,
+            None => {
+                let form = match &director.director {
+                    Some(officer_appointment::director::Director::Person(_)) => "AP01",
+                    _ => "AP02"
+                };
+                plan.appointments.push(ctx.appointment(
+                    form, &name, officer_appointment::officer_appointment::Appointment::Director(director.clone())
+                ));
+                continue;
+            }
+        };
+
+        match (existing, &director.director) {
+            (company_data::director::Value::Person(l), Some(officer_appointment::director::Director::Person(d))) => {
+                let new_service_address = changed(&l.service_address, &d.service_address);
+                let new_residential_address = if l.residential_address.is_some() {
+                    changed(&l.residential_address, &d.residential_address)
+                } else {
+                    None
+                };
+                let new_nationality = changed_string(&l.nationality, &d.nationality);
+                let new_country_of_residence = changed_string(&l.country_of_residence, &d.country_of_residence);
+                let new_occupation = changed_string(&l.occupation, &d.occupation);
+                if new_service_address.is_none() && new_residential_address.is_none() && new_nationality.is_none() &&
+                    new_country_of_residence.is_none() && new_occupation.is_none() {
+                    continue;
+                }
+                plan.changes.push(ctx.change("CH01", &name, officer_change::officer_change::Change::Director(officer_change::Director {
+                    director: Some(officer_change::director::Director::Person(officer_change::DirectorPersonChange {
+                        person: l.person.clone(),
+                        date_of_birth: l.date_of_birth.clone(),
+                        new_name: None,
+                        new_service_address: new_service_address.map(|a| officer_change::ServiceAddress {
+                            address: Some(a),
+                            residential_address_unchanged: new_residential_address.is_none(),
+                        }),
+                        new_residential_address,
+                        new_nationality,
+                        new_country_of_residence,
+                        new_occupation,
+                    }))
+                })));
+            }
+            (company_data::director::Value::Corporate(l), Some(officer_appointment::director::Director::Corporate(d))) => {
+                if let Some(change) = d.corporate.as_ref().and_then(|d| corporate_change(l, d)) {
+                    plan.changes.push(ctx.change("CH02", &name, officer_change::officer_change::Change::Director(officer_change::Director {
+                        director: Some(officer_change::director::Director::Corporate(change))
+                    })));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (_, director) in live {
+        let (name, resignation) = match director {
+            company_data::director::Value::Person(p) => (
+                person_name(&p.person),
+                officer_resignation::director::Director::Person(officer_resignation::Person {
+                    person: p.person.clone(),
+                    date_of_birth: p.date_of_birth.clone(),
+                })
+            ),
+            company_data::director::Value::Corporate(c) => (
+                c.corporate_name.clone(),
+                officer_resignation::director::Director::Corporate(c.corporate_name.clone())
+            ),
+        };
+        plan.resignations.push(ctx.resignation("TM01", &name, officer_resignation::officer_resignation::Resignation::Director(
+            officer_resignation::Director { director: Some(resignation) }
+        )));
+    }
+}
+
+fn plan_secretaries(ctx: &Context, live: &[company_data::Secretary], desired: &[officer_appointment::Secretary], plan: &mut Plan) {
+    let mut live = live.iter()
+        .filter(|s| s.resignation_date.is_none())
+        .filter_map(|s| s.value.as_ref())
+        .map(|s| (match s {
+            company_data::secretary::Value::Person(p) => person_key(&p.person, &None),
+            company_data::secretary::Value::Corporate(c) => OfficerKey::Corporate(normalise(&c.corporate_name)),
+        }, s))
+        .collect::<Vec<_>>();
+
+    for secretary in desired {
+        let (key, name) = match &secretary.secretary {
+            Some(officer_appointment::secretary::Secretary::Person(p)) => (person_key(&p.person, &None), person_name(&p.person)),
+            Some(officer_appointment::secretary::Secretary::Corporate(c)) => match &c.corporate {
+                Some(c) => (OfficerKey::Corporate(normalise(&c.corporate_name)), c.corporate_name.clone()),
+                None => continue
+            },
+            None => continue
+        };
+
+        let existing = match live.iter().position(|(k, _)| k == &key) {
+            Some(i) => live.remove(i).1,
+            None => {
+                let form = match &secretary.secretary {
+                    Some(officer_appointment::secretary::Secretary::Person(_)) => "AP03",
+                    _ => "AP04"
+                };
+                plan.appointments.push(ctx.appointment(
+                    form, &name, officer_appointment::officer_appointment::Appointment::Secretary(secretary.clone())
+                ));
+                continue;
+            }
+        };
+
+        match (existing, &secretary.secretary) {
+            (company_data::secretary::Value::Person(l), Some(officer_appointment::secretary::Secretary::Person(d))) => {
+                if let Some(new_service_address) = changed(&l.service_address, &d.service_address) {
+                    plan.changes.push(ctx.change("CH03", &name, officer_change::officer_change::Change::Secretary(officer_change::Secretary {
+                        secretary: Some(officer_change::secretary::Secretary::Person(officer_change::SecretaryPersonChange {
+                            person: l.person.clone(),
+                            date_of_birth: None,
+                            new_name: None,
+                            new_service_address: Some(new_service_address),
+                        }))
+                    })));
+                }
+            }
+            (company_data::secretary::Value::Corporate(l), Some(officer_appointment::secretary::Secretary::Corporate(d))) => {
+                if let Some(change) = d.corporate.as_ref().and_then(|d| corporate_change(l, d)) {
+                    plan.changes.push(ctx.change("CH04", &name, officer_change::officer_change::Change::Secretary(officer_change::Secretary {
+                        secretary: Some(officer_change::secretary::Secretary::Corporate(change))
+                    })));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (_, secretary) in live {
+        let (name, resignation) = match secretary {
+            company_data::secretary::Value::Person(p) => (
+                person_name(&p.person),
+                officer_resignation::secretary::Secretary::Person(p.person.clone().unwrap_or_default())
+            ),
+            company_data::secretary::Value::Corporate(c) => (
+                c.corporate_name.clone(),
+                officer_resignation::secretary::Secretary::Corporate(c.corporate_name.clone())
+            ),
+        };
+        plan.resignations.push(ctx.resignation("TM02", &name, officer_resignation::officer_resignation::Resignation::Secretary(
+            officer_resignation::Secretary { secretary: Some(resignation) }
+        )));
+    }
+}
+
+fn plan_members(ctx: &Context, live: &[company_data::Member], desired: &[officer_appointment::Member], plan: &mut Plan) {
+    let mut live = live.iter()
+        .filter_map(|m| m.value.as_ref().map(|v| (v, m.designated)))
+        .map(|(m, designated)| (match m {
+            company_data::member::Value::Person(p) => person_key(&p.person, &p.date_of_birth),
+            company_data::member::Value::Corporate(c) => OfficerKey::Corporate(normalise(&c.corporate_name)),
+        }, m, designated))
+        .collect::<Vec<_>>();
+
+    for member in desired {
+        let (key, name) = match &member.member {
+            Some(officer_appointment::member::Member::Person(p)) => (person_key(&p.person, &p.date_of_birth), person_name(&p.person)),
+            Some(officer_appointment::member::Member::Corporate(c)) => match &c.corporate {
+                Some(c) => (OfficerKey::Corporate(normalise(&c.corporate_name)), c.corporate_name.clone()),
+                None => continue
+            },
+            None => continue
+        };
+
+        let (existing, designated) = match live.iter().position(|(k, _, _)| k == &key) {
+            Some(i) => {
+                let (_, m, d) = live.remove(i);
+                (m, d)
+            }
+            None => {
+                let form = match &member.member {
+                    Some(officer_appointment::member::Member::Person(_)) => "LLAP01",
+                    _ => "LLAP02"
+                };
+                plan.appointments.push(ctx.appointment(
+                    form, &name, officer_appointment::officer_appointment::Appointment::Member(member.clone())
+                ));
+                continue;
+            }
+        };
+
+        let new_designated = if designated != member.designated {
+            Some(officer_change::MemberDesignated {
+                designated: member.designated,
+                consent_to_act: ctx.consent_to_act,
+            })
+        } else {
+            None
+        };
+
+        match (existing, &member.member) {
+            (company_data::member::Value::Person(l), Some(officer_appointment::member::Member::Person(d))) => {
+                let new_service_address = changed(&l.service_address, &d.service_address);
+                let new_residential_address = if l.residential_address.is_some() {
+                    changed(&l.residential_address, &d.residential_address)
+                } else {
+                    None
+                };
+                let new_country_of_residence = changed_string(&l.country_of_residence, &d.country_of_residence);
+                if new_service_address.is_none() && new_residential_address.is_none() &&
+                    new_country_of_residence.is_none() && new_designated.is_none() {
+                    continue;
+                }
+                plan.changes.push(ctx.change("LLCH01", &name, officer_change::officer_change::Change::Member(officer_change::Member {
+                    member: Some(officer_change::member::Member::Person(officer_change::MemberPersonChange {
+                        person: l.person.clone(),
+                        date_of_birth: l.date_of_birth.clone(),
+                        new_name: None,
+                        new_service_address: new_service_address.map(|a| officer_change::ServiceAddress {
+                            address: Some(a),
+                            residential_address_unchanged: new_residential_address.is_none(),
+                        }),
+                        new_residential_address,
+                        new_country_of_residence,
+                        designated: new_designated,
+                    }))
+                })));
+            }
+            (company_data::member::Value::Corporate(l), Some(officer_appointment::member::Member::Corporate(d))) => {
+                let corporate_change = d.corporate.as_ref().and_then(|d| corporate_change(l, d));
+                if corporate_change.is_none() && new_designated.is_none() {
+                    continue;
+                }
+                plan.changes.push(ctx.change("LLCH02", &name, officer_change::officer_change::Change::Member(officer_change::Member {
+                    member: Some(officer_change::member::Member::Corporate(officer_change::CorporateMemberChange {
+                        corporate_change: Some(corporate_change.unwrap_or_else(|| officer_change::CorporateChange {
+                            corporate_name: l.corporate_name.clone(),
+                            ..Default::default()
+                        })),
+                        designated: new_designated,
+                    }))
+                })));
+            }
+            _ => {}
+        }
+    }
+
+    for (_, member, _) in live {
+        let (name, resignation) = match member {
+            company_data::member::Value::Person(p) => (
+                person_name(&p.person),
+                officer_resignation::member::Member::Person(officer_resignation::Person {
+                    person: p.person.clone(),
+                    date_of_birth: p.date_of_birth.clone(),
+                })
+            ),
+            company_data::member::Value::Corporate(c) => (
+                c.corporate_name.clone(),
+                officer_resignation::member::Member::Corporate(c.corporate_name.clone())
+            ),
+        };
+        plan.resignations.push(ctx.resignation("LLTM01", &name, officer_resignation::officer_resignation::Resignation::Member(
+            officer_resignation::Member { member: Some(resignation) }
+        )));
+    }
+}
+
+/// Works out the filings needed to bring the live officers in line with the requested rosters.
+///
+/// Appointments come first and resignations last, so the company is never left without its
+/// minimum number of officers part way through the plan.
+pub fn plan(
+    live: &company_data::CompanyDataResponse, request: &officer_reconciliation::ReconcileOfficersRequest,
+    date: prost_types::Timestamp,
+) -> Vec<officer_reconciliation::PlannedFiling> {
+    let ctx = Context {
+        form_submission: &request.form_submission,
+        date: Some(date),
+        consent_to_act: request.consent_to_act,
+    };
+    let mut plan = Plan::default();
+
+    if let Some(directors) = &request.directors {
+        plan_directors(&ctx, &live.directors, &directors.directors, &mut plan);
+    }
+    if let Some(secretaries) = &request.secretaries {
+        plan_secretaries(&ctx, &live.secretaries, &secretaries.secretaries, &mut plan);
+    }
+    if let Some(members) = &request.members {
+        plan_members(&ctx, &live.members, &members.members, &mut plan);
+    }
+
+    plan.appointments.into_iter()
+        .chain(plan.changes.into_iter())
+        .chain(plan.resignations.into_iter())
+        .collect()
+}
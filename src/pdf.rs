@@ -0,0 +1,154 @@
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference};
+
+const MARGIN: f64 = 15.0;
+const FONT_SIZE: f64 = 8.0;
+const HEADING_SIZE: f64 = 14.0;
+const LINE_HEIGHT: f64 = 4.0;
+/// Approximate width of a Helvetica character at `FONT_SIZE`, used to wrap text
+const CHAR_WIDTH: f64 = 1.5;
+const CELL_PADDING: f64 = 1.5;
+
+/// A simple flowing document of headings, paragraphs and tables, using the built in PDF fonts
+pub struct Document {
+    doc: PdfDocumentReference,
+    font: IndirectFontRef,
+    bold: IndirectFontRef,
+    width: f64,
+    height: f64,
+    layer: PdfLayerReference,
+    y: f64,
+}
+
+impl Document {
+    /// A4, in landscape if `landscape` is set
+    pub fn new(title: &str, landscape: bool) -> Result<Self, String> {
+        let (width, height) = if landscape {
+            (297.0, 210.0)
+        } else {
+            (210.0, 297.0)
+        };
+        let (doc, page, layer) = PdfDocument::new(title, Mm(width), Mm(height), "Page 1");
+        let font = doc.add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|err| format!("Unable to load font: {}", err))?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|err| format!("Unable to load font: {}", err))?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Document {
+            doc,
+            font,
+            bold,
+            width,
+            height,
+            layer,
+            y: height - MARGIN,
+        })
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(self.width), Mm(self.height), "Page");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = self.height - MARGIN;
+    }
+
+    fn ensure_space(&mut self, height: f64) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn text_width(&self) -> f64 {
+        self.width - 2.0 * MARGIN
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        self.ensure_space(LINE_HEIGHT * 3.0);
+        self.y -= LINE_HEIGHT * 1.5;
+        self.layer.use_text(text, HEADING_SIZE, Mm(MARGIN), Mm(self.y), &self.bold);
+        self.y -= LINE_HEIGHT * 1.5;
+    }
+
+    pub fn paragraph(&mut self, text: &str) {
+        let max_chars = (self.text_width() / CHAR_WIDTH) as usize;
+        for line in wrap(text, max_chars) {
+            self.ensure_space(LINE_HEIGHT);
+            self.y -= LINE_HEIGHT;
+            self.layer.use_text(line, FONT_SIZE, Mm(MARGIN), Mm(self.y), &self.font);
+        }
+        self.y -= LINE_HEIGHT / 2.0;
+    }
+
+    fn table_row(&mut self, cells: &[Vec<String>], column_width: f64, font: &IndirectFontRef) {
+        for (i, cell_lines) in cells.iter().enumerate() {
+            let x = MARGIN + i as f64 * column_width;
+            for (j, line) in cell_lines.iter().enumerate() {
+                self.layer.use_text(line.as_str(), FONT_SIZE, Mm(x), Mm(self.y - (j + 1) as f64 * LINE_HEIGHT), font);
+            }
+        }
+        self.y -= row_height(cells);
+    }
+
+    /// Columns are given equal widths, with the heading row repeated on each new page
+    pub fn table(&mut self, headings: &[&str], rows: &[Vec<String>]) {
+        if headings.is_empty() {
+            return;
+        }
+        let column_width = self.text_width() / headings.len() as f64;
+        let max_chars = ((column_width - CELL_PADDING) / CHAR_WIDTH).max(1.0) as usize;
+        let headings = headings.iter().map(|h| wrap(h, max_chars)).collect::<Vec<_>>();
+        let bold = self.bold.clone();
+        let font = self.font.clone();
+
+        self.ensure_space(row_height(&headings) + LINE_HEIGHT);
+        self.table_row(&headings, column_width, &bold);
+        for row in rows {
+            let cells = row.iter().map(|c| wrap(c, max_chars)).collect::<Vec<_>>();
+            if self.y - row_height(&cells) < MARGIN {
+                self.new_page();
+                self.table_row(&headings, column_width, &bold);
+            }
+            self.table_row(&cells, column_width, &font);
+        }
+        self.y -= LINE_HEIGHT;
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        let mut out = std::io::BufWriter::new(Vec::new());
+        if let Err(err) = self.doc.save(&mut out) {
+            return Err(format!("Unable to render PDF: {}", err));
+        }
+        out.into_inner().map_err(|err| format!("Unable to render PDF: {}", err))
+    }
+}
+
+fn row_height(cells: &[Vec<String>]) -> f64 {
+    cells.iter().map(|l| l.len()).max().unwrap_or(1).max(1) as f64 * LINE_HEIGHT + CELL_PADDING
+}
+
+/// Wraps text to lines of at most `max_chars`, breaking on whitespace where possible
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            while word.chars().count() > max_chars {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                let split = word.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(word.len());
+                lines.push(word[..split].to_string());
+                word = word[split..].to_string();
+            }
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+        lines.push(line);
+    }
+    lines
+}
@@ -141,9 +141,8 @@ pub enum Form {
     ChangeOfName(super::change_of_name::ChangeOfName),
     #[serde(rename="{http://xmlgw.companieshouse.gov.uk;http://xmlgw.companieshouse.gov.uk/v1-0/schema/forms/ChangeRegisteredOfficeAddress-v2-5.xsd}ChangeRegisteredOfficeAddress")]
     ChangeRegisteredOffice(super::change_registered_office::ChangeRegisteredOfficeAddress),
-    // This appears to only apply to the Companies Act 1985, won't implement further unless there's some need to
-    // #[serde(rename="{http://xmlgw.companieshouse.gov.uk;http://xmlgw.companieshouse.gov.uk/v1-0/schema/forms/IncreaseNominalCapital-v2-6.xsd}IncreaseNominalCapital")]
-    // IncreaseNominalCapital(super::increase_nominal_capital::IncreaseNominalCapital),
+    #[serde(rename="{http://xmlgw.companieshouse.gov.uk;http://xmlgw.companieshouse.gov.uk/v1-0/schema/forms/IncreaseNominalCapital-v2-6.xsd}IncreaseNominalCapital")]
+    IncreaseNominalCapital(super::increase_nominal_capital::IncreaseNominalCapital),
     #[serde(rename="{http://xmlgw.companieshouse.gov.uk;http://xmlgw.companieshouse.gov.uk/v1-0/schema/forms/MembersRegisterElectOrWithdraw-v1-0.xsd}MembersRegisterElectOrWithdraw")]
     MembersRegisterElectOrWithdraw(super::members_register::MembersRegisterElectOrWithdraw),
     #[serde(rename="{http://xmlgw.companieshouse.gov.uk;http://xmlgw.companieshouse.gov.uk/v1-0/schema/forms/MembersRegisterUpdate-v1-0.xsd}MembersRegisterUpdate")]
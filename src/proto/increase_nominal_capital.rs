@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 use chrono::prelude::*;
 
 #[derive(Debug, Serialize, Clone)]
@@ -0,0 +1,392 @@
+use super::ch_ewf_grpc;
+use super::company_history::person_name;
+use ch_ewf_grpc::{psc, psc_determination};
+use psc_determination::entity::Entity;
+
+const MAX_ENTITIES: usize = 250;
+
+/// Offsets into `CompanyNatureOfControls.NatureOfControl` and `LLPNatureOfControls.NatureOfControl`,
+/// which share a layout: each condition has a direct, trust and firm variant, and shares and votes
+/// each have three bands per variant.
+const SHARES_BASE: i32 = 0;
+const VOTES_BASE: i32 = 9;
+const APPOINT_BASE: i32 = 18;
+const INFLUENCE_BASE: i32 = 21;
+
+#[derive(Clone, Copy)]
+enum Via {
+    Direct = 0,
+    Trust = 1,
+    Firm = 2,
+}
+
+#[derive(Default, Clone)]
+struct Control {
+    shares: f64,
+    votes: f64,
+    appoint: bool,
+    influence: bool,
+    held_through: Vec<String>,
+}
+
+impl Control {
+    /// A majority stake in a legal entity means its interests are held through it
+    fn majority(&self) -> bool {
+        self.shares > 50.0 || self.votes > 50.0 || self.appoint
+    }
+
+    fn significant(&self) -> bool {
+        self.shares > 25.0 || self.votes > 25.0 || self.appoint || self.influence
+    }
+
+    fn add(&mut self, other: &Control) {
+        self.shares += other.shares;
+        self.votes += other.votes;
+        self.appoint |= other.appoint;
+        self.influence |= other.influence;
+        for h in &other.held_through {
+            if !self.held_through.contains(h) {
+                self.held_through.push(h.clone());
+            }
+        }
+    }
+}
+
+/// More than 25% up to 50%, more than 50% but less than 75%, and 75% or more
+fn band(percentage: f64) -> Option<i32> {
+    if percentage >= 75.0 {
+        Some(2)
+    } else if percentage > 50.0 {
+        Some(1)
+    } else if percentage > 25.0 {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+fn natures(control: &Control, via: Via, natures: &mut Vec<i32>) {
+    let via = via as i32;
+    if let Some(b) = band(control.shares) {
+        natures.push(SHARES_BASE + via * 3 + b);
+    }
+    if let Some(b) = band(control.votes) {
+        natures.push(VOTES_BASE + via * 3 + b);
+    }
+    if control.appoint {
+        natures.push(APPOINT_BASE + via);
+    }
+    // Significant influence is only a condition when none of the others are met
+    if control.influence && band(control.shares).is_none() && band(control.votes).is_none() && !control.appoint {
+        natures.push(INFLUENCE_BASE + via);
+    }
+}
+
+fn entity_name(entity: &psc_determination::Entity) -> String {
+    match &entity.entity {
+        Some(Entity::Individual(i)) => person_name(&i.person),
+        Some(Entity::Corporate(c)) => c.corporate_name.clone(),
+        Some(Entity::LegalPerson(l)) => l.name.clone(),
+        Some(Entity::Trust(t)) => t.name.clone(),
+        Some(Entity::Firm(f)) => f.name.clone(),
+        None => entity.id.clone(),
+    }
+}
+
+struct Graph<'a> {
+    entities: &'a [psc_determination::Entity],
+    interests: &'a [psc_determination::Interest],
+}
+
+impl<'a> Graph<'a> {
+    /// Whether a holder's interests in an entity pass through to the entity's own interests. Relevant legal
+    /// entities aren't looked through unless `through_rles` is set, as the people controlling them appear
+    /// on their own register instead.
+    fn looked_through(&self, entity: &psc_determination::Entity, through_rles: bool) -> bool {
+        match &entity.entity {
+            Some(Entity::Corporate(_)) => through_rles || !entity.relevant_legal_entity,
+            _ => false
+        }
+    }
+
+    fn direct(&self, holder: &str, target: &str) -> Control {
+        let mut control = Control::default();
+        for interest in self.interests.iter().filter(|i| i.holder_id == holder && i.target_id == target) {
+            control.add(&Control {
+                shares: interest.share_percentage,
+                votes: interest.voting_percentage,
+                appoint: interest.appoint_majority_of_board,
+                influence: interest.significant_influence,
+                held_through: vec![],
+            });
+        }
+        control
+    }
+
+    /// A holder's own interests in a target plus those of the entities it has a majority stake in
+    fn combined(&self, holder: &str, stakes: &[&psc_determination::Entity], target: &str) -> Control {
+        let mut control = self.direct(holder, target);
+        for stake in stakes.iter().filter(|s| s.id != target) {
+            let mut via = self.direct(&stake.id, target);
+            if via.shares > 0.0 || via.votes > 0.0 || via.appoint || via.influence {
+                via.held_through.push(entity_name(stake));
+                control.add(&via);
+            }
+        }
+        control
+    }
+
+    /// Entities a holder has a majority stake in, directly or through other entities it has a majority stake in
+    fn majority_stakes(&self, holder: &str, through_rles: bool) -> Vec<&'a psc_determination::Entity> {
+        let mut stakes: Vec<&psc_determination::Entity> = vec![];
+        loop {
+            let found = stakes.len();
+            for entity in self.entities {
+                if entity.id == holder || stakes.iter().any(|s| s.id == entity.id) || !self.looked_through(entity, through_rles) {
+                    continue;
+                }
+                if self.combined(holder, &stakes, &entity.id).majority() {
+                    stakes.push(entity);
+                }
+            }
+            if stakes.len() == found {
+                return stakes;
+            }
+        }
+    }
+
+    /// Combined direct and indirect control a holder has over a target, with an empty target
+    /// being the company itself
+    fn control(&self, holder: &str, target: &str, through_rles: bool) -> Control {
+        let stakes = self.majority_stakes(holder, through_rles);
+        self.combined(holder, &stakes, target)
+    }
+
+    /// Natures of control over the company a holder has through trusts and firms they control
+    fn arrangement_natures(&self, holder: &str, natures_out: &mut Vec<i32>, held_through: &mut Vec<String>) {
+        for entity in self.entities {
+            let via = match &entity.entity {
+                Some(Entity::Trust(_)) => Via::Trust,
+                Some(Entity::Firm(_)) => Via::Firm,
+                _ => continue
+            };
+            let over_arrangement = self.control(holder, &entity.id, false);
+            if !(over_arrangement.majority() || over_arrangement.influence) {
+                continue;
+            }
+            let arrangement = self.control(&entity.id, "", false);
+            let before = natures_out.len();
+            natures(&arrangement, via, natures_out);
+            if natures_out.len() > before {
+                held_through.push(entity_name(entity));
+            }
+        }
+    }
+}
+
+fn validate(msg: &psc_determination::PscDeterminationRequest) -> Result<(), tonic::Status> {
+    if msg.entities.len() > MAX_ENTITIES {
+        return Err(tonic::Status::invalid_argument("Too many entities"));
+    }
+    for (i, entity) in msg.entities.iter().enumerate() {
+        if entity.id.is_empty() {
+            return Err(tonic::Status::invalid_argument("Entity ID required"));
+        }
+        if msg.entities[..i].iter().any(|e| e.id == entity.id) {
+            return Err(tonic::Status::invalid_argument(format!("Duplicate entity ID {}", entity.id)));
+        }
+        if entity.entity.is_none() {
+            return Err(tonic::Status::invalid_argument(format!("Entity {} has no details", entity.id)));
+        }
+    }
+    for interest in &msg.interests {
+        if !msg.entities.iter().any(|e| e.id == interest.holder_id) {
+            return Err(tonic::Status::invalid_argument(format!("Unknown holder {}", interest.holder_id)));
+        }
+        if !interest.target_id.is_empty() && !msg.entities.iter().any(|e| e.id == interest.target_id) {
+            return Err(tonic::Status::invalid_argument(format!("Unknown target {}", interest.target_id)));
+        }
+        if interest.holder_id == interest.target_id {
+            return Err(tonic::Status::invalid_argument(format!("Entity {} can't hold an interest in itself", interest.holder_id)));
+        }
+        if !(0.0..=100.0).contains(&interest.share_percentage) || !(0.0..=100.0).contains(&interest.voting_percentage) {
+            return Err(tonic::Status::invalid_argument("Percentages must be between 0 and 100"));
+        }
+    }
+    Ok(())
+}
+
+/// Works out who is registrable as a PSC of the company, and with which natures of control
+pub fn determine(
+    msg: &psc_determination::PscDeterminationRequest
+) -> Result<psc_determination::PscDeterminationResponse, tonic::Status> {
+    validate(msg)?;
+    let graph = Graph {
+        entities: &msg.entities,
+        interests: &msg.interests,
+    };
+
+    let mut pscs = vec![];
+    let mut notes = vec![];
+    for entity in &msg.entities {
+        let notification = match &entity.entity {
+            Some(Entity::Individual(i)) => psc::notification::Psc::Individual(i.clone()),
+            Some(Entity::Corporate(c)) if entity.relevant_legal_entity => psc::notification::Psc::Corporate(c.clone()),
+            Some(Entity::LegalPerson(l)) => psc::notification::Psc::LegalPerson(l.clone()),
+            _ => continue
+        };
+
+        let control = graph.control(&entity.id, "", false);
+        let mut nature_of_controls = vec![];
+        natures(&control, Via::Direct, &mut nature_of_controls);
+        let mut held_through = control.held_through.clone();
+        graph.arrangement_natures(&entity.id, &mut nature_of_controls, &mut held_through);
+
+        if nature_of_controls.is_empty() {
+            if graph.control(&entity.id, "", true).significant() {
+                notes.push(format!(
+                    "{} only has significant control through relevant legal entities, so is on their registers instead",
+                    entity_name(entity)
+                ));
+            }
+            continue;
+        }
+
+        nature_of_controls.sort_unstable();
+        nature_of_controls.dedup();
+        pscs.push(psc_determination::DeterminedPsc {
+            entity_id: entity.id.clone(),
+            notification: Some(psc::Notification {
+                psc: Some(notification),
+            }),
+            nature_of_control: Some(psc::NatureOfControls {
+                nature_of_controls: Some(if msg.llp {
+                    psc::nature_of_controls::NatureOfControls::LlpNatureOfControls(psc::LlpNatureOfControls {
+                        nature_of_controls
+                    })
+                } else {
+                    psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(psc::CompanyNatureOfControls {
+                        nature_of_controls
+                    })
+                }),
+            }),
+            share_percentage: control.shares,
+            voting_percentage: control.votes,
+            held_through,
+        });
+    }
+
+    if pscs.is_empty() {
+        notes.push("No registrable PSCs, file a NoSignificantControl statement instead".to_string());
+    }
+
+    Ok(psc_determination::PscDeterminationResponse {
+        pscs,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(id: &str, entity: Entity, relevant_legal_entity: bool) -> psc_determination::Entity {
+        psc_determination::Entity {
+            id: id.to_string(),
+            entity: Some(entity),
+            relevant_legal_entity,
+        }
+    }
+
+    fn individual(id: &str) -> psc_determination::Entity {
+        entity(id, Entity::Individual(Default::default()), false)
+    }
+
+    fn corporate(id: &str, relevant_legal_entity: bool) -> psc_determination::Entity {
+        entity(id, Entity::Corporate(psc::CorporateEntity {
+            corporate_name: id.to_uppercase(),
+            ..Default::default()
+        }), relevant_legal_entity)
+    }
+
+    fn shares(holder_id: &str, target_id: &str, share_percentage: f64) -> psc_determination::Interest {
+        psc_determination::Interest {
+            holder_id: holder_id.to_string(),
+            target_id: target_id.to_string(),
+            share_percentage,
+            ..Default::default()
+        }
+    }
+
+    fn company_natures(psc: &psc_determination::DeterminedPsc) -> Vec<i32> {
+        match psc.nature_of_control.as_ref().and_then(|n| n.nature_of_controls.as_ref()) {
+            Some(psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(n)) => n.nature_of_controls.clone(),
+            _ => vec![]
+        }
+    }
+
+    #[test]
+    fn threshold_bands() {
+        assert_eq!(band(0.0), None);
+        assert_eq!(band(25.0), None);
+        assert_eq!(band(25.01), Some(0));
+        assert_eq!(band(50.0), Some(0));
+        assert_eq!(band(50.01), Some(1));
+        assert_eq!(band(74.99), Some(1));
+        assert_eq!(band(75.0), Some(2));
+        assert_eq!(band(100.0), Some(2));
+    }
+
+    #[test]
+    fn nature_offsets() {
+        let mut out = vec![];
+        natures(&Control { shares: 30.0, votes: 80.0, ..Default::default() }, Via::Direct, &mut out);
+        assert_eq!(out, vec![SHARES_BASE, VOTES_BASE + 2]);
+
+        let mut out = vec![];
+        natures(&Control { shares: 60.0, appoint: true, influence: true, ..Default::default() }, Via::Trust, &mut out);
+        assert_eq!(out, vec![SHARES_BASE + 3 + 1, APPOINT_BASE + 1]);
+
+        // Significant influence only counts when no other condition is met
+        let mut out = vec![];
+        natures(&Control { shares: 20.0, influence: true, ..Default::default() }, Via::Firm, &mut out);
+        assert_eq!(out, vec![INFLUENCE_BASE + 2]);
+    }
+
+    #[test]
+    fn direct_holdings() {
+        let res = determine(&psc_determination::PscDeterminationRequest {
+            llp: false,
+            entities: vec![individual("a"), individual("b")],
+            interests: vec![shares("a", "", 25.0), shares("b", "", 75.0)],
+        }).unwrap();
+        assert_eq!(res.pscs.len(), 1);
+        assert_eq!(res.pscs[0].entity_id, "b");
+        assert_eq!(company_natures(&res.pscs[0]), vec![SHARES_BASE + 2]);
+    }
+
+    #[test]
+    fn indirect_holdings_are_combined() {
+        let res = determine(&psc_determination::PscDeterminationRequest {
+            llp: false,
+            entities: vec![individual("a"), corporate("holdco", false)],
+            interests: vec![shares("a", "", 10.0), shares("a", "holdco", 60.0), shares("holdco", "", 20.0)],
+        }).unwrap();
+        assert_eq!(res.pscs.len(), 1);
+        assert_eq!(res.pscs[0].share_percentage, 30.0);
+        assert_eq!(res.pscs[0].held_through, vec!["HOLDCO"]);
+        assert_eq!(company_natures(&res.pscs[0]), vec![SHARES_BASE]);
+    }
+
+    #[test]
+    fn relevant_legal_entities_are_not_looked_through() {
+        let res = determine(&psc_determination::PscDeterminationRequest {
+            llp: false,
+            entities: vec![individual("a"), corporate("rle", true)],
+            interests: vec![shares("a", "rle", 100.0), shares("rle", "", 100.0)],
+        }).unwrap();
+        assert_eq!(res.pscs.len(), 1);
+        assert_eq!(res.pscs[0].entity_id, "rle");
+        assert_eq!(res.notes.len(), 1);
+    }
+}
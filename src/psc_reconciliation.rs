@@ -0,0 +1,361 @@
+use super::{ch_ewf_grpc, proto};
+use super::company_history::person_name;
+use super::grpc::proto_to_chrono;
+use chrono::Datelike;
+use ch_ewf_grpc::{company_data, psc, psc_cessation, psc_change_details, psc_notification, psc_reconciliation};
+use ch_ewf_grpc::{psc_statement_notification, psc_statement_withdrawal};
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq)]
+enum PSCKey {
+    Individual(String, Option<(i32, u32)>),
+    Corporate(String),
+    LegalPerson(String),
+}
+
+fn normalise(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// PSCs are identified on change and cessation forms by name and month and year of birth only
+fn partial_dob(date_of_birth: &Option<prost_types::Timestamp>) -> Option<(i32, u32)> {
+    proto_to_chrono(date_of_birth.clone()).map(|d| (d.year(), d.month()))
+}
+
+fn psc_key(notification: &psc::notification::Psc) -> PSCKey {
+    match notification {
+        psc::notification::Psc::Individual(i) => PSCKey::Individual(
+            normalise(&person_name(&i.person.as_ref().map(|p| ch_ewf_grpc::base_types::PersonName {
+                title: String::new(),
+                ..p.clone()
+            }))),
+            partial_dob(&i.date_of_birth)
+        ),
+        psc::notification::Psc::Corporate(c) => PSCKey::Corporate(normalise(&c.corporate_name)),
+        psc::notification::Psc::LegalPerson(l) => PSCKey::LegalPerson(normalise(&l.name)),
+    }
+}
+
+fn psc_name(notification: &psc::notification::Psc) -> String {
+    match notification {
+        psc::notification::Psc::Individual(i) => person_name(&i.person),
+        psc::notification::Psc::Corporate(c) => c.corporate_name.clone(),
+        psc::notification::Psc::LegalPerson(l) => l.name.clone(),
+    }
+}
+
+fn individual_identification(individual: &psc::Individual) -> psc::IndividualIdentification {
+    psc::IndividualIdentification {
+        name: individual.person.clone(),
+        partial_dob: partial_dob(&individual.date_of_birth).map(|(year, month)| ch_ewf_grpc::base_types::PartialDob {
+            month,
+            year: year as u64,
+        }),
+    }
+}
+
+fn changed_string(live: &str, desired: &str) -> Option<String> {
+    if !desired.is_empty() && normalise(live) != normalise(desired) {
+        Some(desired.to_string())
+    } else {
+        None
+    }
+}
+
+fn changed<T: PartialEq + Clone>(live: &Option<T>, desired: &Option<T>) -> Option<T> {
+    if desired.is_some() && desired != live {
+        desired.clone()
+    } else {
+        None
+    }
+}
+
+/// Natures of control are compared as sets, the order they're listed in doesn't matter
+fn changed_natures(live: &Option<psc::NatureOfControls>, desired: &Option<psc::NatureOfControls>) -> Option<psc::NatureOfControls> {
+    fn normalised(n: &Option<psc::NatureOfControls>) -> Option<psc::nature_of_controls::NatureOfControls> {
+        n.as_ref().and_then(|n| n.nature_of_controls.clone()).map(|n| match n {
+            psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(mut c) => {
+                c.nature_of_controls.sort_unstable();
+                c.nature_of_controls.dedup();
+                psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(c)
+            }
+            psc::nature_of_controls::NatureOfControls::LlpNatureOfControls(mut c) => {
+                c.nature_of_controls.sort_unstable();
+                c.nature_of_controls.dedup();
+                psc::nature_of_controls::NatureOfControls::LlpNatureOfControls(c)
+            }
+        })
+    }
+
+    if normalised(live) != normalised(desired) {
+        desired.clone()
+    } else {
+        None
+    }
+}
+
+struct Context<'a> {
+    form_submission: &'a Option<ch_ewf_grpc::form_submission::FormSubmission>,
+    date: Option<prost_types::Timestamp>,
+    register_entry_date: Option<prost_types::Timestamp>,
+    form_prefix: &'static str,
+}
+
+impl Context<'_> {
+    fn filing(&self, form: &str, description: String, filing: psc_reconciliation::planned_filing::Filing) -> psc_reconciliation::PlannedFiling {
+        psc_reconciliation::PlannedFiling {
+            form: format!("{}{}", self.form_prefix, form),
+            description,
+            filing: Some(filing),
+            submission: None,
+            error: String::new(),
+        }
+    }
+
+    fn change_details(&self, entity: psc_change_details::psc_change_details::Entity) -> psc_reconciliation::planned_filing::Filing {
+        psc_reconciliation::planned_filing::Filing::ChangeDetails(psc_change_details::PscChangeDetails {
+            form_submission: self.form_submission.clone(),
+            entity: Some(entity),
+            date_of_change: self.date.clone(),
+            register_entry_date: self.register_entry_date.clone(),
+        })
+    }
+}
+
+fn change_details(
+    ctx: &Context, live: &company_data::CompanyDataPscNotification, live_psc: &psc::notification::Psc,
+    desired: &psc_reconciliation::DesiredPsc, desired_psc: &psc::notification::Psc,
+) -> Option<psc_reconciliation::PlannedFiling> {
+    let new_nature_of_controls = changed_natures(&live.nature_of_controls, &desired.nature_of_controls);
+    let name = psc_name(live_psc);
+
+    match (live_psc, desired_psc) {
+        (psc::notification::Psc::Individual(l), psc::notification::Psc::Individual(d)) => {
+            let new_service_address = changed(&l.service_address, &d.service_address);
+            let new_residential_address = if l.residential_address.is_some() {
+                changed(&l.residential_address, &d.residential_address)
+            } else {
+                None
+            };
+            let new_nationality = changed_string(&l.nationality, &d.nationality);
+            let new_country_of_residence = changed_string(&l.country_of_residence, &d.country_of_residence);
+            if new_service_address.is_none() && new_residential_address.is_none() && new_nationality.is_none() &&
+                new_country_of_residence.is_none() && new_nature_of_controls.is_none() {
+                return None;
+            }
+            Some(ctx.filing("PSC04", format!("Change details of {}", name), ctx.change_details(
+                psc_change_details::psc_change_details::Entity::Individual(psc_change_details::Individual {
+                    identification: Some(individual_identification(l)),
+                    new_name: None,
+                    new_service_address,
+                    new_residential_address,
+                    new_nationality,
+                    new_country_of_residence,
+                    new_nature_of_controls,
+                })
+            )))
+        }
+        (psc::notification::Psc::Corporate(l), psc::notification::Psc::Corporate(d)) => {
+            let new_address = changed(&l.address, &d.address);
+            let new_corporate_identification = changed(&l.corporate_identification, &d.corporate_identification);
+            if new_address.is_none() && new_corporate_identification.is_none() && new_nature_of_controls.is_none() {
+                return None;
+            }
+            Some(ctx.filing("PSC05", format!("Change details of {}", name), ctx.change_details(
+                psc_change_details::psc_change_details::Entity::Corporate(psc_change_details::Corporate {
+                    corporate_name: l.corporate_name.clone(),
+                    new_corporate_name: None,
+                    new_address,
+                    new_corporate_identification,
+                    new_nature_of_controls,
+                })
+            )))
+        }
+        (psc::notification::Psc::LegalPerson(l), psc::notification::Psc::LegalPerson(d)) => {
+            let new_address = changed(&l.address, &d.address);
+            let new_legal_person_identification = changed(&l.legal_person_identification, &d.legal_person_identification);
+            if new_address.is_none() && new_legal_person_identification.is_none() && new_nature_of_controls.is_none() {
+                return None;
+            }
+            Some(ctx.filing("PSC06", format!("Change details of {}", name), ctx.change_details(
+                psc_change_details::psc_change_details::Entity::LegalPerson(psc_change_details::LegalPerson {
+                    legal_person_name: l.name.clone(),
+                    new_legal_person_name: None,
+                    new_address,
+                    new_legal_person_identification,
+                    new_nature_of_controls,
+                })
+            )))
+        }
+        _ => None
+    }
+}
+
+fn statement_description(statement: &psc::StatementNotification) -> String {
+    match &statement.notification {
+        Some(psc::statement_notification::Notification::CompanyLevelStatement(s)) =>
+            psc::CompanyLevelStatement::from_i32(*s).map(|s| format!("{:?}", s)).unwrap_or_default(),
+        Some(psc::statement_notification::Notification::PscLevelStatement(s)) =>
+            psc::PscLevelStatement::from_i32(*s).map(|s| format!("{:?}", s)).unwrap_or_default(),
+        Some(psc::statement_notification::Notification::LinkedStatement(l)) => match &l.psc {
+            Some(psc::linked_statement::Psc::Individual(i)) => format!("linked statement for {}", person_name(&i.name)),
+            Some(psc::linked_statement::Psc::CorporateName(n)) => format!("linked statement for {}", n),
+            Some(psc::linked_statement::Psc::LegalPersonName(n)) => format!("linked statement for {}", n),
+            Some(psc::linked_statement::Psc::SuperSecureIndividual(_)) | None => "linked statement".to_string(),
+        },
+        None => String::new()
+    }
+}
+
+/// Works out the filings needed to bring the live PSC register in line with the requested PSCs and statements.
+///
+/// New PSCs and statements are filed before old ones are ceased or withdrawn, so the register
+/// is never left empty part way through the plan. Super secure individuals can't be identified
+/// from company data, so they are left untouched.
+pub fn plan(
+    live: &company_data::CompanyDataResponse, request: &psc_reconciliation::PscReconciliationRequest,
+    date: prost_types::Timestamp,
+) -> Result<Vec<psc_reconciliation::PlannedFiling>, tonic::Status> {
+    let llp = live.category == company_data::CompanyCategory::Llp as i32;
+    let ctx = Context {
+        form_submission: &request.form_submission,
+        register_entry_date: Some(request.register_entry_date.clone().unwrap_or_else(|| date.clone())),
+        date: Some(date),
+        form_prefix: if llp { "LL" } else { "" },
+    };
+
+    let mut live_pscs = vec![];
+    let mut live_statements = vec![];
+    match &live.pscs {
+        Some(company_data::company_data_response::Pscs::PscStatement(s)) => {
+            live_statements.push(psc::StatementNotification {
+                notification: Some(psc::statement_notification::Notification::CompanyLevelStatement(*s))
+            });
+        }
+        Some(company_data::company_data_response::Pscs::CompanyPscs(p)) => for psc in &p.pscs {
+            match &psc.psc {
+                Some(company_data::company_psc::Psc::StatementNotification(s)) => {
+                    live_statements.push(psc::StatementNotification {
+                        notification: Some(psc::statement_notification::Notification::PscLevelStatement(*s))
+                    });
+                }
+                Some(company_data::company_psc::Psc::LinkedStatementNotification(l)) => {
+                    live_statements.push(psc::StatementNotification {
+                        notification: Some(psc::statement_notification::Notification::LinkedStatement(l.clone()))
+                    });
+                }
+                Some(company_data::company_psc::Psc::Notification(n)) if n.cessation_date.is_none() => {
+                    if let Some(p) = n.notification.as_ref().and_then(|n| n.psc.as_ref()) {
+                        live_pscs.push((psc_key(p), n, p));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None => {}
+    }
+
+    let mut notifications = vec![];
+    let mut changes = vec![];
+    for desired in &request.pscs {
+        let notification = match &desired.notification {
+            Some(n) => n,
+            None => return Err(tonic::Status::invalid_argument("Notification required"))
+        };
+        let natures = match &desired.nature_of_controls {
+            Some(n) => n,
+            None => return Err(tonic::Status::invalid_argument("Nature of controls required"))
+        };
+        match (&natures.nature_of_controls, llp) {
+            (Some(psc::nature_of_controls::NatureOfControls::CompanyNatureOfControls(_)), true) =>
+                return Err(tonic::Status::invalid_argument("LLP PSCs require LLP natures of control")),
+            (Some(psc::nature_of_controls::NatureOfControls::LlpNatureOfControls(_)), false) =>
+                return Err(tonic::Status::invalid_argument("Company PSCs require company natures of control")),
+            _ => {}
+        }
+        proto::psc::PSCNotificationType::<proto::base_types::PersonType2>::try_from(notification.clone())?;
+        proto::psc::PSCNatureOfControls::try_from(natures.clone())?;
+
+        let desired_psc = match &notification.psc {
+            Some(p) => p,
+            None => return Err(tonic::Status::invalid_argument("PSC required"))
+        };
+        let key = psc_key(desired_psc);
+
+        match live_pscs.iter().position(|(k, _, _)| k == &key) {
+            Some(i) => {
+                let (_, live, live_psc) = live_pscs.remove(i);
+                changes.extend(change_details(&ctx, live, live_psc, desired, desired_psc));
+            }
+            None => {
+                let form = match desired_psc {
+                    psc::notification::Psc::Individual(_) => "PSC01",
+                    psc::notification::Psc::Corporate(_) => "PSC02",
+                    psc::notification::Psc::LegalPerson(_) => "PSC03",
+                };
+                notifications.push(ctx.filing(form, format!("Notify {}", psc_name(desired_psc)),
+                    psc_reconciliation::planned_filing::Filing::Notification(psc_notification::PscNotification {
+                        form_submission: ctx.form_submission.clone(),
+                        notification: Some(notification.clone()),
+                        nature_of_control: Some(natures.clone()),
+                        notification_date: ctx.date.clone(),
+                        register_entry_date: ctx.register_entry_date.clone(),
+                    })
+                ));
+            }
+        }
+    }
+
+    let mut statement_notifications = vec![];
+    for statement in &request.statements {
+        proto::psc::PSCStatementNotificationType::try_from(statement.clone())?;
+
+        match live_statements.iter().position(|s| s == statement) {
+            Some(i) => {
+                live_statements.remove(i);
+            }
+            None => statement_notifications.push(ctx.filing("PSC08", format!("Notify statement {}", statement_description(statement)),
+                psc_reconciliation::planned_filing::Filing::StatementNotification(psc_statement_notification::PscStatementNotification {
+                    form_submission: ctx.form_submission.clone(),
+                    statement_notification: Some(statement.clone()),
+                    register_entry_date: ctx.register_entry_date.clone(),
+                })
+            ))
+        }
+    }
+
+    let cessations = live_pscs.into_iter().map(|(_, _, live_psc)| ctx.filing("PSC07", format!("Cease {}", psc_name(live_psc)),
+        psc_reconciliation::planned_filing::Filing::Cessation(psc_cessation::PscCessation {
+            form_submission: ctx.form_submission.clone(),
+            entity: Some(match live_psc {
+                psc::notification::Psc::Individual(i) => psc_cessation::psc_cessation::Entity::Individual(individual_identification(i)),
+                psc::notification::Psc::Corporate(c) => psc_cessation::psc_cessation::Entity::Corporate(c.corporate_name.clone()),
+                psc::notification::Psc::LegalPerson(l) => psc_cessation::psc_cessation::Entity::LegalPerson(l.name.clone()),
+            }),
+            cessation_date: ctx.date.clone(),
+            register_entry_date: ctx.register_entry_date.clone(),
+        })
+    ));
+
+    let withdrawals = live_statements.into_iter().map(|statement| ctx.filing("PSC09", format!("Withdraw statement {}", statement_description(&statement)),
+        psc_reconciliation::planned_filing::Filing::StatementWithdrawal(psc_statement_withdrawal::PscStatementWithdrawal {
+            form_submission: ctx.form_submission.clone(),
+            restrictions_notice_withdrawal_reason: match &statement.notification {
+                Some(psc::statement_notification::Notification::PscLevelStatement(s))
+                if *s == psc::PscLevelStatement::RestrictionNoticeIssued as i32 =>
+                    psc_statement_withdrawal::RestrictionsNoticeWithdrawalReason::WithdrawnByCompany.into(),
+                _ => psc_statement_withdrawal::RestrictionsNoticeWithdrawalReason::None.into(),
+            },
+            statement_notification: Some(statement),
+            withdrawal_date: ctx.date.clone(),
+            register_entry_date: ctx.register_entry_date.clone(),
+        })
+    ));
+
+    Ok(notifications.into_iter()
+        .chain(statement_notifications.into_iter())
+        .chain(changes.into_iter())
+        .chain(cessations)
+        .chain(withdrawals)
+        .collect())
+}
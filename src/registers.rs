@@ -0,0 +1,1026 @@
+use super::{ch_ewf_grpc, models, schema};
+use super::company_history::{nature_of_controls, person_name};
+use super::grpc::{chrono_to_proto, proto_to_chrono};
+use chrono::Datelike;
+use diesel::prelude::*;
+
+/// A change to a register, which takes effect once the submission that made it is accepted
+pub struct Entry {
+    pub register: schema::RegisterType,
+    pub entry_key: String,
+    pub entry_date: chrono::NaiveDate,
+    pub removal: bool,
+    pub details: Vec<(&'static str, String)>,
+    /// Details of individuals that identify them, which are kept encrypted at rest
+    pub personal: Vec<(&'static str, String)>,
+}
+
+fn details_object(details: Vec<(&'static str, String)>) -> serde_json::Map<String, serde_json::Value> {
+    details.into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| (k.to_string(), serde_json::Value::String(v)))
+        .collect()
+}
+
+impl Entry {
+    /// The personal details as stored, before encryption
+    pub fn personal_details(&self) -> Option<Vec<u8>> {
+        let personal = details_object(self.personal.clone());
+        if personal.is_empty() {
+            None
+        } else {
+            serde_json::to_vec(&personal).ok()
+        }
+    }
+
+    pub fn into_model(
+        self, submission_id: uuid::Uuid, company_number: String, personal_details: Option<(Vec<u8>, bool)>,
+    ) -> models::RegisterEntry {
+        let (personal_details, personal_details_encrypted) = match personal_details {
+            Some((d, e)) => (Some(d), e),
+            None => (None, false)
+        };
+        models::RegisterEntry {
+            id: uuid::Uuid::new_v4(),
+            submission_id,
+            company_number,
+            register: self.register,
+            entry_key: self.entry_key,
+            entry_date: self.entry_date,
+            removal: self.removal,
+            details: serde_json::Value::Object(details_object(self.details)),
+            personal_details,
+            personal_details_encrypted,
+        }
+    }
+}
+
+/// Keys and headings of the details kept in each register
+pub fn columns(register: schema::RegisterType) -> &'static [(&'static str, &'static str)] {
+    match register {
+        schema::RegisterType::Directors => &[
+            ("name", "Name"),
+            ("former_names", "Former names"),
+            ("date_of_birth", "Date of birth"),
+            ("nationality", "Nationality"),
+            ("country_of_residence", "Country of residence"),
+            ("occupation", "Occupation"),
+            ("service_address", "Service address"),
+            ("identification", "Registration details"),
+        ],
+        schema::RegisterType::Secretaries => &[
+            ("name", "Name"),
+            ("former_names", "Former names"),
+            ("service_address", "Service address"),
+            ("identification", "Registration details"),
+        ],
+        schema::RegisterType::Members => &[
+            ("name", "Name"),
+            ("address", "Address"),
+            ("designated", "Designated member"),
+            ("holding", "Shares or stock held"),
+        ],
+        schema::RegisterType::Pscs => &[
+            ("name", "Name"),
+            ("kind", "Kind"),
+            ("date_of_birth", "Date of birth"),
+            ("nationality", "Nationality"),
+            ("country_of_residence", "Country of residence"),
+            ("address", "Service address"),
+            ("identification", "Registration details"),
+            ("nature_of_control", "Nature of control"),
+        ],
+        schema::RegisterType::Allotments => &[
+            ("allottee", "Allottee"),
+            ("share_class", "Class"),
+            ("num_shares", "Number"),
+            ("currency", "Currency"),
+            ("nominal_value", "Nominal value per share"),
+            ("amount_paid", "Paid per share"),
+            ("amount_unpaid", "Unpaid per share"),
+            ("consideration", "Consideration"),
+        ],
+        schema::RegisterType::Transfers => &[
+            ("member", "Member"),
+            ("share_class", "Class"),
+            ("num_shares", "Number"),
+            ("share_reference", "Share reference"),
+        ],
+        schema::RegisterType::Charges => &[
+            ("charge_code", "Charge code"),
+            ("persons_entitled", "Persons entitled"),
+            ("description", "Description"),
+            ("fixed_charge", "Fixed charge"),
+            ("floating_charge", "Floating charge"),
+            ("negative_pledge", "Negative pledge"),
+        ],
+    }
+}
+
+/// Headings for the date an entry was made and, for registers entries can leave, the date it ceased
+pub fn date_headings(register: schema::RegisterType) -> (&'static str, Option<&'static str>) {
+    match register {
+        schema::RegisterType::Directors | schema::RegisterType::Secretaries => ("Appointed", Some("Resigned")),
+        schema::RegisterType::Members => ("Registered", Some("Ceased")),
+        schema::RegisterType::Pscs => ("Notified", Some("Ceased")),
+        schema::RegisterType::Allotments => ("Allotted", None),
+        schema::RegisterType::Transfers => ("Transferred", None),
+        schema::RegisterType::Charges => ("Created", None),
+    }
+}
+
+pub fn register_name(register: schema::RegisterType) -> &'static str {
+    match register {
+        schema::RegisterType::Directors => "Register of directors",
+        schema::RegisterType::Secretaries => "Register of secretaries",
+        schema::RegisterType::Members => "Register of members",
+        schema::RegisterType::Pscs => "Register of people with significant control",
+        schema::RegisterType::Allotments => "Register of allotments",
+        schema::RegisterType::Transfers => "Register of transfers",
+        schema::RegisterType::Charges => "Register of charges",
+    }
+}
+
+impl From<ch_ewf_grpc::registers::RegisterType> for schema::RegisterType {
+    fn from(value: ch_ewf_grpc::registers::RegisterType) -> Self {
+        match value {
+            ch_ewf_grpc::registers::RegisterType::Directors => schema::RegisterType::Directors,
+            ch_ewf_grpc::registers::RegisterType::Secretaries => schema::RegisterType::Secretaries,
+            ch_ewf_grpc::registers::RegisterType::Members => schema::RegisterType::Members,
+            ch_ewf_grpc::registers::RegisterType::PersonsWithSignificantControl => schema::RegisterType::Pscs,
+            ch_ewf_grpc::registers::RegisterType::Allotments => schema::RegisterType::Allotments,
+            ch_ewf_grpc::registers::RegisterType::Transfers => schema::RegisterType::Transfers,
+            ch_ewf_grpc::registers::RegisterType::Charges => schema::RegisterType::Charges,
+        }
+    }
+}
+
+fn proto_date(date: &Option<prost_types::Timestamp>) -> Option<chrono::NaiveDate> {
+    proto_to_chrono(date.clone()).map(|d| d.naive_utc().date())
+}
+
+fn name_key(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn person_key(name: &str, birth_month: Option<(i32, u32)>) -> String {
+    match birth_month {
+        Some((year, month)) => format!("{}|{:04}-{:02}", name_key(name), year, month),
+        None => name_key(name)
+    }
+}
+
+fn birth_month(date: &Option<prost_types::Timestamp>) -> Option<(i32, u32)> {
+    proto_date(date).map(|d| (d.year(), d.month()))
+}
+
+fn format_date(date: &Option<prost_types::Timestamp>) -> String {
+    proto_date(date).map(|d| d.format("%d/%m/%Y").to_string()).unwrap_or_default()
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "Yes" } else { "No" }.to_string()
+}
+
+fn join_parts<'a>(parts: impl IntoIterator<Item=&'a str>) -> String {
+    parts.into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn base_address(address: &Option<ch_ewf_grpc::base_types::BaseAddress>) -> String {
+    match address {
+        Some(a) => join_parts(vec![
+            a.premise.as_str(), a.street.as_str(), a.thoroughfare.as_str(), a.post_town.as_str(),
+            a.county.as_str(), a.postcode.as_str(), a.country.as_str(),
+        ]),
+        None => String::new()
+    }
+}
+
+fn company_address(address: &Option<ch_ewf_grpc::base_types::CompanyAddress>) -> String {
+    match address {
+        Some(a) => {
+            let base = base_address(&a.base_address);
+            join_parts(vec![a.care_of_name.as_str(), a.po_box.as_str(), base.as_str()])
+        }
+        None => String::new()
+    }
+}
+
+fn service_address(address: &Option<ch_ewf_grpc::base_types::ServiceAddress>) -> String {
+    match address.as_ref().and_then(|a| a.value.as_ref()) {
+        Some(ch_ewf_grpc::base_types::service_address::Value::SameAsRegisteredOffice(true)) => "Registered office".to_string(),
+        Some(ch_ewf_grpc::base_types::service_address::Value::Address(a)) => company_address(&Some(a.clone())),
+        _ => String::new()
+    }
+}
+
+fn former_names(names: &[ch_ewf_grpc::base_types::PreviousName]) -> String {
+    names.iter()
+        .map(|n| format!("{} {}", n.forename, n.surname))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn corporate_officer(officer: &Option<ch_ewf_grpc::base_types::CorporateOfficer>) -> (String, String, String) {
+    let officer = match officer {
+        Some(o) => o,
+        None => return Default::default()
+    };
+    let identification = match officer.company_identification.as_ref().and_then(|i| i.company_identification.as_ref()) {
+        Some(ch_ewf_grpc::base_types::company_identification::CompanyIdentification::UkRegistrationNumber(n)) =>
+            format!("Registered in the UK, number {}", n),
+        Some(ch_ewf_grpc::base_types::company_identification::CompanyIdentification::NonUk(n)) => join_parts(vec![
+            n.legal_form.as_str(), n.governing_law.as_str(), n.place_registered.as_str(), n.registration_number.as_str(),
+        ]),
+        None => String::new()
+    };
+    (officer.corporate_name.clone(), company_address(&officer.address), identification)
+}
+
+fn corporate_officer_entry(
+    register: schema::RegisterType, date: chrono::NaiveDate,
+    corporate: &ch_ewf_grpc::officer_appointment::CorporateOfficerAppointment,
+) -> Entry {
+    let (name, address, identification) = corporate_officer(&corporate.corporate);
+    Entry {
+        register,
+        entry_key: name_key(&name),
+        entry_date: date,
+        removal: false,
+        details: vec![
+            ("name", name),
+            ("service_address", address),
+            ("identification", identification),
+        ],
+        personal: vec![],
+    }
+}
+
+pub fn officer_appointment(msg: &ch_ewf_grpc::officer_appointment::OfficerAppointment) -> Option<Entry> {
+    use ch_ewf_grpc::officer_appointment::{director, member, officer_appointment::Appointment, secretary};
+
+    let date = proto_date(&msg.appointment_date)?;
+    Some(match msg.appointment.as_ref()? {
+        Appointment::Director(d) => match d.director.as_ref()? {
+            director::Director::Person(p) => {
+                let name = person_name(&p.person);
+                Entry {
+                    register: schema::RegisterType::Directors,
+                    entry_key: person_key(&name, birth_month(&p.date_of_birth)),
+                    entry_date: date,
+                    removal: false,
+                    details: vec![
+                        ("name", name),
+                        ("former_names", former_names(&p.previous_names)),
+                        ("occupation", p.occupation.clone()),
+                    ],
+                    personal: vec![
+                        ("date_of_birth", format_date(&p.date_of_birth)),
+                        ("nationality", p.nationality.clone()),
+                        ("country_of_residence", p.country_of_residence.clone()),
+                        ("service_address", service_address(&p.service_address)),
+                    ],
+                }
+            }
+            director::Director::Corporate(c) => corporate_officer_entry(schema::RegisterType::Directors, date, c),
+        },
+        Appointment::Secretary(s) => match s.secretary.as_ref()? {
+            secretary::Secretary::Person(p) => {
+                let name = person_name(&p.person);
+                Entry {
+                    register: schema::RegisterType::Secretaries,
+                    entry_key: name_key(&name),
+                    entry_date: date,
+                    removal: false,
+                    details: vec![
+                        ("name", name),
+                        ("former_names", former_names(&p.previous_names)),
+                    ],
+                    personal: vec![
+                        ("service_address", service_address(&p.service_address)),
+                    ],
+                }
+            }
+            secretary::Secretary::Corporate(c) => corporate_officer_entry(schema::RegisterType::Secretaries, date, c),
+        },
+        Appointment::Member(m) => {
+            let mut entry = match m.member.as_ref()? {
+                member::Member::Person(p) => {
+                    let name = person_name(&p.person);
+                    Entry {
+                        register: schema::RegisterType::Members,
+                        entry_key: person_key(&name, birth_month(&p.date_of_birth)),
+                        entry_date: date,
+                        removal: false,
+                        details: vec![
+                            ("name", name),
+                        ],
+                        personal: vec![
+                            ("address", service_address(&p.service_address)),
+                        ],
+                    }
+                }
+                member::Member::Corporate(c) => {
+                    let (name, address, _) = corporate_officer(&c.corporate);
+                    Entry {
+                        register: schema::RegisterType::Members,
+                        entry_key: name_key(&name),
+                        entry_date: date,
+                        removal: false,
+                        details: vec![
+                            ("name", name),
+                            ("address", address),
+                        ],
+                        personal: vec![],
+                    }
+                }
+            };
+            entry.details.push(("designated", yes_no(m.designated)));
+            entry
+        }
+    })
+}
+
+pub fn officer_resignation(msg: &ch_ewf_grpc::officer_resignation::OfficerResignation) -> Option<Entry> {
+    use ch_ewf_grpc::officer_resignation::{director, member, officer_resignation::Resignation, secretary};
+
+    let date = proto_date(&msg.resignation_date)?;
+    let (register, entry_key) = match msg.resignation.as_ref()? {
+        Resignation::Director(d) => (schema::RegisterType::Directors, match d.director.as_ref()? {
+            director::Director::Person(p) => person_key(&person_name(&p.person), birth_month(&p.date_of_birth)),
+            director::Director::Corporate(c) => name_key(c),
+        }),
+        Resignation::Secretary(s) => (schema::RegisterType::Secretaries, match s.secretary.as_ref()? {
+            secretary::Secretary::Person(p) => name_key(&person_name(&Some(p.clone()))),
+            secretary::Secretary::Corporate(c) => name_key(c),
+        }),
+        Resignation::Member(m) => (schema::RegisterType::Members, match m.member.as_ref()? {
+            member::Member::Person(p) => person_key(&person_name(&p.person), birth_month(&p.date_of_birth)),
+            member::Member::Corporate(c) => name_key(c),
+        }),
+    };
+
+    Some(Entry {
+        register,
+        entry_key,
+        entry_date: date,
+        removal: true,
+        details: vec![],
+        personal: vec![],
+    })
+}
+
+pub fn psc_notification(msg: &ch_ewf_grpc::psc_notification::PscNotification) -> Option<Entry> {
+    use ch_ewf_grpc::psc::notification::Psc;
+
+    let date = proto_date(&msg.notification_date)?;
+    let nature_of_control = nature_of_controls(&msg.nature_of_control).join(", ");
+    let (entry_key, details, personal) = match msg.notification.as_ref()?.psc.as_ref()? {
+        Psc::Individual(i) => {
+            let name = person_name(&i.person);
+            (person_key(&name, birth_month(&i.date_of_birth)), vec![
+                ("name", name),
+                ("kind", "Individual".to_string()),
+            ], vec![
+                ("date_of_birth", format_date(&i.date_of_birth)),
+                ("nationality", i.nationality.clone()),
+                ("country_of_residence", i.country_of_residence.clone()),
+                ("address", service_address(&i.service_address)),
+            ])
+        }
+        Psc::Corporate(c) => {
+            let identification = c.corporate_identification.as_ref().map(|i| join_parts(vec![
+                i.legal_form.as_str(), i.law_governed.as_str(), i.place_registered.as_str(),
+                i.country_or_state.as_str(), i.registration_number.as_str(),
+            ])).unwrap_or_default();
+            (name_key(&c.corporate_name), vec![
+                ("name", c.corporate_name.clone()),
+                ("kind", "Relevant legal entity".to_string()),
+                ("address", company_address(&c.address)),
+                ("identification", identification),
+            ], vec![])
+        }
+        Psc::LegalPerson(l) => {
+            let identification = l.legal_person_identification.as_ref().map(|i| join_parts(vec![
+                i.legal_form.as_str(), i.law_governed.as_str(),
+            ])).unwrap_or_default();
+            (name_key(&l.name), vec![
+                ("name", l.name.clone()),
+                ("kind", "Other registrable person".to_string()),
+                ("address", company_address(&l.address)),
+                ("identification", identification),
+            ], vec![])
+        }
+    };
+
+    let mut details = details;
+    details.push(("nature_of_control", nature_of_control));
+    Some(Entry {
+        register: schema::RegisterType::Pscs,
+        entry_key,
+        entry_date: date,
+        removal: false,
+        details,
+        personal,
+    })
+}
+
+pub fn psc_cessation(msg: &ch_ewf_grpc::psc_cessation::PscCessation) -> Option<Entry> {
+    use ch_ewf_grpc::psc_cessation::psc_cessation::Entity;
+
+    let date = proto_date(&msg.cessation_date)?;
+    let entry_key = match msg.entity.as_ref()? {
+        Entity::Individual(i) => person_key(
+            &person_name(&i.name),
+            i.partial_dob.as_ref().map(|d| (d.year as i32, d.month)),
+        ),
+        Entity::Corporate(c) => name_key(c),
+        Entity::LegalPerson(l) => name_key(l),
+    };
+
+    Some(Entry {
+        register: schema::RegisterType::Pscs,
+        entry_key,
+        entry_date: date,
+        removal: true,
+        details: vec![],
+        personal: vec![],
+    })
+}
+
+pub fn return_of_allotment_shares(msg: &ch_ewf_grpc::return_allotment_shares::ReturnOfAllotmentShares) -> Vec<Entry> {
+    let date = match proto_date(&msg.end_period).or_else(|| proto_date(&msg.start_period)) {
+        Some(d) => d,
+        None => return vec![]
+    };
+    msg.allotments.iter().map(|a| Entry {
+        register: schema::RegisterType::Allotments,
+        entry_key: name_key(&a.share_class),
+        entry_date: date,
+        removal: false,
+        details: vec![
+            ("share_class", a.share_class.clone()),
+            ("num_shares", a.num_shares.to_string()),
+            ("currency", a.share_currency.clone()),
+            ("nominal_value", a.share_value.to_string()),
+            ("amount_paid", a.amount_paid_due_per_share.to_string()),
+            ("amount_unpaid", a.amount_unpaid_per_share.to_string()),
+            ("consideration", a.consideration.clone()),
+        ],
+        personal: vec![],
+    }).collect()
+}
+
+/// The first officers, members and PSCs of a newly incorporated company, entered on its incorporation date
+pub fn company_incorporation(msg: &ch_ewf_grpc::company_incorporation::CompanyIncorporation, date: chrono::NaiveDate) -> Vec<Entry> {
+    use ch_ewf_grpc::company_incorporation::{appointment, company_incorporation::Psc};
+    use ch_ewf_grpc::officer_appointment::officer_appointment::Appointment;
+
+    let proto_date = chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(date.and_hms(0, 0, 0), chrono::Utc)));
+    let mut entries: Vec<Entry> = msg.appointments.iter().filter_map(|a| officer_appointment(
+        &ch_ewf_grpc::officer_appointment::OfficerAppointment {
+            form_submission: None,
+            appointment_date: proto_date.clone(),
+            consent_to_act: a.consent_to_act,
+            appointment: Some(match a.appointment.clone()? {
+                appointment::Appointment::Director(d) => Appointment::Director(d),
+                appointment::Appointment::Secretary(s) => Appointment::Secretary(s),
+                appointment::Appointment::Member(m) => Appointment::Member(m),
+            }),
+        }
+    )).collect();
+
+    // Subscribers and guarantors are the first members
+    let member = |person: &Option<ch_ewf_grpc::company_incorporation::Person>, holding: String| {
+        let person = person.as_ref()?;
+        let name = subscriber_name(person);
+        if name.is_empty() {
+            return None;
+        }
+        Some(Entry {
+            register: schema::RegisterType::Members,
+            entry_key: name_key(&name),
+            entry_date: date,
+            removal: false,
+            details: vec![
+                ("name", name),
+                ("address", base_address(&person.address)),
+                ("holding", holding),
+            ],
+            personal: vec![],
+        })
+    };
+    entries.extend(msg.subscribers.iter().filter_map(|s| member(&s.person, subscriber_holding(&s.allotments))));
+    entries.extend(msg.guarantors.iter().filter_map(|g| member(
+        &g.person, g.person.as_ref().map(|p| p.member_class.clone()).unwrap_or_default(),
+    )));
+
+    if let Some(Psc::Pscs(pscs)) = &msg.psc {
+        entries.extend(pscs.pscs.iter().filter_map(|p| psc_notification(
+            &ch_ewf_grpc::psc_notification::PscNotification {
+                form_submission: None,
+                notification: p.notification.clone(),
+                nature_of_control: p.nature_of_control.clone(),
+                notification_date: proto_date.clone(),
+                register_entry_date: proto_date.clone(),
+            }
+        )));
+    }
+
+    entries
+}
+
+fn subscriber_name(person: &ch_ewf_grpc::company_incorporation::Person) -> String {
+    match &person.name {
+        Some(ch_ewf_grpc::company_incorporation::person::Name::Person(p)) => format!("{} {}", p.forename, p.surname),
+        Some(ch_ewf_grpc::company_incorporation::person::Name::Corporate(c)) => c.corporate_name.clone(),
+        None => String::new()
+    }
+}
+
+/// Shares taken by a subscriber, described as for members registered later
+fn subscriber_holding(allotments: &[ch_ewf_grpc::company_incorporation::Allotment]) -> String {
+    allotments.iter().map(|a| format!(
+        "{} {} (paid up {})",
+        a.num_shares, a.share_class, (a.num_shares * a.amount_paid_due_per_share * 1_000_000.0).round() / 1_000_000.0
+    )).collect::<Vec<_>>().join("; ")
+}
+
+fn member_name(names: &[ch_ewf_grpc::members_data::MemberName]) -> String {
+    names.iter().filter_map(|n| match n.name.as_ref()? {
+        ch_ewf_grpc::members_data::member_name::Name::IndividualName(p) => Some(format!("{} {}", p.forename, p.surname)),
+        ch_ewf_grpc::members_data::member_name::Name::CorporateName(c) => Some(c.clone()),
+    }).collect::<Vec<_>>().join(" and ")
+}
+
+fn holding(held: &[ch_ewf_grpc::members_data::SharesOrStockHeld]) -> String {
+    held.iter().filter_map(|h| match h.shares_or_stock.as_ref()? {
+        ch_ewf_grpc::members_data::shares_or_stock_held::SharesOrStock::Shares(s) => s.share.as_ref().map(|share| format!(
+            "{} {} (paid up {})", share.num_shares, share.share_class, s.amount_paid_up
+        )),
+        ch_ewf_grpc::members_data::shares_or_stock_held::SharesOrStock::Stock(s) => Some(format!(
+            "{} {} {} stock", s.currency, s.amount_held, s.stock_class
+        )),
+    }).collect::<Vec<_>>().join("; ")
+}
+
+fn ceased_member(
+    name: &str, ceased: &ch_ewf_grpc::members_register_update::CeasedToBeMember, today: chrono::NaiveDate,
+) -> Entry {
+    let date = match &ceased.status {
+        Some(ch_ewf_grpc::members_register_update::ceased_to_be_member::Status::DateCeased(d)) =>
+            proto_date(&Some(d.clone())).unwrap_or(today),
+        _ => today
+    };
+    Entry {
+        register: schema::RegisterType::Members,
+        entry_key: name_key(name),
+        entry_date: date,
+        removal: true,
+        details: vec![],
+        personal: vec![],
+    }
+}
+
+pub fn members_register_update(msg: &ch_ewf_grpc::members_register_update::MembersRegisterUpdate) -> Vec<Entry> {
+    use ch_ewf_grpc::members_register_update::{members_register_update::Members, member_with_shares, member_without_shares, new_or_existing_member};
+
+    let today = chrono::Utc::today().naive_utc();
+    let mut entries = vec![];
+    match &msg.members {
+        Some(Members::MembersWithShares(m)) => for member in &m.members {
+            let name = member_name(&member.name);
+            let address = base_address(&member.address);
+            match &member.member_status {
+                Some(member_with_shares::MemberStatus::NewOrExistingMember(n)) => {
+                    let date = match &n.member_status {
+                        Some(new_or_existing_member::MemberStatus::ExistingMemberDateRegistered(d)) =>
+                            proto_date(&Some(d.clone())).unwrap_or(today),
+                        _ => today
+                    };
+                    entries.push(Entry {
+                        register: schema::RegisterType::Members,
+                        entry_key: name_key(&name),
+                        entry_date: date,
+                        removal: false,
+                        details: vec![
+                            ("name", name.clone()),
+                            ("address", address),
+                            ("holding", holding(&n.shares_or_stock_held)),
+                        ],
+                        personal: vec![],
+                    });
+                }
+                Some(member_with_shares::MemberStatus::CeasedToBeMember(c)) => entries.push(ceased_member(&name, c, today)),
+                None => {}
+            }
+            for transfer in &member.transfers {
+                let share = transfer.share.clone().unwrap_or_default();
+                entries.push(Entry {
+                    register: schema::RegisterType::Transfers,
+                    entry_key: name_key(&name),
+                    entry_date: proto_date(&transfer.transfer_date).unwrap_or(today),
+                    removal: false,
+                    details: vec![
+                        ("member", name.clone()),
+                        ("share_class", share.share_class),
+                        ("num_shares", share.num_shares.to_string()),
+                        ("share_reference", share.share_reference),
+                    ],
+                    personal: vec![],
+                });
+            }
+            for allotment in &member.allotments {
+                let share = allotment.share.clone().unwrap_or_default();
+                entries.push(Entry {
+                    register: schema::RegisterType::Allotments,
+                    entry_key: name_key(&name),
+                    entry_date: proto_date(&allotment.allotment_date).unwrap_or(today),
+                    removal: false,
+                    details: vec![
+                        ("allottee", name.clone()),
+                        ("share_class", share.share_class),
+                        ("num_shares", share.num_shares.to_string()),
+                    ],
+                    personal: vec![],
+                });
+            }
+        },
+        Some(Members::MembersWithoutShares(m)) => for member in &m.members {
+            let company_member = member.member.clone().unwrap_or_default();
+            let name = company_member.name.as_ref().map(|n| member_name(std::slice::from_ref(n))).unwrap_or_default();
+            let entry = |date| Entry {
+                register: schema::RegisterType::Members,
+                entry_key: name_key(&name),
+                entry_date: date,
+                removal: false,
+                details: vec![
+                    ("name", name.clone()),
+                    ("address", base_address(&company_member.address)),
+                    ("holding", company_member.class.clone()),
+                ],
+                personal: vec![],
+            };
+            match &member.member_status {
+                Some(member_without_shares::MemberStatus::NewMember(_)) => entries.push(entry(today)),
+                Some(member_without_shares::MemberStatus::ExistingMemberDateRegistered(d)) =>
+                    entries.push(entry(proto_date(&Some(d.clone())).unwrap_or(today))),
+                Some(member_without_shares::MemberStatus::CeasedToBeMember(c)) => entries.push(ceased_member(&name, c, today)),
+                None => {}
+            }
+        },
+        None => {}
+    }
+    entries
+}
+
+pub fn charge_registration(msg: &ch_ewf_grpc::charge_registration::ChargeRegistration) -> Option<Entry> {
+    let date = proto_date(&msg.creation_date).or_else(|| proto_date(&msg.property_acquired_date))?;
+    let mut persons_entitled = msg.persons_entitled.join(", ");
+    if msg.additional_persons_entitled {
+        persons_entitled.push_str(" and others");
+    }
+
+    Some(Entry {
+        register: schema::RegisterType::Charges,
+        entry_key: String::new(),
+        entry_date: date,
+        removal: false,
+        details: vec![
+            ("persons_entitled", persons_entitled),
+            ("description", msg.charge_description.clone()),
+            ("fixed_charge", yes_no(msg.fixed_charge)),
+            ("floating_charge", match ch_ewf_grpc::charge_registration::FloatingCharge::from_i32(msg.floating_charge) {
+                Some(ch_ewf_grpc::charge_registration::FloatingCharge::CoversAll) => "Covers all property".to_string(),
+                Some(ch_ewf_grpc::charge_registration::FloatingCharge::DoesNotCoverAll) => "Yes".to_string(),
+                _ => "No".to_string()
+            }),
+            ("negative_pledge", yes_no(msg.negative_pledge)),
+        ],
+        personal: vec![],
+    })
+}
+
+pub struct RegisterRow {
+    pub entry: models::RegisterEntry,
+    pub ceased: Option<chrono::NaiveDate>,
+    /// Charge codes are only assigned once a charge is registered
+    pub charge_code: Option<String>,
+    /// The entry's personal details once decrypted
+    pub personal: serde_json::Value,
+}
+
+impl RegisterRow {
+    fn detail(&self, key: &str) -> String {
+        if key == "charge_code" {
+            return self.charge_code.clone().unwrap_or_default();
+        }
+        self.entry.details.get(key)
+            .or_else(|| self.personal.get(key))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+/// Loads the entries of a register as they stood at the end of a given date, counting only
+/// changes made by accepted submissions
+pub fn load_register(
+    conn: &diesel::pg::PgConnection, company_number: String, register: schema::RegisterType,
+    as_at: chrono::NaiveDate, include_ceased: bool,
+) -> QueryResult<Vec<RegisterRow>> {
+    let rows: Vec<(models::RegisterEntry, schema::Status, Option<String>)> = schema::register_entries::table
+        .inner_join(schema::submissions::table)
+        .filter(schema::register_entries::dsl::company_number.eq(company_number))
+        .filter(schema::register_entries::dsl::register.eq(register))
+        .filter(schema::register_entries::dsl::entry_date.le(as_at))
+        .order_by(schema::register_entries::dsl::entry_date.asc())
+        .select((
+            schema::register_entries::all_columns, schema::submissions::dsl::status, schema::submissions::dsl::charge_code,
+        ))
+        .load(conn)?;
+
+    Ok(register_rows(rows, include_ceased))
+}
+
+/// Matches entries with their removals, leaving out changes by submissions that weren't accepted
+fn register_rows(
+    rows: Vec<(models::RegisterEntry, schema::Status, Option<String>)>, include_ceased: bool,
+) -> Vec<RegisterRow> {
+    let (removals, entries): (Vec<_>, Vec<_>) = rows.into_iter()
+        .filter(|(_, status, _)| status == &schema::Status::Accepted)
+        .map(|(e, _, charge_code)| (e, charge_code))
+        .partition(|(e, _)| e.removal);
+    entries.into_iter().filter_map(|(entry, charge_code)| {
+        // An entry ends at the first removal of the same person on or after it was made
+        let ceased = removals.iter()
+            .filter(|(r, _)| r.entry_key == entry.entry_key && r.entry_date >= entry.entry_date)
+            .map(|(r, _)| r.entry_date)
+            .min();
+        if ceased.is_some() && !include_ceased {
+            return None;
+        }
+        Some(RegisterRow {
+            entry,
+            ceased,
+            charge_code,
+            personal: serde_json::Value::Null,
+        })
+    }).collect()
+}
+
+pub fn to_proto(register: schema::RegisterType, rows: Vec<RegisterRow>) -> ch_ewf_grpc::registers::RegisterResponse {
+    let to_proto_date = |d: chrono::NaiveDate| chrono_to_proto(Some(chrono::DateTime::<chrono::Utc>::from_utc(d.and_hms(0, 0, 0), chrono::Utc)));
+    ch_ewf_grpc::registers::RegisterResponse {
+        columns: columns(register).iter().map(|(key, heading)| ch_ewf_grpc::registers::RegisterColumn {
+            key: key.to_string(),
+            heading: heading.to_string(),
+        }).collect(),
+        entries: rows.into_iter().map(|r| ch_ewf_grpc::registers::RegisterEntry {
+            entry_id: r.entry.id.to_string(),
+            submission_id: r.entry.submission_id.to_string(),
+            entry_date: to_proto_date(r.entry.entry_date),
+            ceased_date: r.ceased.and_then(to_proto_date),
+            details: columns(register).iter()
+                .map(|(key, _)| (key.to_string(), r.detail(key)))
+                .filter(|(_, v)| !v.is_empty())
+                .collect(),
+        }).collect(),
+    }
+}
+
+fn table(register: schema::RegisterType, rows: &[RegisterRow]) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    let (entry_heading, ceased_heading) = date_headings(register);
+    let mut headings = vec![entry_heading];
+    headings.extend(columns(register).iter().map(|(_, h)| *h));
+    headings.extend(ceased_heading);
+
+    let rows = rows.iter().map(|r| {
+        let mut row = vec![r.entry.entry_date.format("%d/%m/%Y").to_string()];
+        row.extend(columns(register).iter().map(|(key, _)| r.detail(key)));
+        if ceased_heading.is_some() {
+            row.push(r.ceased.map(|d| d.format("%d/%m/%Y").to_string()).unwrap_or_default());
+        }
+        row
+    }).collect();
+    (headings, rows)
+}
+
+pub fn to_csv(register: schema::RegisterType, rows: &[RegisterRow]) -> Result<Vec<u8>, String> {
+    let (headings, rows) = table(register, rows);
+    let mut writer = csv::Writer::from_writer(vec![]);
+    if let Err(err) = writer.write_record(&headings) {
+        return Err(format!("Unable to write CSV: {}", err));
+    }
+    for row in rows {
+        if let Err(err) = writer.write_record(&row) {
+            return Err(format!("Unable to write CSV: {}", err));
+        }
+    }
+    writer.into_inner().map_err(|err| format!("Unable to write CSV: {}", err))
+}
+
+pub fn to_pdf(
+    register: schema::RegisterType, company_number: &str, as_at: chrono::NaiveDate, rows: &[RegisterRow],
+) -> Result<Vec<u8>, String> {
+    let title = format!("{} - {}", register_name(register), company_number);
+    let mut doc = super::pdf::Document::new(&title, true)?;
+    doc.heading(&title);
+    doc.paragraph(&format!(
+        "As at {}, produced {}", as_at.format("%d/%m/%Y"), chrono::Utc::now().format("%d/%m/%Y %H:%M UTC")
+    ));
+    let (headings, rows) = table(register, rows);
+    if rows.is_empty() {
+        doc.paragraph("There are no entries in this register.");
+    } else {
+        doc.table(&headings, &rows);
+    }
+    doc.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn timestamp(year: i32, month: u32, day: u32) -> Option<prost_types::Timestamp> {
+        chrono_to_proto(Some(chrono::Utc.ymd(year, month, day).and_hms(0, 0, 0)))
+    }
+
+    fn name(forename: &str, surname: &str) -> Option<ch_ewf_grpc::base_types::PersonName> {
+        Some(ch_ewf_grpc::base_types::PersonName {
+            forenames: vec![forename.to_string()],
+            surname: surname.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn director_appointed(surname: &str, born: (i32, u32), date: (i32, u32, u32)) -> Entry {
+        use ch_ewf_grpc::officer_appointment::{director, officer_appointment::Appointment};
+        officer_appointment(&ch_ewf_grpc::officer_appointment::OfficerAppointment {
+            appointment_date: timestamp(date.0, date.1, date.2),
+            appointment: Some(Appointment::Director(ch_ewf_grpc::officer_appointment::Director {
+                director: Some(director::Director::Person(ch_ewf_grpc::base_types::DirectorPerson {
+                    person: name("Jane", surname),
+                    date_of_birth: timestamp(born.0, born.1, 15),
+                    ..Default::default()
+                })),
+            })),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    fn director_resigned(surname: &str, born: (i32, u32), date: (i32, u32, u32)) -> Entry {
+        use ch_ewf_grpc::officer_resignation::{director, officer_resignation::Resignation};
+        officer_resignation(&ch_ewf_grpc::officer_resignation::OfficerResignation {
+            resignation_date: timestamp(date.0, date.1, date.2),
+            resignation: Some(Resignation::Director(ch_ewf_grpc::officer_resignation::Director {
+                // Only the month and year of birth are used to tell people apart
+                director: Some(director::Director::Person(ch_ewf_grpc::officer_resignation::Person {
+                    person: name("Jane", surname),
+                    date_of_birth: timestamp(born.0, born.1, 1),
+                })),
+            })),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    fn secretary_appointed(surname: &str, date: (i32, u32, u32)) -> Entry {
+        use ch_ewf_grpc::officer_appointment::{officer_appointment::Appointment, secretary};
+        officer_appointment(&ch_ewf_grpc::officer_appointment::OfficerAppointment {
+            appointment_date: timestamp(date.0, date.1, date.2),
+            appointment: Some(Appointment::Secretary(ch_ewf_grpc::officer_appointment::Secretary {
+                secretary: Some(secretary::Secretary::Person(ch_ewf_grpc::base_types::SecretaryPerson {
+                    person: name("Jane", surname),
+                    ..Default::default()
+                })),
+            })),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    fn secretary_resigned(surname: &str, date: (i32, u32, u32)) -> Entry {
+        use ch_ewf_grpc::officer_resignation::{officer_resignation::Resignation, secretary};
+        officer_resignation(&ch_ewf_grpc::officer_resignation::OfficerResignation {
+            resignation_date: timestamp(date.0, date.1, date.2),
+            resignation: Some(Resignation::Secretary(ch_ewf_grpc::officer_resignation::Secretary {
+                secretary: Some(secretary::Secretary::Person(name("Jane", surname).unwrap())),
+            })),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    fn row(entry: Entry, status: schema::Status) -> (models::RegisterEntry, schema::Status, Option<String>) {
+        (entry.into_model(uuid::Uuid::new_v4(), "EW00000001".to_string(), None), status, None)
+    }
+
+    fn ceased(rows: &[RegisterRow]) -> Vec<(String, Option<chrono::NaiveDate>)> {
+        rows.iter().map(|r| (r.entry.entry_key.clone(), r.ceased)).collect()
+    }
+
+    #[test]
+    fn directors_matched_by_name_and_birth_month() {
+        let rows = register_rows(vec![
+            row(director_appointed("Smith", (1980, 5), (2020, 1, 1)), schema::Status::Accepted),
+            row(director_appointed("Smith", (1990, 7), (2020, 1, 1)), schema::Status::Accepted),
+            row(director_resigned("SMITH", (1990, 7), (2021, 3, 1)), schema::Status::Accepted),
+        ], true);
+        assert_eq!(ceased(&rows), vec![
+            ("jane smith|1980-05".to_string(), None),
+            ("jane smith|1990-07".to_string(), Some(chrono::NaiveDate::from_ymd(2021, 3, 1))),
+        ]);
+    }
+
+    #[test]
+    fn secretaries_matched_by_name() {
+        let rows = register_rows(vec![
+            row(secretary_appointed("Smith", (2020, 1, 1)), schema::Status::Accepted),
+            row(secretary_appointed("Jones", (2020, 1, 1)), schema::Status::Accepted),
+            row(secretary_resigned("smith", (2021, 3, 1)), schema::Status::Accepted),
+        ], false);
+        assert_eq!(ceased(&rows), vec![("jane jones".to_string(), None)]);
+    }
+
+    #[test]
+    fn removals_only_end_later_entries() {
+        // Reappointed after resigning
+        let rows = register_rows(vec![
+            row(secretary_appointed("Smith", (2019, 1, 1)), schema::Status::Accepted),
+            row(secretary_resigned("Smith", (2020, 1, 1)), schema::Status::Accepted),
+            row(secretary_appointed("Smith", (2021, 1, 1)), schema::Status::Accepted),
+        ], true);
+        assert_eq!(ceased(&rows), vec![
+            ("jane smith".to_string(), Some(chrono::NaiveDate::from_ymd(2020, 1, 1))),
+            ("jane smith".to_string(), None),
+        ]);
+    }
+
+    #[test]
+    fn only_accepted_changes_count() {
+        let rows = register_rows(vec![
+            row(secretary_appointed("Smith", (2020, 1, 1)), schema::Status::Accepted),
+            row(secretary_appointed("Jones", (2020, 1, 1)), schema::Status::Rejected),
+            row(secretary_appointed("Brown", (2020, 1, 1)), schema::Status::Pending),
+            row(secretary_resigned("Smith", (2021, 1, 1)), schema::Status::Rejected),
+            row(secretary_resigned("Smith", (2021, 2, 1)), schema::Status::Unsent),
+        ], true);
+        assert_eq!(ceased(&rows), vec![("jane smith".to_string(), None)]);
+    }
+
+    #[test]
+    fn subscribers_entered_as_members() {
+        use ch_ewf_grpc::company_incorporation::{person, Allotment, Person, Subscriber};
+        let msg = ch_ewf_grpc::company_incorporation::CompanyIncorporation {
+            subscribers: vec![Subscriber {
+                person: Some(Person {
+                    name: Some(person::Name::Person(ch_ewf_grpc::base_types::Person {
+                        forename: "Jane".to_string(),
+                        surname: "Smith".to_string(),
+                    })),
+                    ..Default::default()
+                }),
+                allotments: vec![
+                    Allotment {
+                        share_class: "ORDINARY".to_string(),
+                        num_shares: 3.0,
+                        amount_paid_due_per_share: 0.1,
+                        ..Default::default()
+                    },
+                    Allotment {
+                        share_class: "PREFERENCE".to_string(),
+                        num_shares: 10.0,
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let entries = company_incorporation(&msg, chrono::NaiveDate::from_ymd(2020, 1, 1));
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].register == schema::RegisterType::Members);
+        assert_eq!(entries[0].details, vec![
+            ("name", "Jane Smith".to_string()),
+            ("address", String::new()),
+            ("holding", "3 ORDINARY (paid up 0.3); 10 PREFERENCE (paid up 0)".to_string()),
+        ]);
+
+        // Ceasing to be a member is recorded by name
+        let removal = ceased_member("jane  smith", &ch_ewf_grpc::members_register_update::CeasedToBeMember {
+            status: Some(ch_ewf_grpc::members_register_update::ceased_to_be_member::Status::DateCeased(
+                timestamp(2022, 1, 1).unwrap()
+            )),
+            ..Default::default()
+        }, chrono::NaiveDate::from_ymd(2023, 1, 1));
+        let mut entries = entries;
+        let rows = register_rows(vec![
+            row(entries.remove(0), schema::Status::Accepted),
+            row(removal, schema::Status::Accepted),
+        ], true);
+        assert_eq!(ceased(&rows), vec![("jane smith".to_string(), Some(chrono::NaiveDate::from_ymd(2022, 1, 1)))]);
+    }
+}
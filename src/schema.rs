@@ -1,10 +1,57 @@
-#[derive(DbEnum, Serialize, Deserialize, Clone, Debug)]
+#[derive(DbEnum, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum Status {
     Pending,
     Accepted,
     Rejected,
     Parked,
-    InternalFailure
+    InternalFailure,
+    Unsent
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompanyType {
+    EnglandAndWales,
+    Scotland,
+    NorthernIreland,
+    Ireland,
+    LlpEnglandAndWales,
+    LlpScotland,
+    LlpNorthernIreland,
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterType {
+    Directors,
+    Secretaries,
+    Members,
+    Pscs,
+    Allotments,
+    Transfers,
+    Charges,
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareMovementType {
+    Allotment,
+    Transfer,
+    Cancellation,
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncorporationWorkflowState {
+    Draft,
+    Submitted,
+    Rejected,
+    Accepted,
+    Completed,
+}
+
+#[derive(DbEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BulkItemState {
+    Pending,
+    Submitting,
+    Submitted,
+    Failed,
 }
 
 table! {
@@ -22,6 +69,9 @@ table! {
         incorporation_date -> Nullable<Date>,
         authentication_code -> Nullable<Varchar>,
         charge_code -> Nullable<Varchar>,
+        form_type -> Nullable<Varchar>,
+        authentication_code_encrypted -> Nullable<Bytea>,
+        document_request_key -> Nullable<Varchar>,
     }
 }
 
@@ -44,14 +94,249 @@ table! {
         document_id -> Varchar,
         document_filename -> Varchar,
         storage_filename -> Varchar,
+        encrypted -> Bool,
+        content_hash -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    company_snapshots (id) {
+        id -> Uuid,
+        company_number -> Varchar,
+        company_name -> Varchar,
+        transaction_id -> Varchar,
+        made_up_date -> Nullable<Date>,
+        snapshot_timestamp -> Timestamp,
+        data -> Bytea,
+        data_encrypted -> Bool,
+    }
+}
+
+table! {
+    company_snapshot_officers (id) {
+        id -> Uuid,
+        snapshot_id -> Uuid,
+        role -> Varchar,
+        name -> Varchar,
+        corporate -> Bool,
+        date_of_birth -> Nullable<Date>,
+        appointment_date -> Nullable<Date>,
+        resignation_date -> Nullable<Date>,
+        designated -> Bool,
+    }
+}
+
+table! {
+    company_snapshot_pscs (id) {
+        id -> Uuid,
+        snapshot_id -> Uuid,
+        kind -> Varchar,
+        name -> Varchar,
+        notification_date -> Nullable<Date>,
+        cessation_date -> Nullable<Date>,
+        nature_of_controls -> Array<Varchar>,
+    }
+}
+
+table! {
+    company_snapshot_shareholdings (id) {
+        id -> Uuid,
+        snapshot_id -> Uuid,
+        share_class -> Varchar,
+        shareholders -> Varchar,
+        number_held -> Double,
+    }
+}
+
+table! {
+    company_snapshot_sic_codes (snapshot_id, sic_code) {
+        snapshot_id -> Uuid,
+        sic_code -> Varchar,
+    }
+}
+
+table! {
+    company_snapshot_registered_offices (snapshot_id) {
+        snapshot_id -> Uuid,
+        premise -> Varchar,
+        street -> Varchar,
+        thoroughfare -> Varchar,
+        post_town -> Varchar,
+        county -> Varchar,
+        country -> Varchar,
+        postcode -> Varchar,
+        care_of_name -> Varchar,
+        po_box -> Varchar,
+    }
+}
+
+table! {
+    company_snapshot_capital (id) {
+        id -> Uuid,
+        snapshot_id -> Uuid,
+        currency -> Varchar,
+        share_class -> Varchar,
+        prescribed_particulars -> Varchar,
+        num_shares -> Double,
+        aggregate_nominal_value -> Double,
+        total_amount_unpaid -> Double,
+    }
+}
+
+table! {
+    tracked_companies (company_number) {
+        company_number -> Varchar,
+        company_name -> Varchar,
+        public_company -> Bool,
+        incorporation_date -> Date,
+        accounting_period_end -> Date,
+        last_accounts_made_up_to -> Nullable<Date>,
+        confirmation_statement_made_up_to -> Nullable<Date>,
+        confirmation_statement_due -> Nullable<Date>,
+    }
+}
+
+table! {
+    accounting_reference_date_changes (submission_id) {
+        submission_id -> Uuid,
+        company_number -> Varchar,
+        current_period_end -> Date,
+        new_period_end -> Date,
+        shortened -> Bool,
+        notice_date -> Date,
+    }
+}
+
+table! {
+    managed_companies (id) {
+        id -> Uuid,
+        company_number -> Integer,
+        company_type -> crate::schema::CompanyTypeMapping,
+        company_name -> Varchar,
+        authentication_code -> Bytea,
+        tags -> Array<Varchar>,
+    }
+}
+
+table! {
+    register_entries (id) {
+        id -> Uuid,
+        submission_id -> Uuid,
+        company_number -> Varchar,
+        register -> crate::schema::RegisterTypeMapping,
+        entry_key -> Varchar,
+        entry_date -> Date,
+        removal -> Bool,
+        details -> Jsonb,
+        personal_details -> Nullable<Bytea>,
+        personal_details_encrypted -> Bool,
+    }
+}
+
+table! {
+    share_classes (id) {
+        id -> Uuid,
+        company_number -> Varchar,
+        share_class -> Varchar,
+        currency -> Varchar,
+        nominal_value -> Double,
+        prescribed_particulars -> Varchar,
+    }
+}
+
+table! {
+    share_movements (id) {
+        id -> Uuid,
+        share_class_id -> Uuid,
+        movement -> crate::schema::ShareMovementTypeMapping,
+        member_name -> Varchar,
+        from_member_name -> Nullable<Varchar>,
+        num_shares -> Double,
+        amount_paid_per_share -> Double,
+        amount_unpaid_per_share -> Double,
+        movement_date -> Date,
+    }
+}
+
+table! {
+    incorporation_workflows (id) {
+        id -> Uuid,
+        state -> crate::schema::IncorporationWorkflowStateMapping,
+        draft -> Bytea,
+        draft_encrypted -> Bool,
+        e_reminder_emails -> Array<Varchar>,
+        submission_id -> Nullable<Uuid>,
+        company_number -> Nullable<Varchar>,
+        incorporation_date -> Nullable<Date>,
+        authentication_code -> Nullable<Bytea>,
+        authentication_code_encrypted -> Bool,
+        certificate_document_id -> Nullable<Uuid>,
+        managed_company_id -> Nullable<Uuid>,
+        e_reminders_set -> Bool,
+        registers_recorded -> Bool,
+        follow_up_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    bulk_submissions (id) {
+        id -> Uuid,
+        form_type -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    bulk_submission_items (id) {
+        id -> Uuid,
+        bulk_submission_id -> Uuid,
+        position -> Integer,
+        company_number -> Varchar,
+        state -> crate::schema::BulkItemStateMapping,
+        request -> Nullable<Bytea>,
+        request_encrypted -> Bool,
+        submission_id -> Nullable<Uuid>,
+        error -> Nullable<Varchar>,
+        updated_at -> Timestamp,
     }
 }
 
 joinable!(submission_rejections -> submissions (submission_id));
+joinable!(company_snapshot_officers -> company_snapshots (snapshot_id));
+joinable!(company_snapshot_pscs -> company_snapshots (snapshot_id));
+joinable!(company_snapshot_shareholdings -> company_snapshots (snapshot_id));
+joinable!(company_snapshot_sic_codes -> company_snapshots (snapshot_id));
+joinable!(company_snapshot_registered_offices -> company_snapshots (snapshot_id));
+joinable!(company_snapshot_capital -> company_snapshots (snapshot_id));
 joinable!(submissions -> documents (document_id));
+joinable!(accounting_reference_date_changes -> submissions (submission_id));
+joinable!(register_entries -> submissions (submission_id));
+joinable!(share_movements -> share_classes (share_class_id));
+joinable!(incorporation_workflows -> submissions (submission_id));
+joinable!(incorporation_workflows -> managed_companies (managed_company_id));
+joinable!(bulk_submission_items -> bulk_submissions (bulk_submission_id));
+joinable!(bulk_submission_items -> submissions (submission_id));
 
 allow_tables_to_appear_in_same_query!(
     submissions,
     submission_rejections,
     documents,
+    company_snapshots,
+    company_snapshot_officers,
+    company_snapshot_pscs,
+    company_snapshot_shareholdings,
+    company_snapshot_sic_codes,
+    company_snapshot_registered_offices,
+    company_snapshot_capital,
+    tracked_companies,
+    accounting_reference_date_changes,
+    managed_companies,
+    register_entries,
+    share_classes,
+    share_movements,
+    incorporation_workflows,
+    bulk_submissions,
+    bulk_submission_items,
 );
\ No newline at end of file
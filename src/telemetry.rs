@@ -0,0 +1,77 @@
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[derive(Debug, Deserialize)]
+pub struct TracingConfig {
+    #[serde(default = "default_otlp_endpoint")]
+    otlp_endpoint: String,
+    #[serde(default = "default_service_name")]
+    service_name: String,
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+
+fn default_service_name() -> String {
+    "ch-ewf".to_string()
+}
+
+pub fn init(config: &TracingConfig) {
+    opentelemetry::global::set_text_map_propagator(
+        opentelemetry::sdk::propagation::TraceContextPropagator::new()
+    );
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.otlp_endpoint)
+        )
+        .with_trace_config(opentelemetry::sdk::trace::config().with_resource(
+            opentelemetry::sdk::Resource::new(vec![
+                opentelemetry::KeyValue::new("service.name", config.service_name.clone())
+            ])
+        ))
+        .install_batch(opentelemetry::runtime::Tokio)
+        .expect("Unable to setup OTLP exporter");
+
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer));
+    tracing::subscriber::set_global_default(subscriber).expect("Unable to setup tracing");
+}
+
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+struct HeaderExtractor<'a>(&'a hyper::HeaderMap);
+
+impl<'a> opentelemetry::propagation::Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Creates the root span for an incoming gRPC request, continuing any W3C trace context
+/// sent by the caller in the request metadata
+pub fn grpc_request_span(req: &hyper::Request<()>) -> tracing::Span {
+    let span = tracing::info_span!(
+        "grpc_request",
+        otel.name = %req.uri().path(),
+        otel.kind = "server",
+        rpc.system = "grpc",
+        submission_id = tracing::field::Empty,
+        transaction_id = tracing::field::Empty,
+    );
+    let parent_context = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req.headers()))
+    });
+    span.set_parent(parent_context);
+    span
+}